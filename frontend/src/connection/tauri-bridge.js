@@ -3,6 +3,10 @@
  *
  * - listen('song-info') 订阅后端广播的 SongInfo（零回环开销，Tauri 主路径）
  * - invoke('set_background_state', ...) 通知后端窗口可见性以降频轮询
+ * - invoke('play_pause' / 'next_track' / 'previous_track' / 'seek_to') 控制播放
+ * - invoke('seek_to_line' / 'seek_to_lyric_time') 跳转到歌词行，返回跳转是否生效
+ * - invoke('list_lyric_candidates' / 'pin_lyrics' / 'unpin_lyrics') 列出歌词候选并为当前歌曲固定/取消固定
+ * - invoke('set_lyrics_offset' / 'adjust_lyrics_offset') 设置/增减当前歌曲的歌词偏移，返回保存后的偏移
  */
 
 /**
@@ -28,6 +32,17 @@ export function notifyBackgroundState(isBackground) {
     window.__TAURI__.core.invoke('set_background_state', { isBackground });
 }
 
+/**
+ * 通过 Tauri 命令控制播放。
+ * @param {'play_pause' | 'next_track' | 'previous_track' | 'seek_to' | 'seek_to_line' | 'seek_to_lyric_time' | 'set_lyrics_offset' | 'adjust_lyrics_offset'} command
+ * @param {{positionMs?: number, line?: number, timeMs?: number, offsetMs?: number, deltaMs?: number}} [args] - seek_to 需要 positionMs，seek_to_line 需要 line
+ * @returns {Promise<any>} seek_to_line / seek_to_lyric_time 返回 {requested_ms, honored, observed_ms}，歌词偏移命令返回保存后的偏移
+ */
+export function invokeControl(command, args = {}) {
+    if (!window.__TAURI__) return Promise.reject(new Error('not in tauri'));
+    return window.__TAURI__.core.invoke(command, args);
+}
+
 /** 是否运行在 Tauri 环境中 */
 export function isTauri() {
     return !!window.__TAURI__;
@@ -11,6 +11,37 @@ import {
     setAnimFrame, animFrame
 } from '../state/state.js';
 
+/** 客户端使用的推送协议版本：v2 为 track（静态内容）+ tick（进度） */
+const PROTOCOL_VERSION = 2;
+
+/**
+ * 将 v2 的 track/tick 合并为 SongInfo 形状，供 update-handler 沿用。
+ * @param {any} track
+ * @param {any} tick
+ */
+function mergeTrackTick(track, tick) {
+    return {
+        source_app: track.source_app,
+        title: track.title,
+        artist: track.artist,
+        album: track.album,
+        lyrics: track.lyrics,
+        trans: track.trans,
+        qrc_data: track.qrc_data,
+        album_pic_url: track.cover_url,
+        lyrics_confidence: track.lyrics_confidence,
+        current_time_ms: tick.current_time_ms,
+        total_time_ms: tick.total_time_ms,
+        is_playing: tick.is_playing,
+        server_ts: tick.server_ts,
+        playback_rate: tick.playback_rate,
+        shuffle: tick.shuffle,
+        repeat_mode: tick.repeat_mode,
+        lyrics_offset_ms: tick.lyrics_offset_ms,
+        lyric_time_ms: tick.lyric_time_ms
+    };
+}
+
 /** @param {'connected' | 'connecting' | 'disconnected'} state */
 function updateConnectionUI(state, elStatusDot, elStatusText) {
     elStatusDot.className = 'status-indicator';
@@ -34,8 +65,9 @@ function updateConnectionUI(state, elStatusDot, elStatusText) {
  * @param {(data: any) => void} opts.onMessage - 收到 SongInfo 的回调
  * @param {() => void} opts.onConnected - 连接建立后启动渲染循环
  * @param {() => void} opts.onDisconnected
+ * @param {string | null} [opts.session] - 订阅的会话 id（如 `QQMusic.exe`），为空时跟随活动会话
  */
-export function connect({ port, elStatusDot, elStatusText, onMessage, onConnected, onDisconnected }) {
+export function connect({ port, elStatusDot, elStatusText, onMessage, onConnected, onDisconnected, session = null }) {
     if (ws && (ws.readyState === WebSocket.OPEN || ws.readyState === WebSocket.CONNECTING)) {
         return;
     }
@@ -45,14 +77,17 @@ export function connect({ port, elStatusDot, elStatusText, onMessage, onConnecte
 
     const host = location.hostname || '127.0.0.1';
     const proto = location.protocol === 'https:' ? 'wss' : 'ws';
-    const wsUrl = `${proto}://${host}:${port}/ws`;
+    const query = `?protocol=${PROTOCOL_VERSION}` + (session ? `&session=${encodeURIComponent(session)}` : '');
+    const wsUrl = `${proto}://${host}:${port}/ws${query}`;
+    // 当前连接最近一次收到的 track，tick 与其 track_id 一致时才合并
+    let track = null;
 
     /** @type {WebSocket} */
     let socket;
     try {
         socket = new WebSocket(wsUrl);
     } catch {
-        scheduleReconnect({ port, elStatusDot, elStatusText, onMessage, onConnected, onDisconnected });
+        scheduleReconnect({ port, elStatusDot, elStatusText, onMessage, onConnected, onDisconnected, session });
         return;
     }
     setWs(socket);
@@ -68,7 +103,16 @@ export function connect({ port, elStatusDot, elStatusText, onMessage, onConnecte
         if (ws !== socket) return;
         try {
             const data = JSON.parse(e.data);
-            if (data && typeof data === 'object') onMessage(data);
+            if (!data || typeof data !== 'object') return;
+            if (data.type === 'track') {
+                track = data;
+            } else if (data.type === 'tick') {
+                if (track && track.track_id === data.track_id) onMessage(mergeTrackTick(track, data));
+            } else if (!data.type) {
+                // v1 服务端：完整 SongInfo
+                onMessage(data);
+            }
+            // 其余带 type 的是 hello、控制回执、订阅确认等非 SongInfo 消息
         } catch {}
     };
 
@@ -82,7 +126,7 @@ export function connect({ port, elStatusDot, elStatusText, onMessage, onConnecte
         updateConnectionUI('disconnected', elStatusDot, elStatusText);
         onDisconnected();
         if (!isIntentionalClose) {
-            scheduleReconnect({ port, elStatusDot, elStatusText, onMessage, onConnected, onDisconnected });
+            scheduleReconnect({ port, elStatusDot, elStatusText, onMessage, onConnected, onDisconnected, session });
         }
     };
 }
@@ -102,6 +146,51 @@ function scheduleReconnect(opts) {
     setReconnectTimer(timer);
 }
 
+/**
+ * 通过 WebSocket 发送播放控制命令，回执为 {type: 'control_result', ok, error?}
+ * @param {{command: 'play_pause' | 'next' | 'previous' | 'seek', position_ms?: number}} command
+ * @returns {boolean} 是否已发送
+ */
+export function sendControl(command) {
+    if (!ws || ws.readyState !== WebSocket.OPEN) return false;
+    ws.send(JSON.stringify(command));
+    return true;
+}
+
+/**
+ * 通过 HTTP 请求跳转到歌词行，回执为 {ok, error?, requested_ms?, honored?, observed_ms?}
+ * @param {number} port
+ * @param {{line: number} | {time_ms: number}} target
+ * @returns {Promise<any>}
+ */
+export async function requestSeekLyric(port, target) {
+    const host = location.hostname || '127.0.0.1';
+    const proto = location.protocol === 'https:' ? 'https' : 'http';
+    const resp = await fetch(`${proto}://${host}:${port}/api/control/seek_lyric`, {
+        method: 'POST',
+        headers: { 'Content-Type': 'application/json' },
+        body: JSON.stringify(target)
+    });
+    return resp.json();
+}
+
+/**
+ * 通过 HTTP 调整当前歌曲的歌词偏移，回执为 {ok, error?, offset_ms?}
+ * @param {number} port
+ * @param {{offset_ms: number} | {delta_ms: number}} change
+ * @returns {Promise<any>}
+ */
+export async function requestLyricsOffset(port, change) {
+    const host = location.hostname || '127.0.0.1';
+    const proto = location.protocol === 'https:' ? 'https' : 'http';
+    const resp = await fetch(`${proto}://${host}:${port}/api/lyrics/offset`, {
+        method: 'POST',
+        headers: { 'Content-Type': 'application/json' },
+        body: JSON.stringify(change)
+    });
+    return resp.json();
+}
+
 /** 主动断开，不再重连 */
 export function disconnect() {
     setIsIntentionalClose(true);
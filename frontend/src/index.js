@@ -14,8 +14,8 @@
 import { $, bindElements } from './utils/dom.js';
 import { loadFrontendConfig } from './config/frontend-config.js';
 import { fetchBackendConfig, getDefaultBackendConfig } from './config/backend-config.js';
-import { connect, disconnect, updateConnectionUI } from './connection/ws.js';
-import { listenSongInfo, notifyBackgroundState, isTauri } from './connection/tauri-bridge.js';
+import { connect, disconnect, updateConnectionUI, requestSeekLyric, requestLyricsOffset } from './connection/ws.js';
+import { listenSongInfo, notifyBackgroundState, isTauri, invokeControl } from './connection/tauri-bridge.js';
 import {
     animFrame, setAnimFrame, setLastSyncMs,
     setIsIntentionalClose
@@ -53,6 +53,10 @@ const els = bindElements({
     valOffset: 'val-offset',
     btnOffsetDec: 'btn-offset-dec',
     btnOffsetInc: 'btn-offset-inc',
+    valSongOffset: 'val-song-offset',
+    btnSongOffsetDec: 'btn-song-offset-dec',
+    btnSongOffsetReset: 'btn-song-offset-reset',
+    btnSongOffsetInc: 'btn-song-offset-inc',
     cfgInterval: 'cfg-interval',
     valInterval: 'val-interval',
     cfgPort: 'cfg-port',
@@ -90,6 +94,7 @@ const lastSyncMsRef = { val: 0 };
 function onMessage(data) {
     const t0 = performance.now();
     handleSongInfoUpdate(els, data, frontConfig, backConfig, lastSyncMsRef);
+    els.valSongOffset.textContent = `${data.lyrics_offset_ms || 0}ms`;
     lastSyncMsRef.val = performance.now() - t0;
     setLastSyncMs(lastSyncMsRef.val);
 }
@@ -119,6 +124,48 @@ function onFrontConfigChange() {
     });
 }
 
+/* ============================================================
+ * 歌词行点击跳转（事件委托，歌词 DOM 切歌时会整体重建）
+ * ============================================================ */
+
+function onLyricClick(event) {
+    const lineEl = /** @type {HTMLElement} */ (event.target).closest('.lyric-line');
+    if (!lineEl || lineEl.dataset.index === undefined) return;
+    const line = Number(lineEl.dataset.index);
+    const request = isTauri()
+        ? invokeControl('seek_to_line', { line })
+        : requestSeekLyric(backConfig.port, { line });
+    request
+        .then((result) => {
+            if (result && result.honored === false) {
+                console.warn('Seek to lyric line was not honored by the player:', result);
+            }
+        })
+        .catch((e) => console.error('Seek to lyric line failed:', e));
+}
+
+/* ============================================================
+ * 本曲歌词偏移：后端保存并随 SongInfo 下发，渲染循环据此修正歌词时间
+ * ============================================================ */
+
+/** @param {{offset_ms: number} | {delta_ms: number}} change */
+function changeSongOffset(change) {
+    let request;
+    if (!isTauri()) {
+        request = requestLyricsOffset(backConfig.port, change).then((result) => {
+            if (!result.ok) throw new Error(result.error);
+            return result.offset_ms;
+        });
+    } else if ('delta_ms' in change) {
+        request = invokeControl('adjust_lyrics_offset', { deltaMs: change.delta_ms });
+    } else {
+        request = invokeControl('set_lyrics_offset', { offsetMs: change.offset_ms });
+    }
+    request
+        .then((offsetMs) => { els.valSongOffset.textContent = `${offsetMs}ms`; })
+        .catch((e) => console.error('Adjust lyric offset failed:', e));
+}
+
 /* ============================================================
  * 启动流程
  * ============================================================ */
@@ -126,7 +173,7 @@ function onFrontConfigChange() {
 // 1. 初始 UI 同步
 syncConfigToUI(els, frontConfig, backConfig);
 
-// 2. 绑定设置面板交互
+// 2. 绑定设置面板与歌词点击交互
 wireUpSettings({
     els,
     configs: { front: frontConfig, back: backConfig },
@@ -138,6 +185,10 @@ wireUpSettings({
         });
     }
 });
+els.lyricsViewport.addEventListener('click', onLyricClick);
+els.btnSongOffsetDec.addEventListener('click', () => changeSongOffset({ delta_ms: -50 }));
+els.btnSongOffsetInc.addEventListener('click', () => changeSongOffset({ delta_ms: 50 }));
+els.btnSongOffsetReset.addEventListener('click', () => changeSongOffset({ offset_ms: 0 }));
 
 // 3. 加载后端配置并启动数据源
 if (isTauri()) {
@@ -159,6 +210,8 @@ if (isTauri()) {
             elStatusText: els.statusText,
             onMessage,
             onConnected: startRendering,
+            // 叠加层可用 ?session=<会话 id> 固定跟随某个应用（见 /api/sessions）
+            session: new URLSearchParams(location.search).get('session'),
             onDisconnected: () => {
                 if (animFrame) { cancelAnimationFrame(animFrame); setAnimFrame(0); }
             }
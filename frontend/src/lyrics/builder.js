@@ -67,6 +67,8 @@ export function buildLyricsArea(viewport, data, transMap, showTranslation) {
         const line = data.qrc_data[idx];
         const lineDiv = document.createElement('div');
         lineDiv.className = 'lyric-line before';
+        // 行号与后端 qrc_data 下标一致，点击跳转时回传
+        lineDiv.dataset.index = String(idx);
 
         // 首尾留白以实现居中滚动
         if (idx === 0) lineDiv.style.marginTop = '180px';
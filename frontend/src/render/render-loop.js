@@ -34,13 +34,14 @@ export function startRenderLoop(elTimeCurrent, elTimeTotal, elProgressFill, elLy
         setAnimFrame(requestAnimationFrame(loop));
         if (!songInfo) return;
 
-        // 前进推算插值时间（含用户配置的音频延迟补偿）
+        // 前进推算插值时间（含用户配置的音频延迟补偿）；变速播放时墙钟流逝与延迟都乘以播放速率
+        const rate = songInfo.playback_rate > 0 ? songInfo.playback_rate : 1;
         let t;
         if (isPlaying && lastSampleLocalTime > 0) {
             const elapsed = performance.now() - lastSampleLocalTime;
-            t = lastSampleProgress + elapsed + cfg.offsetMs;
+            t = lastSampleProgress + (elapsed + cfg.offsetMs) * rate;
         } else {
-            t = lastSampleProgress + cfg.offsetMs;
+            t = lastSampleProgress + cfg.offsetMs * rate;
         }
         t = Math.max(0, t);
 
@@ -57,6 +58,12 @@ export function startRenderLoop(elTimeCurrent, elTimeTotal, elProgressFill, elLy
 
         if (lyricLines.length === 0) return;
 
+        // 歌词时间轴：后端按本曲偏移修正的 lyric_time_ms 与 current_time_ms 同一时刻采样，
+        // 两者之差即歌词相对播放位置的位移；进度条仍显示播放位置
+        if (songInfo.lyric_time_ms != null && songInfo.current_time_ms != null) {
+            t = Math.max(0, t + songInfo.lyric_time_ms - songInfo.current_time_ms);
+        }
+
         let activeIdx = -1;
 
         for (let i = 0; i < lyricLines.length; i++) {
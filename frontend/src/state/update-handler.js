@@ -17,15 +17,37 @@ import {
 import { parseTranslation } from '../lyrics/translation.js';
 import { buildLyricsArea, showLoadingPlaceholder } from '../lyrics/builder.js';
 import { setBgImage } from '../utils/dom.js';
+import { isTauri } from '../connection/tauri-bridge.js';
 
-/** 切歌后已收到几次非切歌更新（用于延迟歌词构建直到进度稳定） */
-let postChangeCount = 0;
+/**
+ * 切歌时刻（performance.now()），用于延迟歌词构建直到进度稳定。
+ * 后端只在状态变化时推送（另有约 1 秒心跳），更新次数不再对应固定时长，因此按时间兜底。
+ */
+let songChangedAt = 0;
+
+/** 切歌后等待 timeline 就绪的最长时间（ms），超时后无论如何构建歌词 */
+const REBUILD_FALLBACK_MS = 1000;
+
+/**
+ * 封面地址补全：后端封面存储的相对地址 `/api/cover/{id}` 指向同步服务。
+ *  - Tauri：webview 的源是 `tauri.localhost`（Windows）或 `tauri://localhost`，同步服务只监听 127.0.0.1
+ *  - 浏览器：页面由同步服务提供，沿用页面自身的主机与端口；本地打开（file://）时退回 127.0.0.1
+ * @param {string} url
+ * @param {number} port - 同步服务端口（backCfg.port）
+ */
+function resolveCoverUrl(url, port) {
+    if (!url || !url.startsWith('/')) return url;
+    if (!isTauri() && location.protocol.startsWith('http') && location.host) {
+        return `${location.protocol}//${location.host}${url}`;
+    }
+    return `http://127.0.0.1:${port}${url}`;
+}
 
 /**
  * @param {Object} els - DOM 元素集合
  * @param {any} data - 后端推送的 SongInfo
  * @param {Object} cfg - 前端偏好（用于 showTranslation）
- * @param {Object} backCfg - 后端配置引用（用于调试显示）
+ * @param {Object} backCfg - 后端配置引用（用于调试显示与封面地址补全）
  * @param {number} lastSyncMsRef - 上次同步耗时（{val: number}，需按引用更新）
  */
 export function handleSongInfoUpdate(els, data, cfg, backCfg, lastSyncMsRef) {
@@ -55,7 +77,7 @@ export function handleSongInfoUpdate(els, data, cfg, backCfg, lastSyncMsRef) {
         setTransMap([]);
         setLastRenderKey('');
         setLastActiveIdx(-1);
-        postChangeCount = 0;
+        songChangedAt = performance.now();
 
         // 3) 清空旧专辑封面，避免上一首封面残留
         setBgImage(els.albumArt, '');
@@ -83,9 +105,10 @@ export function handleSongInfoUpdate(els, data, cfg, backCfg, lastSyncMsRef) {
         resetForSongChange(freshProgress, performance.now());
         rawTimeMs = freshProgress;
     } else {
-        // —— 同首歌的进度同步 ——
+        // —— 同首歌的进度同步（变速播放时本地流逝乘以播放速率，与 render-loop 一致） ——
+        const rate = data.playback_rate > 0 ? data.playback_rate : 1;
         const localEstimate = isPlaying && lastSampleLocalTime > 0
-            ? lastSampleProgress + (performance.now() - lastSampleLocalTime)
+            ? lastSampleProgress + (performance.now() - lastSampleLocalTime) * rate
             : lastSampleProgress;
 
         if (isPlaying !== oldIsPlaying) {
@@ -94,7 +117,7 @@ export function handleSongInfoUpdate(els, data, cfg, backCfg, lastSyncMsRef) {
                 // play → pause: 把暂停瞬间累积的 elapsed 烘焙进基线，
                 // 让 render-loop 的 paused 分支（lastSampleProgress + offset）
                 // 与暂停前最后一帧的 playing 分支显示位置完全连续，无闪回。
-                const displayNow = lastSampleProgress + (performance.now() - lastSampleLocalTime);
+                const displayNow = lastSampleProgress + (performance.now() - lastSampleLocalTime) * rate;
                 setLastSampleProgress(displayNow);
             } else {
                 // pause → play 或异常初始状态: snap 到 SMTC 真实位置，
@@ -134,10 +157,11 @@ export function handleSongInfoUpdate(els, data, cfg, backCfg, lastSyncMsRef) {
 
     // —— 专辑封面 ——
     if (data.album_pic_url) {
-        setBgImage(els.albumArt, data.album_pic_url);
-        const newBg = `url("${data.album_pic_url}")`;
+        const coverUrl = resolveCoverUrl(data.album_pic_url, backCfg.port);
+        setBgImage(els.albumArt, coverUrl);
+        const newBg = `url("${coverUrl}")`;
         if (els.albumBlurBg.style.backgroundImage !== newBg) {
-            setBgImage(els.albumBlurBg, data.album_pic_url);
+            setBgImage(els.albumBlurBg, coverUrl);
         }
         els.albumBlurBg.classList.add('active');
     } else if (!data.title || data.title === 'No music playing' || data.title === 'ERROR') {
@@ -157,20 +181,20 @@ export function handleSongInfoUpdate(els, data, cfg, backCfg, lastSyncMsRef) {
     // 切歌后后端会将 total_time_ms/current_time_ms 归零，直到 SMTC 报告
     // 新歌的 timeline 才恢复非零值。只有 totalMs > 0 才表示 timeline 已就绪，
     // 此时 rawTimeMs 也是真实的播放进度，用 t 构建歌词不会闪回。
-    // postChangeCount >= 20（约1秒）作为兜底，防止异常情况下永远不构建。
+    // 切歌后超过 REBUILD_FALLBACK_MS 作为兜底，防止异常情况下永远不构建。
     const qrcLength = data.qrc_data ? data.qrc_data.length : 0;
     const renderKey = `${data.title}|${data.artist}|${qrcLength}`;
     const needRebuild = renderKey !== lastRenderKey;
     if (!isSongChanged) {
-        postChangeCount++;
-        if (qrcLength > 0 && needRebuild && (totalMs > 0 || postChangeCount >= 20)) {
+        const sinceChangeMs = performance.now() - songChangedAt;
+        if (qrcLength > 0 && needRebuild && (totalMs > 0 || sinceChangeMs >= REBUILD_FALLBACK_MS)) {
             setLastRenderKey(renderKey);
             const newTransMap = parseTranslation(data.trans);
             setTransMap(newTransMap);
             const newLines = buildLyricsArea(els.lyricsViewport, data, newTransMap, cfg.showTranslation);
             setLyricLines(newLines);
             setLastActiveIdx(-1);
-        } else if (qrcLength === 0 && postChangeCount === 20) {
+        } else if (qrcLength === 0 && needRebuild && sinceChangeMs >= REBUILD_FALLBACK_MS) {
             setLastRenderKey(renderKey);
             const newLines = buildLyricsArea(els.lyricsViewport, data, [], cfg.showTranslation);
             setLyricLines(newLines);
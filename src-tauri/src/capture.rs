@@ -0,0 +1,181 @@
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::fs::{File, OpenOptions};
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use std::sync::{Mutex, OnceLock};
+
+use crate::media_source::MediaSample;
+use crate::song_info::RepeatMode;
+
+/// 录制文件中的一条原始采样（NDJSON 每行一条）。
+///
+/// 记录媒体源翻译前的原始时间轴字段，回放时重新经过漂移修正与切歌修正，
+/// 用于在 Linux 上复现用户报告的进度闪烁 / 切歌后歌词陈旧问题。
+#[derive(Debug, Clone, Default, Deserialize, Serialize, PartialEq)]
+pub struct CaptureEntry {
+    /// 采样时刻（Unix 毫秒）
+    pub ts_ms: i64,
+    /// 会话来源（SMTC: SourceAppUserModelId，MPRIS: bus name）
+    #[serde(default)]
+    pub source: String,
+    pub title: String,
+    #[serde(default)]
+    pub artist: String,
+    #[serde(default)]
+    pub album: String,
+    /// 源报告的 Position（毫秒）
+    pub position_ms: u64,
+    /// 源报告的 LastUpdatedTime（Unix 毫秒）
+    #[serde(default)]
+    pub last_updated_ms: Option<i64>,
+    /// 源报告的 EndTime（毫秒）
+    pub end_time_ms: u64,
+    /// 源报告的原始播放状态（如 `Playing` / `Paused` / `Changing`）
+    pub playback_status: String,
+    /// 播放速率 / 随机 / 循环模式；旧录制文件中没有这些字段
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub playback_rate: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub shuffle: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub repeat_mode: Option<RepeatMode>,
+}
+
+impl CaptureEntry {
+    pub fn from_sample(ts_ms: i64, source: &str, sample: &MediaSample, playback_status: &str) -> Self {
+        Self {
+            ts_ms,
+            source: source.to_string(),
+            title: sample.title.clone(),
+            artist: sample.artist.clone(),
+            album: sample.album.clone(),
+            position_ms: sample.position_ms,
+            last_updated_ms: sample.last_updated_ms,
+            end_time_ms: sample.end_time_ms,
+            playback_status: playback_status.to_string(),
+            playback_rate: sample.playback_rate,
+            shuffle: sample.shuffle,
+            repeat_mode: sample.repeat_mode,
+        }
+    }
+
+    /// 还原为媒体源采样（封面不录制）
+    pub fn to_sample(&self) -> MediaSample {
+        MediaSample {
+            title: self.title.clone(),
+            artist: self.artist.clone(),
+            album: self.album.clone(),
+            position_ms: self.position_ms,
+            end_time_ms: self.end_time_ms,
+            last_updated_ms: self.last_updated_ms,
+            is_playing: self.playback_status == "Playing",
+            album_pic_url: String::new(),
+            playback_rate: self.playback_rate,
+            shuffle: self.shuffle,
+            repeat_mode: self.repeat_mode,
+        }
+    }
+}
+
+static RECORDER: OnceLock<Mutex<BufWriter<File>>> = OnceLock::new();
+
+/// 开始录制：之后媒体源产生的每个原始采样都追加写入 `path`
+pub fn start_recording(path: &Path) -> Result<()> {
+    let file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .map_err(|e| anyhow!("无法打开录制文件 {}: {}", path.display(), e))?;
+    RECORDER
+        .set(Mutex::new(BufWriter::new(file)))
+        .map_err(|_| anyhow!("录制已经开始"))
+}
+
+pub fn is_recording() -> bool {
+    RECORDER.get().is_some()
+}
+
+/// 追加一条采样；未开启录制时为空操作。写入失败只打印错误，不影响主循环
+pub fn record(entry: &CaptureEntry) {
+    let Some(recorder) = RECORDER.get() else {
+        return;
+    };
+    let Ok(mut writer) = recorder.lock() else {
+        return;
+    };
+    let result = serde_json::to_string(entry)
+        .map_err(anyhow::Error::from)
+        .and_then(|line| {
+            writeln!(writer, "{}", line)?;
+            writer.flush()?;
+            Ok(())
+        });
+    if let Err(e) = result {
+        eprintln!("[capture] Failed to write sample: {}", e);
+    }
+}
+
+/// 解析 NDJSON 录制内容，空行忽略，按采样时刻排序
+pub fn parse_capture(content: &str) -> Result<Vec<CaptureEntry>> {
+    let mut entries = content
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(idx, line)| {
+            serde_json::from_str::<CaptureEntry>(line)
+                .map_err(|e| anyhow!("录制文件第 {} 行解析失败: {}", idx + 1, e))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    entries.sort_by_key(|e| e.ts_ms);
+    Ok(entries)
+}
+
+pub fn load_capture(path: &Path) -> Result<Vec<CaptureEntry>> {
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| anyhow!("无法读取录制文件 {}: {}", path.display(), e))?;
+    let entries = parse_capture(&content)?;
+    if entries.is_empty() {
+        anyhow::bail!("录制文件 {} 中没有采样", path.display());
+    }
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_entry_round_trip() {
+        let sample = MediaSample {
+            title: "那时雨".to_string(),
+            artist: "徐良".to_string(),
+            position_ms: 12_000,
+            end_time_ms: 200_000,
+            last_updated_ms: Some(1_700_000_000_000),
+            is_playing: true,
+            playback_rate: Some(1.25),
+            repeat_mode: Some(RepeatMode::Track),
+            ..MediaSample::default()
+        };
+        let entry = CaptureEntry::from_sample(1_700_000_000_500, "QQMusic.exe", &sample, "Playing");
+        let line = serde_json::to_string(&entry).unwrap();
+        let parsed = parse_capture(&format!("{}\n\n", line)).unwrap();
+        assert_eq!(parsed, vec![entry]);
+        assert_eq!(parsed[0].to_sample(), sample);
+    }
+
+    /// 非 Playing 状态（Paused / Changing 等）还原为暂停；行号出现在解析错误中
+    #[test]
+    fn test_parse_capture_status_and_errors() {
+        let content = r#"{"ts_ms":2,"title":"B","position_ms":0,"end_time_ms":0,"playback_status":"Changing"}
+{"ts_ms":1,"title":"A","position_ms":0,"end_time_ms":0,"playback_status":"Playing"}"#;
+        let entries = parse_capture(content).unwrap();
+        assert_eq!(entries[0].title, "A");
+        assert!(entries[0].to_sample().is_playing);
+        assert!(!entries[1].to_sample().is_playing);
+
+        let err = parse_capture("{}\nnot json").unwrap_err().to_string();
+        assert!(err.contains("第 1 行"), "{}", err);
+    }
+}
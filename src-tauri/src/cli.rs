@@ -64,6 +64,18 @@ pub struct Cli {
     #[arg(short, long)]
     pub quiet: bool,
 
+    /// 回放脚本路径（JSON/TOML），指定后以回放源替代 SMTC
+    #[arg(long)]
+    pub replay: Option<String>,
+
+    /// 回放速度倍率（默认 1.0 实时）
+    #[arg(long)]
+    pub replay_speed: Option<f64>,
+
+    /// 录制媒体源原始采样到文件（NDJSON），用于问题复现
+    #[arg(long)]
+    pub record: Option<String>,
+
 
     /// 显示版本信息
     #[arg(long)]
@@ -108,6 +120,9 @@ impl Cli {
         println!("      --port <端口>       自定义同步服务端口 (默认: 3000)");
         println!("  -r, --retries <次数>    最大重试次数 (默认: 3)");
         println!("  -q, --quiet             静默模式（不输出控制台信息）");
+        println!("      --replay <文件>     使用回放脚本替代 SMTC (JSON/TOML)");
+        println!("      --replay-speed <倍率> 回放速度 (默认: 1.0)");
+        println!("      --record <文件>     录制媒体源原始采样 (NDJSON，可用 --replay 回放)");
 
         println!("  -v, --version           显示版本信息");
         println!("  -h, --help              显示帮助信息");
@@ -116,5 +131,7 @@ impl Cli {
         println!("  qqmusic-reader -d --interval 1000");
         println!("  qqmusic-reader --no-json --txt-file music_info.txt");
         println!("  qqmusic-reader -c custom_config.toml");
+        println!("  qqmusic-reader --replay examples/replay_demo.toml --replay-speed 4");
+        println!("  qqmusic-reader --record capture.ndjson");
     }
 }
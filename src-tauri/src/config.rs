@@ -1,11 +1,17 @@
 use anyhow::{anyhow, Result};
 use serde::{Deserialize, Serialize};
 use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::session_filter::SessionFilter;
 
 /// 配置文件结构
 #[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct Config {
     pub settings: Settings,
+    /// 媒体会话过滤：允许/拒绝列表与回退策略
+    #[serde(default)]
+    pub sessions: SessionFilter,
 }
 
 /// 程序设置配置
@@ -23,6 +29,81 @@ pub struct Settings {
     pub debug_mode: bool,
     pub enable_server: bool,
     pub server_port: u16,
+    /// 媒体源：`auto`（按平台选择）/ `smtc` / `mpris` / `replay`
+    #[serde(default = "default_media_source")]
+    pub media_source: String,
+    /// 回放脚本路径（JSON/TOML），`media_source = "replay"` 时使用
+    #[serde(default)]
+    pub replay_file: String,
+    /// 回放速度倍率，1.0 为实时
+    #[serde(default = "default_replay_speed")]
+    pub replay_speed: f64,
+    /// 录制文件路径（NDJSON），非空时记录媒体源的每个原始采样，可用 `--replay` 回放
+    #[serde(default)]
+    pub record_file: String,
+    /// 封面磁盘缓存目录，非空时 `/api/cover/{id}` 的图片同时落盘，重启后仍可读取
+    #[serde(default)]
+    pub cover_cache_dir: String,
+    /// 磁盘歌词缓存目录，为空时不持久化（重启后重新获取）
+    #[serde(default = "default_lyrics_cache_dir")]
+    pub lyrics_cache_dir: String,
+    /// 磁盘歌词缓存有效期（小时），过期条目先显示再后台重新获取
+    #[serde(default = "default_lyrics_cache_ttl_hours")]
+    pub lyrics_cache_ttl_hours: u64,
+    /// 磁盘歌词缓存大小上限（MB），超出时淘汰最旧的条目
+    #[serde(default = "default_lyrics_cache_max_mb")]
+    pub lyrics_cache_max_mb: u64,
+    /// 在线未找到歌词（或失败重试用尽）后，间隔多少分钟再查一次
+    #[serde(default = "default_lyrics_not_found_recheck_minutes")]
+    pub lyrics_not_found_recheck_minutes: u64,
+    /// 在线歌词源，按优先级排列：前一个没有歌词时查询下一个
+    #[serde(default = "default_lyric_providers")]
+    pub lyric_providers: Vec<String>,
+    /// 用户歌词目录（`.lrc` / `.ttml`，含子目录），命中的歌词覆盖缓存与在线结果；为空时不启用
+    #[serde(default)]
+    pub lyrics_folder: String,
+    /// 手动固定歌词的映射文件（JSON，`title|artist` → 歌曲 id 或文件路径）；为空时不启用
+    #[serde(default = "default_lyrics_pins_file")]
+    pub lyrics_pins_file: String,
+    /// 单曲歌词偏移的映射文件（JSON，`title|artist` → 毫秒），为空时不启用
+    #[serde(default = "default_lyrics_offsets_file")]
+    pub lyrics_offsets_file: String,
+}
+
+fn default_media_source() -> String {
+    "auto".to_string()
+}
+
+fn default_replay_speed() -> f64 {
+    1.0
+}
+
+fn default_lyrics_cache_dir() -> String {
+    "lyrics_cache".to_string()
+}
+
+fn default_lyrics_cache_ttl_hours() -> u64 {
+    24 * 7
+}
+
+fn default_lyrics_cache_max_mb() -> u64 {
+    64
+}
+
+fn default_lyrics_not_found_recheck_minutes() -> u64 {
+    60
+}
+
+fn default_lyrics_pins_file() -> String {
+    "lyrics_pins.json".to_string()
+}
+
+fn default_lyrics_offsets_file() -> String {
+    "lyrics_offsets.json".to_string()
+}
+
+fn default_lyric_providers() -> Vec<String> {
+    crate::lyric_provider::DEFAULT_PROVIDERS.iter().map(|id| id.to_string()).collect()
 }
 
 impl Default for Config {
@@ -41,7 +122,21 @@ impl Default for Config {
                 debug_mode: false,
                 enable_server: true,
                 server_port: 3000,
+                media_source: default_media_source(),
+                replay_file: String::new(),
+                replay_speed: default_replay_speed(),
+                record_file: String::new(),
+                cover_cache_dir: String::new(),
+                lyrics_cache_dir: default_lyrics_cache_dir(),
+                lyrics_cache_ttl_hours: default_lyrics_cache_ttl_hours(),
+                lyrics_cache_max_mb: default_lyrics_cache_max_mb(),
+                lyrics_not_found_recheck_minutes: default_lyrics_not_found_recheck_minutes(),
+                lyric_providers: default_lyric_providers(),
+                lyrics_folder: String::new(),
+                lyrics_pins_file: default_lyrics_pins_file(),
+                lyrics_offsets_file: default_lyrics_offsets_file(),
             },
+            sessions: SessionFilter::default(),
         }
     }
 }
@@ -80,3 +175,57 @@ impl Config {
         }
     }
 }
+
+/// 应用数据目录名（与 productName 一致）
+const APP_DATA_DIR_NAME: &str = "qqmusic-monitor";
+
+/// 应用数据目录：Windows 为 `%APPDATA%\qqmusic-monitor`，
+/// 其他平台为 `$XDG_DATA_HOME/qqmusic-monitor`（默认 `~/.local/share/qqmusic-monitor`）
+pub fn app_data_dir() -> Option<PathBuf> {
+    let base = if cfg!(windows) {
+        std::env::var_os("APPDATA").map(PathBuf::from)
+    } else {
+        std::env::var_os("XDG_DATA_HOME")
+            .map(PathBuf::from)
+            .filter(|dir| dir.is_absolute())
+            .or_else(|| std::env::var_os("HOME").map(|home| Path::new(&home).join(".local").join("share")))
+    };
+    base.filter(|dir| !dir.as_os_str().is_empty()).map(|dir| dir.join(APP_DATA_DIR_NAME))
+}
+
+/// 解析配置中的数据路径（歌词缓存目录、映射文件等）：空字符串表示不启用，返回 None；
+/// 相对路径相对应用数据目录，不随启动时的工作目录（开机自启、快捷方式）变化；
+/// 取不到应用数据目录时才按工作目录解析
+pub fn resolve_data_path(path: &str) -> Option<PathBuf> {
+    let path = path.trim();
+    if path.is_empty() {
+        return None;
+    }
+    let path = Path::new(path);
+    if path.is_absolute() {
+        return Some(path.to_path_buf());
+    }
+    Some(match app_data_dir() {
+        Some(dir) => dir.join(path),
+        None => path.to_path_buf(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_data_path() {
+        assert_eq!(resolve_data_path("  "), None);
+        let absolute = std::env::temp_dir().join("lyrics_cache");
+        assert_eq!(resolve_data_path(absolute.to_str().unwrap()), Some(absolute));
+
+        let resolved = resolve_data_path("lyrics_cache").unwrap();
+        assert!(resolved.ends_with("lyrics_cache"));
+        if let Some(dir) = app_data_dir() {
+            assert!(dir.is_absolute());
+            assert_eq!(resolved, dir.join("lyrics_cache"));
+        }
+    }
+}
@@ -0,0 +1,290 @@
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+use tokio::sync::{mpsc, oneshot};
+
+use crate::lyric_provider::LyricCandidate;
+use crate::lyrics_offsets::OffsetChange;
+use crate::lyrics_pins::{self, LyricsPin};
+use crate::song_info::{scale_elapsed, SongInfo};
+
+/// 播放控制命令。JSON 形如 `{"command": "seek", "position_ms": 60000}`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(tag = "command", rename_all = "snake_case")]
+pub enum PlaybackCommand {
+    PlayPause,
+    Next,
+    Previous,
+    Seek { position_ms: u64 },
+}
+
+/// 跳转目标：歌词行索引（`qrc_data` 下标；无逐字歌词时为带时间戳的 LRC 行序号）或歌词时间戳。
+/// JSON 形如 `{"line": 12}` 或 `{"time_ms": 61000}`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum SeekTarget {
+    Line { line: usize },
+    Time { time_ms: u64 },
+}
+
+/// 跳转结果：是否生效由跳转后的时间轴采样判断
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct SeekResult {
+    /// 实际发给播放器的位置（已扣除 smtc_offset_ms）
+    pub requested_ms: u64,
+    pub honored: bool,
+    /// 用于判断的采样位置；超时前没有任何采样时为 None
+    pub observed_ms: Option<u64>,
+}
+
+/// 发往主循环的控制请求；媒体源与当前歌曲只能在主循环所在的 LocalSet 上访问，
+/// 因此 Tauri 命令与 HTTP/WS 处理器都通过通道转交，并经 `reply` 取回结果。
+pub enum ControlRequest {
+    Command {
+        command: PlaybackCommand,
+        reply: oneshot::Sender<Result<(), String>>,
+    },
+    SeekTo {
+        target: SeekTarget,
+        reply: oneshot::Sender<Result<SeekResult, String>>,
+    },
+    /// 列出当前歌曲的歌词候选
+    LyricCandidates {
+        reply: oneshot::Sender<Result<Vec<LyricCandidate>, String>>,
+    },
+    /// 为当前歌曲固定歌词（`None` 为取消固定），随后重新加载歌词
+    PinLyrics {
+        pin: Option<LyricsPin>,
+        reply: oneshot::Sender<Result<(), String>>,
+    },
+    /// 调整当前歌曲的歌词偏移，回复调整后的偏移（毫秒）
+    LyricsOffset {
+        change: OffsetChange,
+        reply: oneshot::Sender<Result<i64, String>>,
+    },
+}
+
+/// 控制请求通道容量：按键连点时的积压上限
+const CONTROL_CHANNEL_CAPACITY: usize = 16;
+/// 等待主循环执行命令的超时（需大于 SEEK_VERIFY_TIMEOUT）
+const CONTROL_TIMEOUT: Duration = Duration::from_secs(5);
+/// 等待候选搜索的超时：依次查询所有歌词源
+const CANDIDATES_TIMEOUT: Duration = Duration::from_secs(20);
+/// 跳转后等待时间轴采样确认的最长时间；SMTC timeline 通常滞后 1~2 秒
+pub const SEEK_VERIFY_TIMEOUT: Duration = Duration::from_secs(3);
+/// 采样位置与期望位置的容差
+const SEEK_TOLERANCE_MS: u64 = 1000;
+
+static CONTROL_TX: OnceLock<mpsc::Sender<ControlRequest>> = OnceLock::new();
+
+/// 创建控制通道并登记全局发送端，接收端交给主循环
+pub fn init_channel() -> Result<mpsc::Receiver<ControlRequest>> {
+    let (tx, rx) = mpsc::channel(CONTROL_CHANNEL_CAPACITY);
+    CONTROL_TX
+        .set(tx)
+        .map_err(|_| anyhow!("控制通道已初始化"))?;
+    Ok(rx)
+}
+
+/// 控制请求失败的原因：HTTP 按此区分状态码
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ControlError {
+    /// 主循环尚未启动或已退出
+    Unavailable(&'static str),
+    /// 主循环未在时限内回复
+    Timeout,
+    /// 请求本身无效（参数不合法等）
+    Invalid(String),
+    /// 主循环执行失败（没有会话、媒体源拒绝等）
+    Failed(String),
+}
+
+impl std::fmt::Display for ControlError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ControlError::Unavailable(reason) => write!(f, "{}", reason),
+            ControlError::Timeout => write!(f, "播放控制超时"),
+            ControlError::Invalid(reason) | ControlError::Failed(reason) => write!(f, "{}", reason),
+        }
+    }
+}
+
+/// 发送控制命令并等待主循环执行结果
+pub async fn send(command: PlaybackCommand) -> Result<(), ControlError> {
+    let (reply, result) = oneshot::channel();
+    request(ControlRequest::Command { command, reply }, result, CONTROL_TIMEOUT).await
+}
+
+/// 跳转到歌词行/歌词时间戳，并等待时间轴确认是否生效
+pub async fn seek_to(target: SeekTarget) -> Result<SeekResult, ControlError> {
+    let (reply, result) = oneshot::channel();
+    request(ControlRequest::SeekTo { target, reply }, result, CONTROL_TIMEOUT).await
+}
+
+/// 搜索当前歌曲的歌词候选（在线歌词源 + 本地缓存文件），按匹配度排列
+pub async fn lyric_candidates() -> Result<Vec<LyricCandidate>, ControlError> {
+    let (reply, result) = oneshot::channel();
+    request(ControlRequest::LyricCandidates { reply }, result, CANDIDATES_TIMEOUT).await
+}
+
+/// 为当前歌曲固定歌词或取消固定；固定文件时先检查路径（见 `lyrics_pins::check_pin_file`）
+pub async fn pin_lyrics(mut pin: Option<LyricsPin>) -> Result<(), ControlError> {
+    if let Some(LyricsPin::File { path }) = &mut pin {
+        let roots = crate::pin_file_roots().await;
+        let checked = lyrics_pins::check_pin_file(Path::new(path.as_str()), &roots)
+            .map_err(|e| ControlError::Invalid(format!("{:#}", e)))?;
+        *path = checked.to_string_lossy().into_owned();
+    }
+    let (reply, result) = oneshot::channel();
+    request(ControlRequest::PinLyrics { pin, reply }, result, CONTROL_TIMEOUT).await
+}
+
+/// 设置或增减当前歌曲的歌词偏移并保存，返回调整后的偏移
+pub async fn lyrics_offset(change: OffsetChange) -> Result<i64, ControlError> {
+    let (reply, result) = oneshot::channel();
+    request(ControlRequest::LyricsOffset { change, reply }, result, CONTROL_TIMEOUT).await
+}
+
+async fn request<T>(
+    request: ControlRequest,
+    result: oneshot::Receiver<Result<T, String>>,
+    timeout: Duration,
+) -> Result<T, ControlError> {
+    let tx = CONTROL_TX.get().ok_or(ControlError::Unavailable("播放控制尚未就绪"))?;
+    tx.send(request)
+        .await
+        .map_err(|_| ControlError::Unavailable("监控循环已退出"))?;
+    match tokio::time::timeout(timeout, result).await {
+        Ok(Ok(result)) => result.map_err(ControlError::Failed),
+        Ok(Err(_)) => Err(ControlError::Unavailable("监控循环已退出")),
+        Err(_) => Err(ControlError::Timeout),
+    }
+}
+
+/// 解析跳转目标对应的歌词时间（毫秒）；行号越界或没有歌词时返回 None
+pub fn resolve_seek_target(info: &SongInfo, target: SeekTarget) -> Option<u64> {
+    match target {
+        SeekTarget::Time { time_ms } => Some(time_ms),
+        SeekTarget::Line { line } => {
+            if !info.qrc_data.is_empty() {
+                return info.qrc_data.get(line).map(|l| l.start_time_ms);
+            }
+            // 无逐字歌词：按带时间戳的 LRC 行计数
+            info.lyrics
+                .lines()
+                .filter_map(|l| {
+                    let start = l.find('[')?;
+                    let end = l.find(']')?;
+                    crate::parse_lrc_time(l.get(start + 1..end)?).ok()
+                })
+                .nth(line)
+                .map(|secs| (secs * 1000.0).round() as u64)
+        }
+    }
+}
+
+/// 歌词时间换算为播放器位置：显示时间 = 播放位置 + smtc_offset_ms + 单曲偏移，
+/// 因此要让该行在显示上恰好开始，播放器应跳到 歌词时间 - 两者之和（`offset_ms`）
+pub fn player_position_for(lyric_time_ms: u64, offset_ms: i64) -> u64 {
+    lyric_time_ms.saturating_add_signed(offset_ms.saturating_neg())
+}
+
+/// 等待时间轴确认的跳转
+pub struct PendingSeek {
+    pub requested_ms: u64,
+    pub started: Instant,
+    pub last_observed_ms: Option<u64>,
+    pub reply: oneshot::Sender<Result<SeekResult, String>>,
+}
+
+impl PendingSeek {
+    pub fn new(requested_ms: u64, reply: oneshot::Sender<Result<SeekResult, String>>) -> Self {
+        Self { requested_ms, started: Instant::now(), last_observed_ms: None, reply }
+    }
+
+    /// 用跳转后的采样检查：位置落在期望范围内即生效；超时仍未落入则判定未生效。
+    /// 尚无结论时返回 `Some(self)` 继续等待。
+    pub fn observe(mut self, info: &SongInfo) -> Option<Self> {
+        let elapsed_ms = scale_elapsed(self.started.elapsed().as_millis() as u64, info.rate());
+        self.last_observed_ms = Some(info.current_time_ms);
+        let honored = is_seek_honored(self.requested_ms, info.current_time_ms, elapsed_ms, info.is_playing);
+        if honored || self.started.elapsed() >= SEEK_VERIFY_TIMEOUT {
+            self.finish(honored);
+            return None;
+        }
+        Some(self)
+    }
+
+    pub fn finish(self, honored: bool) {
+        let _ = self.reply.send(Ok(SeekResult {
+            requested_ms: self.requested_ms,
+            honored,
+            observed_ms: self.last_observed_ms,
+        }));
+    }
+}
+
+/// 采样位置是否与跳转目标一致（播放中允许按跳转后流逝的时间前进）
+pub fn is_seek_honored(requested_ms: u64, observed_ms: u64, elapsed_ms: u64, is_playing: bool) -> bool {
+    let expected_ms = if is_playing { requested_ms + elapsed_ms } else { requested_ms };
+    observed_ms.abs_diff(expected_ms) <= SEEK_TOLERANCE_MS
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::song_info::QrcLine;
+
+    #[test]
+    fn test_command_json() {
+        let seek: PlaybackCommand = serde_json::from_str(r#"{"command":"seek","position_ms":60000}"#).unwrap();
+        assert_eq!(seek, PlaybackCommand::Seek { position_ms: 60_000 });
+        let toggle: PlaybackCommand = serde_json::from_str(r#"{"command":"play_pause"}"#).unwrap();
+        assert_eq!(toggle, PlaybackCommand::PlayPause);
+        assert_eq!(serde_json::to_string(&PlaybackCommand::Next).unwrap(), r#"{"command":"next"}"#);
+    }
+
+    #[test]
+    fn test_seek_target_json() {
+        let line: SeekTarget = serde_json::from_str(r#"{"line":12}"#).unwrap();
+        assert_eq!(line, SeekTarget::Line { line: 12 });
+        let time: SeekTarget = serde_json::from_str(r#"{"time_ms":61000}"#).unwrap();
+        assert_eq!(time, SeekTarget::Time { time_ms: 61_000 });
+    }
+
+    /// 行号优先取 qrc_data，没有逐字歌词时按 LRC 时间戳行计数（跳过无时间戳的标签行）
+    #[test]
+    fn test_resolve_seek_target() {
+        let qrc_line = |start_time_ms| QrcLine {
+            content: String::new(),
+            start_time_ms,
+            duration_ms: 1000,
+            words: Vec::new(),
+        };
+        let qrc = SongInfo { qrc_data: vec![qrc_line(1_000), qrc_line(5_500)], ..SongInfo::default() };
+        assert_eq!(resolve_seek_target(&qrc, SeekTarget::Line { line: 1 }), Some(5_500));
+        assert_eq!(resolve_seek_target(&qrc, SeekTarget::Line { line: 2 }), None);
+        assert_eq!(resolve_seek_target(&qrc, SeekTarget::Time { time_ms: 42 }), Some(42));
+
+        let lrc = SongInfo {
+            lyrics: "[ti:那时雨]\n[00:01.50]第一行\n[01:02.25]第二行".to_string(),
+            ..SongInfo::default()
+        };
+        assert_eq!(resolve_seek_target(&lrc, SeekTarget::Line { line: 1 }), Some(62_250));
+        assert_eq!(resolve_seek_target(&SongInfo::default(), SeekTarget::Line { line: 0 }), None);
+    }
+
+    #[test]
+    fn test_seek_verification() {
+        assert_eq!(player_position_for(5_500, 200), 5_300);
+        assert_eq!(player_position_for(100, 200), 0);
+        assert_eq!(player_position_for(5_500, 200 - 500), 5_800);
+
+        assert!(is_seek_honored(60_000, 60_400, 300, true));
+        assert!(is_seek_honored(60_000, 61_800, 1_500, true));
+        assert!(!is_seek_honored(60_000, 12_000, 300, true));
+        assert!(!is_seek_honored(60_000, 61_800, 1_500, false));
+    }
+}
@@ -0,0 +1,229 @@
+use anyhow::{anyhow, Context, Result};
+use image::imageops::FilterType;
+use image::{DynamicImage, GenericImageView, ImageFormat};
+use serde::Serialize;
+use std::io::Cursor;
+
+/// 缩放尺寸上下限：请求值会被限制在此范围内，且不会放大原图
+const MIN_SIZE: u32 = 16;
+const MAX_SIZE: u32 = 1024;
+/// 重新编码 JPEG 的质量
+const JPEG_QUALITY: u8 = 85;
+/// 取色前先缩小到该边长，封面细节对主题色无影响
+const PALETTE_SAMPLE_SIZE: u32 = 48;
+/// 调色板最多返回的颜色数
+const PALETTE_COLORS: usize = 5;
+
+/// 封面输出格式（`?format=`）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CoverFormat {
+    Jpeg,
+    Png,
+    Webp,
+}
+
+impl CoverFormat {
+    /// 解析 `?format=` 参数；`auto` 由调用方按 Accept 协商，不在此处理
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "jpeg" | "jpg" => Some(Self::Jpeg),
+            "png" => Some(Self::Png),
+            "webp" => Some(Self::Webp),
+            _ => None,
+        }
+    }
+
+    /// 按 Accept 请求头协商：接受 WebP 时优先 WebP，否则 JPEG
+    pub fn negotiate(accept: &str) -> Self {
+        if accept.split(',').any(|t| t.trim().starts_with("image/webp")) {
+            Self::Webp
+        } else {
+            Self::Jpeg
+        }
+    }
+
+    pub fn content_type(self) -> &'static str {
+        match self {
+            Self::Jpeg => "image/jpeg",
+            Self::Png => "image/png",
+            Self::Webp => "image/webp",
+        }
+    }
+
+    /// 原图的格式（按 Content-Type），不支持重新编码的格式返回 None
+    pub fn from_content_type(content_type: &str) -> Option<Self> {
+        match content_type {
+            "image/jpeg" => Some(Self::Jpeg),
+            "image/png" => Some(Self::Png),
+            "image/webp" => Some(Self::Webp),
+            _ => None,
+        }
+    }
+}
+
+/// 封面主题色（`#rrggbb`）
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Palette {
+    /// 面积最大的颜色，适合作背景
+    pub dominant: String,
+    /// 与主色有明显区别且饱和度较高的颜色，适合作强调色；没有合适颜色时同主色
+    pub accent: String,
+    /// 按面积排序的主要颜色
+    pub colors: Vec<String>,
+}
+
+/// 限制请求尺寸：不小于 MIN_SIZE，不大于 MAX_SIZE
+pub fn clamp_size(size: u32) -> u32 {
+    size.clamp(MIN_SIZE, MAX_SIZE)
+}
+
+/// 缩放（等比适配 size×size，不放大）并按指定格式重新编码
+pub fn render(bytes: &[u8], size: Option<u32>, format: CoverFormat) -> Result<Vec<u8>> {
+    let image = image::load_from_memory(bytes).context("无法解码封面")?;
+    let image = match size.map(clamp_size) {
+        Some(size) if image.width() > size || image.height() > size => {
+            image.resize(size, size, FilterType::Triangle)
+        }
+        _ => image,
+    };
+    encode(&image, format)
+}
+
+fn encode(image: &DynamicImage, format: CoverFormat) -> Result<Vec<u8>> {
+    let mut buffer = Cursor::new(Vec::new());
+    match format {
+        CoverFormat::Jpeg => {
+            // JPEG 不支持透明通道
+            let rgb = DynamicImage::ImageRgb8(image.to_rgb8());
+            let encoder = image::codecs::jpeg::JpegEncoder::new_with_quality(&mut buffer, JPEG_QUALITY);
+            rgb.write_with_encoder(encoder)?;
+        }
+        CoverFormat::Png => image.write_to(&mut buffer, ImageFormat::Png)?,
+        // image 仅支持无损 WebP 编码；缩略图场景下体积仍明显小于原图
+        CoverFormat::Webp => DynamicImage::ImageRgba8(image.to_rgba8()).write_to(&mut buffer, ImageFormat::WebP)?,
+    }
+    Ok(buffer.into_inner())
+}
+
+/// 提取封面主题色：缩小后按每通道 4 bit 量化统计，取面积最大的若干颜色
+pub fn palette(bytes: &[u8]) -> Result<Palette> {
+    let image = image::load_from_memory(bytes)
+        .context("无法解码封面")?
+        .resize_exact(PALETTE_SAMPLE_SIZE, PALETTE_SAMPLE_SIZE, FilterType::Triangle);
+
+    // 4096 个桶：计数与各通道累加值，桶内取平均色
+    let mut buckets = vec![(0u32, [0u32; 3]); 4096];
+    for (_, _, pixel) in image.pixels() {
+        let [r, g, b, a] = pixel.0;
+        if a < 128 {
+            continue;
+        }
+        let idx = ((r as usize >> 4) << 8) | ((g as usize >> 4) << 4) | (b as usize >> 4);
+        let bucket = &mut buckets[idx];
+        bucket.0 += 1;
+        bucket.1[0] += r as u32;
+        bucket.1[1] += g as u32;
+        bucket.1[2] += b as u32;
+    }
+
+    let mut ranked: Vec<(u32, [u8; 3])> = buckets
+        .into_iter()
+        .filter(|(count, _)| *count > 0)
+        .map(|(count, sum)| (count, sum.map(|c| (c / count) as u8)))
+        .collect();
+    ranked.sort_by_key(|(count, _)| std::cmp::Reverse(*count));
+
+    // 合并相近颜色，避免调色板里出现几乎相同的颜色
+    let mut colors: Vec<(u32, [u8; 3])> = Vec::new();
+    for (count, rgb) in ranked {
+        match colors.iter_mut().find(|(_, c)| distance(*c, rgb) < 48) {
+            Some(existing) => existing.0 += count,
+            None => colors.push((count, rgb)),
+        }
+    }
+    colors.sort_by_key(|(count, _)| std::cmp::Reverse(*count));
+    colors.truncate(PALETTE_COLORS);
+
+    let dominant = colors.first().map(|(_, c)| *c).ok_or_else(|| anyhow!("封面没有不透明像素"))?;
+    let total: u32 = colors.iter().map(|(count, _)| count).sum();
+    // 强调色：面积不可忽略（≥5%）、与主色有区别，按饱和度 × 面积排序
+    let accent = colors
+        .iter()
+        .skip(1)
+        .filter(|(count, c)| *count * 20 >= total && distance(*c, dominant) >= 96)
+        .max_by(|a, b| {
+            let score = |(count, c): &(u32, [u8; 3])| saturation(*c) * (*count as f32).sqrt();
+            score(a).total_cmp(&score(b))
+        })
+        .map(|(_, c)| *c)
+        .unwrap_or(dominant);
+
+    Ok(Palette {
+        dominant: hex(dominant),
+        accent: hex(accent),
+        colors: colors.iter().map(|(_, c)| hex(*c)).collect(),
+    })
+}
+
+fn distance(a: [u8; 3], b: [u8; 3]) -> u32 {
+    a.iter().zip(b).map(|(x, y)| (*x as i32 - y as i32).unsigned_abs()).sum()
+}
+
+fn saturation([r, g, b]: [u8; 3]) -> f32 {
+    let max = r.max(g).max(b) as f32;
+    let min = r.min(g).min(b) as f32;
+    if max == 0.0 { 0.0 } else { (max - min) / max }
+}
+
+fn hex([r, g, b]: [u8; 3]) -> String {
+    format!("#{:02x}{:02x}{:02x}", r, g, b)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{Rgb, RgbImage};
+
+    /// 左 3/4 深蓝、右 1/4 橙色的测试封面
+    fn cover_png(width: u32, height: u32) -> Vec<u8> {
+        let image = RgbImage::from_fn(width, height, |x, _| {
+            if x < width * 3 / 4 { Rgb([16, 32, 96]) } else { Rgb([240, 128, 16]) }
+        });
+        let mut buffer = Cursor::new(Vec::new());
+        DynamicImage::ImageRgb8(image).write_to(&mut buffer, ImageFormat::Png).unwrap();
+        buffer.into_inner()
+    }
+
+    #[test]
+    fn test_render_resizes_and_reencodes() {
+        let source = cover_png(400, 200);
+        let webp = render(&source, Some(100), CoverFormat::Webp).unwrap();
+        let decoded = image::load_from_memory_with_format(&webp, ImageFormat::WebP).unwrap();
+        assert_eq!(decoded.dimensions(), (100, 50));
+
+        // 不放大：请求尺寸大于原图时保持原尺寸
+        let jpeg = render(&source, Some(800), CoverFormat::Jpeg).unwrap();
+        let decoded = image::load_from_memory_with_format(&jpeg, ImageFormat::Jpeg).unwrap();
+        assert_eq!(decoded.dimensions(), (400, 200));
+
+        assert!(render(b"not an image", Some(64), CoverFormat::Png).is_err());
+    }
+
+    #[test]
+    fn test_palette() {
+        let palette = palette(&cover_png(64, 64)).unwrap();
+        assert_eq!(palette.dominant, "#102060");
+        assert_eq!(palette.accent, "#f08010");
+        assert_eq!(palette.colors[0], palette.dominant);
+        assert!(palette.colors.len() <= PALETTE_COLORS);
+    }
+
+    #[test]
+    fn test_format_negotiation() {
+        assert_eq!(CoverFormat::parse("JPG"), Some(CoverFormat::Jpeg));
+        assert_eq!(CoverFormat::parse("avif"), None);
+        assert_eq!(CoverFormat::negotiate("image/avif,image/webp,*/*;q=0.8"), CoverFormat::Webp);
+        assert_eq!(CoverFormat::negotiate("*/*"), CoverFormat::Jpeg);
+        assert_eq!(clamp_size(4), 16);
+    }
+}
@@ -0,0 +1,301 @@
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, VecDeque};
+use std::hash::{Hash, Hasher};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex, OnceLock};
+
+use anyhow::Result;
+
+use crate::cover_image::{self, CoverFormat, Palette};
+
+/// 封面接口路径前缀：`SongInfo.album_pic_url` 形如 `/api/cover/{id}`
+pub const COVER_PATH: &str = "/api/cover";
+
+/// 内存中保留的封面数量上限，超出后淘汰最早写入的条目（磁盘缓存不受影响）。
+/// 当前曲目的封面不淘汰（见 `set_current`）。封面约 50~300KB/张，32 张约 10MB。
+const MAX_MEMORY_COVERS: usize = 32;
+/// 缩放/转码结果与调色板的缓存上限（缩略图通常只有几 KB）
+const MAX_DERIVED: usize = 64;
+
+/// 派生图缓存键：原图摘要 + 尺寸 + 格式，原图更新后自然失效
+type VariantKey = (String, Option<u32>, CoverFormat);
+
+/// 一张已登记的封面
+#[derive(Debug, Clone)]
+pub struct Cover {
+    pub content_type: &'static str,
+    pub bytes: Arc<Vec<u8>>,
+    /// 内容摘要，用作 HTTP ETag
+    pub etag: String,
+}
+
+/// 封面存储：按 album_mid 或缩略图内容摘要登记图片，由 HTTP 服务以短地址提供。
+///
+/// 未启用 HTTP 服务时没有地址可用，`publish` 退回 base64 data URI（与旧行为一致）。
+struct CoverStore {
+    serve: bool,
+    dir: Option<PathBuf>,
+    covers: HashMap<String, Cover>,
+    order: VecDeque<String>,
+    variants: HashMap<VariantKey, Cover>,
+    variant_order: VecDeque<VariantKey>,
+    /// 原图摘要 → 调色板
+    palettes: HashMap<String, Palette>,
+    /// 当前曲目的封面 id：未配置 `cover_cache_dir` 时淘汰后无法从磁盘恢复，切歌前一直保留
+    current: Option<String>,
+}
+
+static STORE: OnceLock<Mutex<CoverStore>> = OnceLock::new();
+
+fn store() -> &'static Mutex<CoverStore> {
+    STORE.get_or_init(|| {
+        Mutex::new(CoverStore {
+            serve: false,
+            dir: None,
+            covers: HashMap::new(),
+            order: VecDeque::new(),
+            variants: HashMap::new(),
+            variant_order: VecDeque::new(),
+            palettes: HashMap::new(),
+            current: None,
+        })
+    })
+}
+
+/// 启动时配置：`serve` 为 HTTP 服务是否启用，`dir` 非空时封面同时落盘，重启后仍可按 id 读取
+pub fn init(serve: bool, dir: Option<PathBuf>) {
+    if let Some(dir) = &dir {
+        if let Err(e) = std::fs::create_dir_all(dir) {
+            eprintln!("[cover] 无法创建封面缓存目录 {}: {}", dir.display(), e);
+        }
+    }
+    let mut store = lock();
+    store.serve = serve;
+    store.dir = dir;
+}
+
+/// 登记封面并返回 `SongInfo.album_pic_url` 使用的地址。
+///
+/// `key` 为稳定标识（如 album_mid），为空时按内容摘要生成 id；
+/// `content_type` 为空时按文件头推断。
+pub fn publish(key: Option<&str>, bytes: Vec<u8>, content_type: Option<&str>) -> String {
+    let content_type = content_type
+        .and_then(known_content_type)
+        .unwrap_or_else(|| sniff_content_type(&bytes));
+    let digest = digest(&bytes);
+    let id = match key.map(sanitize_id).filter(|k| !k.is_empty()) {
+        Some(key) => key,
+        None => format!("h{}", digest),
+    };
+
+    let mut store = lock();
+    if !store.serve {
+        use base64::{engine::general_purpose::STANDARD, Engine as _};
+        return format!("data:{};base64,{}", content_type, STANDARD.encode(&bytes));
+    }
+
+    let unchanged = store.covers.get(&id).is_some_and(|c| c.etag == digest);
+    if !unchanged {
+        if let Some(dir) = &store.dir {
+            if let Err(e) = std::fs::write(dir.join(&id), &bytes) {
+                eprintln!("[cover] 写入封面缓存失败 {}: {}", id, e);
+            }
+        }
+        store.insert(id.clone(), Cover { content_type, bytes: Arc::new(bytes), etag: digest });
+    }
+    format!("{}/{}", COVER_PATH, id)
+}
+
+/// 登记当前推送的曲目封面（`album_pic_url`），在切到下一首之前不从内存中淘汰；
+/// 非封面存储地址（data URI、在线地址、空）时取消保留
+pub fn set_current(url: &str) {
+    let id = url.strip_prefix(COVER_PATH).and_then(|id| id.strip_prefix('/'));
+    lock().current = id.map(str::to_string);
+}
+
+/// 按 id 读取封面：内存未命中时尝试磁盘缓存
+pub fn get(id: &str) -> Option<Cover> {
+    if sanitize_id(id) != id || id.is_empty() {
+        return None;
+    }
+    let mut store = lock();
+    if let Some(cover) = store.covers.get(id) {
+        return Some(cover.clone());
+    }
+    let bytes = std::fs::read(store.dir.as_ref()?.join(id)).ok()?;
+    let cover = Cover { content_type: sniff_content_type(&bytes), etag: digest(&bytes), bytes: Arc::new(bytes) };
+    store.insert(id.to_string(), cover.clone());
+    Some(cover)
+}
+
+/// 按尺寸/格式读取封面（`?size=256&format=webp`）：结果按原图摘要缓存。
+/// 缩放与编码耗 CPU，应在 spawn_blocking 中调用；id 不存在时返回 None。
+pub fn get_variant(id: &str, size: Option<u32>, format: CoverFormat) -> Option<Result<Cover>> {
+    let source = get(id)?;
+    let size = size.map(cover_image::clamp_size);
+    if size.is_none() && CoverFormat::from_content_type(source.content_type) == Some(format) {
+        return Some(Ok(source));
+    }
+    let key = (source.etag.clone(), size, format);
+    if let Some(cover) = lock().variants.get(&key) {
+        return Some(Ok(cover.clone()));
+    }
+
+    // 编码期间不持锁，并发请求同一变体时最多重复计算一次
+    let bytes = match cover_image::render(&source.bytes, size, format) {
+        Ok(bytes) => bytes,
+        Err(e) => return Some(Err(e)),
+    };
+    let size_tag = size.map(|s| s.to_string()).unwrap_or_else(|| "orig".to_string());
+    let cover = Cover {
+        content_type: format.content_type(),
+        bytes: Arc::new(bytes),
+        etag: format!("{}-{}-{}", source.etag, size_tag, format.content_type().trim_start_matches("image/")),
+    };
+
+    let mut store = lock();
+    if store.variants.insert(key.clone(), cover.clone()).is_none() {
+        store.variant_order.push_back(key);
+    }
+    while store.variant_order.len() > MAX_DERIVED {
+        if let Some(oldest) = store.variant_order.pop_front() {
+            store.variants.remove(&oldest);
+        }
+    }
+    Some(Ok(cover))
+}
+
+/// 封面主题色，按原图摘要缓存；同样应在 spawn_blocking 中调用
+pub fn palette(id: &str) -> Option<Result<Palette>> {
+    let source = get(id)?;
+    if let Some(palette) = lock().palettes.get(&source.etag) {
+        return Some(Ok(palette.clone()));
+    }
+    let palette = match cover_image::palette(&source.bytes) {
+        Ok(palette) => palette,
+        Err(e) => return Some(Err(e)),
+    };
+    let mut store = lock();
+    if store.palettes.len() >= MAX_DERIVED {
+        store.palettes.clear();
+    }
+    store.palettes.insert(source.etag, palette.clone());
+    Some(Ok(palette))
+}
+
+fn lock() -> std::sync::MutexGuard<'static, CoverStore> {
+    store().lock().unwrap_or_else(|e| e.into_inner())
+}
+
+impl CoverStore {
+    fn insert(&mut self, id: String, cover: Cover) {
+        if self.covers.insert(id.clone(), cover).is_none() {
+            self.order.push_back(id);
+        }
+        while self.order.len() > MAX_MEMORY_COVERS {
+            let Some(oldest) = self.order.pop_front() else {
+                break;
+            };
+            if self.current.as_deref() == Some(oldest.as_str()) {
+                // 当前封面移到队尾，淘汰下一张
+                self.order.push_back(oldest);
+                continue;
+            }
+            self.covers.remove(&oldest);
+        }
+    }
+}
+
+fn digest(bytes: &[u8]) -> String {
+    let mut hasher = DefaultHasher::new();
+    bytes.hash(&mut hasher);
+    format!("{:016x}", hasher.finish())
+}
+
+/// id 同时用作 URL 路径段与文件名，只保留字母、数字、`-`、`_`
+fn sanitize_id(key: &str) -> String {
+    key.chars().filter(|c| c.is_ascii_alphanumeric() || *c == '-' || *c == '_').collect()
+}
+
+fn known_content_type(content_type: &str) -> Option<&'static str> {
+    ["image/jpeg", "image/png", "image/webp", "image/gif", "image/bmp"]
+        .into_iter()
+        .find(|known| content_type.eq_ignore_ascii_case(known))
+}
+
+/// 按文件头推断图片类型，未知格式按 JPEG 处理（QQ 音乐本地封面无扩展名且均为 JPEG）
+pub fn sniff_content_type(bytes: &[u8]) -> &'static str {
+    if bytes.starts_with(b"\x89PNG") {
+        "image/png"
+    } else if bytes.starts_with(b"GIF8") {
+        "image/gif"
+    } else if bytes.len() >= 12 && &bytes[0..4] == b"RIFF" && &bytes[8..12] == b"WEBP" {
+        "image/webp"
+    } else if bytes.starts_with(b"BM") {
+        "image/bmp"
+    } else {
+        "image/jpeg"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 全局存储只在此测试中初始化，避免与其他测试互相影响
+    #[test]
+    fn test_publish_and_get() {
+        let dir = std::env::temp_dir().join(format!("cover_store_test_{}", std::process::id()));
+        init(true, Some(dir.clone()));
+
+        let png = b"\x89PNG\r\n\x1a\nrest".to_vec();
+        let url = publish(None, png.clone(), None);
+        let id = url.strip_prefix("/api/cover/").unwrap();
+        let cover = get(id).unwrap();
+        assert_eq!(cover.content_type, "image/png");
+        assert_eq!(*cover.bytes, png);
+        // 相同内容得到相同地址
+        assert_eq!(publish(None, png, None), url);
+
+        let url = publish(Some("003kQTm81fCMvb"), b"jpeg".to_vec(), Some("image/jpeg"));
+        assert_eq!(url, "/api/cover/003kQTm81fCMvb");
+        assert!(dir.join("003kQTm81fCMvb").exists());
+        assert!(get("../etc/passwd").is_none());
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn test_current_cover_not_evicted() {
+        let cover = |n: usize| Cover { content_type: "image/jpeg", bytes: Arc::new(vec![n as u8]), etag: n.to_string() };
+        let mut store = CoverStore {
+            serve: true,
+            dir: None,
+            covers: HashMap::new(),
+            order: VecDeque::new(),
+            variants: HashMap::new(),
+            variant_order: VecDeque::new(),
+            palettes: HashMap::new(),
+            current: Some("c0".to_string()),
+        };
+        for n in 0..MAX_MEMORY_COVERS * 2 {
+            store.insert(format!("c{}", n), cover(n));
+        }
+        assert_eq!(store.covers.len(), MAX_MEMORY_COVERS);
+        assert!(store.covers.contains_key("c0"));
+        assert!(!store.covers.contains_key("c1"));
+
+        // 切歌后旧封面照常淘汰
+        store.current = None;
+        store.insert("next".to_string(), cover(0));
+        assert!(!store.covers.contains_key("c0"));
+    }
+
+    #[test]
+    fn test_sniff_content_type() {
+        assert_eq!(sniff_content_type(b"RIFF\0\0\0\0WEBPVP8 "), "image/webp");
+        assert_eq!(sniff_content_type(b"\xff\xd8\xff\xe0"), "image/jpeg");
+        assert_eq!(known_content_type("IMAGE/PNG"), Some("image/png"));
+        assert_eq!(known_content_type("text/html"), None);
+    }
+}
@@ -0,0 +1,172 @@
+use anyhow::{bail, Context, Result};
+use base64::{Engine as _, engine::general_purpose::STANDARD};
+use flate2::read::ZlibDecoder;
+use serde_json::Value;
+use std::io::Read;
+use std::path::Path;
+
+use crate::song_info::{QrcLine, QrcWord};
+
+/// KRC 文件头
+const KRC_MAGIC: &[u8] = b"krc1";
+/// 文件头之后的数据与此密钥循环异或，再 zlib 解压
+const KRC_KEY: [u8; 16] = [0x40, 0x47, 0x61, 0x77, 0x5e, 0x32, 0x74, 0x47, 0x51, 0x36, 0x31, 0x2d, 0xce, 0xd2, 0x6e, 0x69];
+
+/// 解析后的 KRC 歌词
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct KrcLyrics {
+    pub lines: Vec<QrcLine>,
+    /// (行开始毫秒, 翻译)，来自 `[language:]` 标签
+    pub trans: Vec<(u64, String)>,
+}
+
+/// 解密 KRC 文件：`krc1` 文件头 + 循环异或 + zlib
+pub fn decode_krc(raw: &[u8]) -> Result<String> {
+    let Some(data) = raw.strip_prefix(KRC_MAGIC) else {
+        bail!("Not a KRC file (missing krc1 header)");
+    };
+    let xored: Vec<u8> = data.iter().enumerate().map(|(i, b)| b ^ KRC_KEY[i % KRC_KEY.len()]).collect();
+    let mut text = String::new();
+    ZlibDecoder::new(xored.as_slice())
+        .read_to_string(&mut text)
+        .context("KRC zlib decompression failed")?;
+    Ok(text.trim_start_matches('\u{feff}').to_string())
+}
+
+/// 酷狗下载接口返回的 KRC 为 Base64 编码
+pub fn decode_krc_base64(content: &str) -> Result<String> {
+    let raw = STANDARD.decode(content.trim()).context("Failed to decode KRC Base64")?;
+    decode_krc(&raw)
+}
+
+/// 读取并解密本地 KRC 文件
+pub fn decode_krc_from_file(path: &Path) -> Result<String> {
+    let raw = std::fs::read(path).context(format!("Failed to read KRC file: {:?}", path))?;
+    decode_krc(&raw)
+}
+
+/// 解析解密后的 KRC 文本：`[行开始,行时长]<字偏移,字时长,0>字<…>字…`。
+///
+/// 与 QRC 不同，KRC 的字时间是相对行开始的偏移，这里换算为绝对时间，与 `QrcWord` 约定一致。
+/// `[ar:]` 等标签行与没有文字的行被跳过；`[language:]` 中的翻译按行序对应到歌词行。
+pub fn parse_krc(text: &str) -> KrcLyrics {
+    let mut lines = Vec::new();
+    let mut translations = Vec::new();
+    for raw_line in text.lines() {
+        let line = raw_line.trim();
+        if let Some(value) = line.strip_prefix("[language:").and_then(|l| l.strip_suffix(']')) {
+            translations = parse_language_tag(value);
+        } else if let Some(parsed) = parse_krc_line(line) {
+            lines.push(parsed);
+        }
+    }
+
+    let trans = lines
+        .iter()
+        .zip(translations)
+        .filter(|(_, text)| !text.trim().is_empty())
+        .map(|(line, text)| (line.start_time_ms, text))
+        .collect();
+    KrcLyrics { lines, trans }
+}
+
+fn parse_krc_line(line: &str) -> Option<QrcLine> {
+    let (header, mut body) = line.strip_prefix('[')?.split_once(']')?;
+    let (start, duration) = header.split_once(',')?;
+    let start_time_ms: u64 = start.trim().parse().ok()?;
+    let duration_ms = duration.trim().parse().ok()?;
+
+    let mut words = Vec::new();
+    while let Some(((offset, word_duration), rest)) = take_timing(body) {
+        // 字的文本一直到下一个时间标记；文本本身可能含尖括号
+        let end = rest
+            .char_indices()
+            .find(|(i, c)| *c == '<' && take_timing(&rest[*i..]).is_some())
+            .map_or(rest.len(), |(i, _)| i);
+        words.push(QrcWord { content: rest[..end].to_string(), start_time_ms: start_time_ms.saturating_add(offset), duration_ms: word_duration });
+        body = &rest[end..];
+    }
+
+    let content: String = words.iter().map(|w| w.content.as_str()).collect();
+    if content.trim().is_empty() {
+        return None;
+    }
+    Some(QrcLine { content, start_time_ms, duration_ms, words })
+}
+
+/// 解析开头的 `<偏移,时长,0>` 时间标记，返回时间与剩余文本
+fn take_timing(s: &str) -> Option<((u64, u64), &str)> {
+    let inner_len = s.strip_prefix('<')?.find('>')?;
+    let parts: Vec<&str> = s[1..1 + inner_len].split(',').collect();
+    if parts.len() != 3 || !parts.iter().all(|p| p.trim().parse::<u64>().is_ok()) {
+        return None;
+    }
+    let offset = parts[0].trim().parse().ok()?;
+    let duration = parts[1].trim().parse().ok()?;
+    Some(((offset, duration), &s[inner_len + 2..]))
+}
+
+/// `[language:]` 的值为 Base64 JSON：`content` 中 `type == 1` 的条目为翻译，
+/// `lyricContent` 每行一个数组（翻译只有一个元素）；`type == 0` 为逐字音译，不使用。
+fn parse_language_tag(value: &str) -> Vec<String> {
+    let Some(json) = STANDARD.decode(value.trim()).ok().and_then(|bytes| serde_json::from_slice::<Value>(&bytes).ok()) else {
+        return Vec::new();
+    };
+    let Some(content) = json["content"].as_array() else {
+        return Vec::new();
+    };
+    content
+        .iter()
+        .find(|c| c["type"].as_i64() == Some(1))
+        .and_then(|c| c["lyricContent"].as_array())
+        .map(|rows| {
+            rows.iter()
+                .map(|row| row.as_array().map(|parts| parts.iter().filter_map(Value::as_str).collect::<String>()).unwrap_or_default())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE_KRC: &[u8] = include_bytes!("../tests/fixtures/kugou_sample.krc");
+
+    #[test]
+    fn test_decode_krc() {
+        let text = decode_krc(SAMPLE_KRC).unwrap();
+        assert!(text.starts_with("[id:$00000000]"));
+        assert!(text.contains("[ti:那时雨]"));
+
+        // 下载接口返回的 Base64 形式
+        assert_eq!(decode_krc_base64(&STANDARD.encode(SAMPLE_KRC)).unwrap(), text);
+
+        assert!(decode_krc(b"not a krc file").is_err());
+        assert!(decode_krc(b"krc1\x00\x01\x02").is_err());
+    }
+
+    #[test]
+    fn test_parse_krc() {
+        let krc = parse_krc(&decode_krc(SAMPLE_KRC).unwrap());
+        assert_eq!(krc.lines.len(), 3);
+
+        let line = &krc.lines[0];
+        assert_eq!((line.start_time_ms, line.duration_ms), (16210, 3460));
+        assert_eq!(line.content, "雨落在窗前");
+        // 字偏移换算为绝对时间
+        assert_eq!(line.words[3], QrcWord { content: "窗前".to_string(), start_time_ms: 17810, duration_ms: 1860 });
+
+        // 文本中的尖括号不是时间标记
+        assert_eq!(krc.lines[2].content, "落<雨>落");
+
+        // 下载数据中越界的时间不会溢出
+        let line = parse_krc_line("[18446744073709551615,1]<1,1,0>x").unwrap();
+        assert_eq!(line.words[0].start_time_ms, u64::MAX);
+        assert_eq!(krc.lines[2].words.len(), 2);
+
+        assert_eq!(krc.trans, [(16210, "rain on the window".to_string()), (20000, "rain falls again".to_string())]);
+
+        assert!(parse_krc("[ar:徐良]\n[100,abc]<0,50,0>x\n[200,100]<0,50,0> ").lines.is_empty());
+    }
+}
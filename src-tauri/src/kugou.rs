@@ -0,0 +1,259 @@
+use anyhow::{bail, Context, Result};
+use reqwest::{Client, Url};
+use serde_json::Value;
+
+use crate::krc;
+use crate::lyric_provider::{LyricCandidate, LyricProvider, ProviderLyrics};
+use crate::lyrics_service::BoxFuture;
+use crate::song_match::{self, LyricsQuery, SongFields};
+use crate::qrc::lines_to_lrc;
+
+const SEARCH_URL: &str = "https://krcs.kugou.com/search";
+const DOWNLOAD_URL: &str = "https://lyrics.kugou.com/download";
+
+/// 酷狗歌词搜索返回的候选
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct KugouCandidate {
+    pub id: String,
+    pub accesskey: String,
+    pub song: String,
+    pub singer: String,
+    pub duration_ms: u64,
+}
+
+/// 酷狗音乐：krcs 按关键词与时长搜索歌词 + download 下载 KRC（逐字，可附带翻译）。
+/// 粤语歌常有逐字 KRC，而 QQ 音乐只有 LRC。
+pub struct KugouProvider {
+    client: Client,
+    search_url: String,
+    download_url: String,
+    debug: bool,
+}
+
+impl KugouProvider {
+    pub fn new(debug: bool) -> Self {
+        Self::with_urls(SEARCH_URL, DOWNLOAD_URL, debug)
+    }
+
+    /// 指定搜索与下载接口地址（测试时指向本地服务）
+    fn with_urls(search_url: &str, download_url: &str, debug: bool) -> Self {
+        Self {
+            client: Client::builder()
+                .user_agent("Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/131.0.0.0 Safari/537.36")
+                .build()
+                .unwrap_or_default(),
+            search_url: search_url.to_string(),
+            download_url: download_url.to_string(),
+            debug,
+        }
+    }
+
+    async fn get_json(&self, base: &str, params: &[(&str, &str)]) -> Result<Value> {
+        let url = Url::parse_with_params(base, params)?;
+        let resp: Value = self.client.get(url)
+            .send()
+            .await
+            .context("Kugou request failed")?
+            .json()
+            .await
+            .context("Failed to parse Kugou JSON")?;
+        match resp["status"].as_i64() {
+            Some(200) => Ok(resp),
+            status => bail!("Kugou API returned status {:?}", status),
+        }
+    }
+
+    pub async fn search(&self, keyword: &str, duration_ms: u64) -> Result<Vec<KugouCandidate>> {
+        let duration = duration_ms.to_string();
+        let params = [("ver", "1"), ("man", "yes"), ("client", "mobi"), ("keyword", keyword), ("duration", duration.as_str()), ("hash", "")];
+        let resp = self.get_json(&self.search_url, &params).await?;
+        Ok(parse_search(&resp))
+    }
+
+    /// 下载并解密 KRC
+    pub async fn download(&self, candidate: &KugouCandidate) -> Result<krc::KrcLyrics> {
+        let params = [
+            ("ver", "1"),
+            ("client", "pc"),
+            ("id", candidate.id.as_str()),
+            ("accesskey", candidate.accesskey.as_str()),
+            ("fmt", "krc"),
+            ("charset", "utf8"),
+        ];
+        let resp = self.get_json(&self.download_url, &params).await?;
+        parse_download(&resp)
+    }
+}
+
+impl LyricProvider for KugouProvider {
+    fn id(&self) -> &'static str {
+        "kugou"
+    }
+
+    fn fetch<'a>(&'a self, query: &'a LyricsQuery) -> BoxFuture<'a, Result<Option<ProviderLyrics>>> {
+        Box::pin(async move {
+            // "artist - title" 搜不到时只用标题再搜一次
+            let mut candidate = None;
+            for keyword in [format!("{} - {}", query.artist, query.title), query.title.clone()] {
+                let candidates = self.search(&keyword, query.duration_ms).await?;
+                if let Some(found) = pick_candidate(&candidates, query) {
+                    candidate = Some(found.clone());
+                    break;
+                }
+            }
+            let Some(candidate) = candidate else {
+                return Ok(None);
+            };
+            if self.debug { eprintln!("[kugou] Matched '{} - {}' (id={})", candidate.singer, candidate.song, candidate.id); }
+
+            Ok(krc_to_lyrics(self.download(&candidate).await?))
+        })
+    }
+
+    fn search<'a>(&'a self, query: &'a LyricsQuery) -> BoxFuture<'a, Result<Vec<LyricCandidate>>> {
+        Box::pin(async move {
+            let candidates = self.search(&format!("{} - {}", query.artist, query.title), query.duration_ms).await?;
+            Ok(candidates
+                .iter()
+                .map(|c| LyricCandidate::song(self.id(), c.pin_id(), &c.song, &c.singer, "", c.duration_ms))
+                .collect())
+        })
+    }
+
+    fn fetch_by_id<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<Option<ProviderLyrics>>> {
+        Box::pin(async move {
+            let Some((id, accesskey)) = id.split_once(':') else {
+                bail!("Invalid Kugou lyric id '{}', expected id:accesskey", id);
+            };
+            let candidate = KugouCandidate { id: id.to_string(), accesskey: accesskey.to_string(), ..KugouCandidate::default() };
+            Ok(krc_to_lyrics(self.download(&candidate).await?))
+        })
+    }
+}
+
+impl KugouCandidate {
+    /// 固定匹配使用的 id：下载需要 id 与 accesskey 两者
+    pub fn pin_id(&self) -> String {
+        format!("{}:{}", self.id, self.accesskey)
+    }
+}
+
+fn krc_to_lyrics(krc: krc::KrcLyrics) -> Option<ProviderLyrics> {
+    let lyrics = ProviderLyrics {
+        provider: "kugou",
+        lyrics: lines_to_lrc(krc.lines.iter().map(|l| (l.start_time_ms, l.content.as_str()))),
+        trans: lines_to_lrc(krc.trans.iter().map(|(ms, text)| (*ms, text.as_str()))),
+        qrc_data: krc.lines,
+        ..ProviderLyrics::default()
+    };
+    lyrics.has_lyrics().then_some(lyrics)
+}
+
+/// 解析 krcs 搜索响应
+pub fn parse_search(resp: &Value) -> Vec<KugouCandidate> {
+    let Some(candidates) = resp["candidates"].as_array() else {
+        return Vec::new();
+    };
+    candidates
+        .iter()
+        .filter_map(|c| {
+            // id 在不同版本的接口中可能是字符串或数字
+            let id = match &c["id"] {
+                Value::String(s) => s.clone(),
+                Value::Number(n) => n.to_string(),
+                _ => return None,
+            };
+            Some(KugouCandidate {
+                id,
+                accesskey: c["accesskey"].as_str()?.to_string(),
+                song: c["song"].as_str().unwrap_or_default().to_string(),
+                singer: c["singer"].as_str().unwrap_or_default().to_string(),
+                duration_ms: c["duration"].as_u64().unwrap_or(0),
+            })
+        })
+        .collect()
+}
+
+/// 解析 download 响应：`content` 为 Base64 编码的 KRC 文件，为空时没有歌词
+pub fn parse_download(resp: &Value) -> Result<krc::KrcLyrics> {
+    let content = resp["content"].as_str().unwrap_or_default();
+    if content.is_empty() {
+        return Ok(krc::KrcLyrics::default());
+    }
+    Ok(krc::parse_krc(&krc::decode_krc_base64(content)?))
+}
+
+/// 从候选中选出同一首歌中匹配度最高的一条（见 `song_match::pick_best`）；
+/// 搜索结果没有专辑，按标题、歌手与时长打分。
+pub fn pick_candidate<'a>(candidates: &'a [KugouCandidate], query: &LyricsQuery) -> Option<&'a KugouCandidate> {
+    song_match::pick_best(candidates, query, |c| SongFields {
+        title: &c.song,
+        artists: std::slice::from_ref(&c.singer),
+        duration_ms: c.duration_ms,
+        ..SongFields::default()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::song_query;
+
+    const SEARCH_FIXTURE: &str = include_str!("../tests/fixtures/kugou_search.json");
+    /// 按 download 接口的响应格式构造，`content` 为 kugou_sample.krc 的 Base64
+    const DOWNLOAD_FIXTURE: &str = include_str!("../tests/fixtures/kugou_download.json");
+
+
+    #[test]
+    fn test_parse_search_and_pick() {
+        let candidates = parse_search(&serde_json::from_str(SEARCH_FIXTURE).unwrap());
+        assert_eq!(candidates.len(), 3);
+        assert_eq!(candidates[0].id, "20114528");
+        assert_eq!(candidates[2].id, "20114530");
+
+        // 时长接近的版本优先于排在前面的现场版；时长未知时取第一个匹配；混音版标题不一致
+        assert_eq!(pick_candidate(&candidates, &song_query(253_000)).map(|c| c.id.as_str()), Some("20114529"));
+        assert_eq!(pick_candidate(&candidates, &song_query(0)).map(|c| c.id.as_str()), Some("20114528"));
+        let other_artist = LyricsQuery { artist: "群星".to_string(), ..song_query(0) };
+        assert!(pick_candidate(&candidates, &other_artist).is_none());
+
+        assert!(parse_search(&serde_json::json!({ "status": 200, "candidates": [] })).is_empty());
+    }
+
+    /// 本地服务按接口格式返回搜索与下载响应，走完 fetch 的搜索 → 选择 → 下载 → Base64 → 解密 → 解析
+    #[tokio::test]
+    async fn test_fetch_end_to_end() {
+        use axum::{extract::Query, routing::get, Json, Router};
+        use std::collections::HashMap;
+
+        let app = Router::new()
+            .route("/search", get(|| async { Json(serde_json::from_str::<Value>(SEARCH_FIXTURE).unwrap()) }))
+            .route("/download", get(|Query(params): Query<HashMap<String, String>>| async move {
+                let wanted = params.get("id").map(String::as_str) == Some("20114529")
+                    && params.get("accesskey").map(String::as_str) == Some("0F1E2D3C4B5A69788796A5B4C3D2E1F0");
+                if wanted {
+                    Json(serde_json::from_str::<Value>(DOWNLOAD_FIXTURE).unwrap())
+                } else {
+                    Json(serde_json::json!({ "status": 0, "info": "no lyrics", "error_code": 404 }))
+                }
+            }));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await });
+        let provider = KugouProvider::with_urls(&format!("http://{}/search", addr), &format!("http://{}/download", addr), false);
+
+        let lyrics = provider.fetch(&song_query(253_000)).await.unwrap().unwrap();
+        assert_eq!(lyrics.provider, "kugou");
+        assert_eq!(lyrics.qrc_data.len(), 3);
+        assert_eq!(lyrics.qrc_data[0].content, "雨落在窗前");
+        assert!(lyrics.lyrics.starts_with("[00:16.21]雨落在窗前"));
+        assert!(lyrics.trans.starts_with("[00:16.21]rain on the window"));
+
+        // 固定匹配按 id:accesskey 下载同一份歌词；接口报错时返回错误
+        let pinned = provider.fetch_by_id("20114529:0F1E2D3C4B5A69788796A5B4C3D2E1F0").await.unwrap();
+        assert_eq!(pinned, Some(lyrics));
+        assert!(provider.fetch_by_id("20114528:A1B2C3D4E5F60718293A4B5C6D7E8F90").await.is_err());
+
+        assert_eq!(parse_download(&serde_json::json!({ "status": 200, "content": "" })).unwrap(), krc::KrcLyrics::default());
+    }
+}
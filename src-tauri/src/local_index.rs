@@ -0,0 +1,441 @@
+use anyhow::{Context, Result};
+use notify::{Event, RecommendedWatcher, RecursiveMode, Watcher};
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+
+use crate::local_qrc::{self, LyricFileName};
+use crate::song_match::{LyricsQuery, NormalizedQuery};
+
+/// 本地歌词缓存文件类型（只索引主歌词，翻译文件按主文件名推导）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LyricFileKind {
+    Qrc,
+    Lrc,
+}
+
+impl LyricFileKind {
+    pub fn suffix(self) -> &'static str {
+        match self {
+            LyricFileKind::Qrc => "_qm.qrc",
+            LyricFileKind::Lrc => "_qm.lrc",
+        }
+    }
+
+    fn of(file_name: &str) -> Option<Self> {
+        [LyricFileKind::Qrc, LyricFileKind::Lrc].into_iter().find(|kind| file_name.ends_with(kind.suffix()))
+    }
+}
+
+/// 索引中的一个歌词文件
+#[derive(Debug, Clone, PartialEq)]
+pub struct IndexedLyricFile {
+    pub file_name: String,
+    /// 文件名中解析出的原始字段
+    pub parsed: LyricFileName,
+    /// 归一化后的字段，用于打分
+    normalized: LyricFileName,
+}
+
+/// 一种歌词文件的索引：按路径有序保存（同分时结果稳定），另按归一化标题分桶
+#[derive(Debug, Default)]
+struct LyricFiles {
+    files: BTreeMap<PathBuf, IndexedLyricFile>,
+    by_title: HashMap<String, Vec<PathBuf>>,
+}
+
+impl LyricFiles {
+    fn insert(&mut self, path: PathBuf, file_name: &str) {
+        self.remove(&path);
+        let parsed = local_qrc::parse_lyric_filename(file_name);
+        let normalized = parsed.normalized();
+        if normalized.title.is_empty() {
+            return;
+        }
+        self.by_title.entry(normalized.title.clone()).or_default().push(path.clone());
+        self.files.insert(path, IndexedLyricFile { file_name: file_name.to_string(), parsed, normalized });
+    }
+
+    fn remove(&mut self, path: &Path) {
+        let Some(old) = self.files.remove(path) else {
+            return;
+        };
+        if let Some(paths) = self.by_title.get_mut(&old.normalized.title) {
+            paths.retain(|p| p != path);
+            if paths.is_empty() {
+                self.by_title.remove(&old.normalized.title);
+            }
+        }
+    }
+
+    /// 先在标题完全一致的桶中找，桶中最佳得分高于标题部分匹配可能达到的最高分时直接采用；
+    /// 否则对全部条目打分（仍在内存中，不读目录），结果与逐个扫描目录相同。
+    /// 同分时取路径较小的一个，结果不依赖目录遍历顺序。
+    fn find(&self, query: &LyricsQuery) -> Option<PathBuf> {
+        let query = NormalizedQuery::new(query);
+        if let Some((path, score)) = self.by_title.get(&query.title).and_then(|paths| self.best(paths.iter(), &query)) {
+            if score > local_qrc::MAX_PARTIAL_TITLE_SCORE {
+                return Some(path.clone());
+            }
+        }
+        self.best(self.files.keys(), &query).map(|(path, _)| path.clone())
+    }
+
+    fn best<'a>(&self, paths: impl Iterator<Item = &'a PathBuf>, query: &NormalizedQuery) -> Option<(&'a PathBuf, u32)> {
+        let mut best: Option<(&PathBuf, u32)> = None;
+        for path in paths {
+            let Some(score) = self.files.get(path).and_then(|f| local_qrc::score_normalized(&f.normalized, query)) else {
+                continue;
+            };
+            let better = match best {
+                Some((best_path, best_score)) => score > best_score || (score == best_score && path < best_path),
+                None => score > 0,
+            };
+            if better {
+                best = Some((path, score));
+            }
+        }
+        best
+    }
+}
+
+/// 某个 album_mid 的本地封面
+#[derive(Debug, Clone, Default, PartialEq)]
+struct AlbumPics {
+    pic_500: Option<PathBuf>,
+    pic_150: Option<PathBuf>,
+}
+
+#[derive(Debug, Default)]
+struct IndexData {
+    qrc: LyricFiles,
+    lrc: LyricFiles,
+    pictures: HashMap<String, AlbumPics>,
+}
+
+impl IndexData {
+    fn lyric_files(&self, kind: LyricFileKind) -> &LyricFiles {
+        match kind {
+            LyricFileKind::Qrc => &self.qrc,
+            LyricFileKind::Lrc => &self.lrc,
+        }
+    }
+
+    fn lyric_files_mut(&mut self, kind: LyricFileKind) -> &mut LyricFiles {
+        match kind {
+            LyricFileKind::Qrc => &mut self.qrc,
+            LyricFileKind::Lrc => &mut self.lrc,
+        }
+    }
+
+    fn scan_lyric_dir(&mut self, dir: &Path) {
+        self.qrc = LyricFiles::default();
+        self.lrc = LyricFiles::default();
+        for path in read_dir_paths(dir) {
+            self.update_lyric_file(&path);
+        }
+    }
+
+    fn scan_picture_dir(&mut self, dir: &Path) {
+        self.pictures.clear();
+        for path in read_dir_paths(dir) {
+            self.update_picture(&path);
+        }
+    }
+
+    /// 文件存在则（重新）登记，不存在则移除
+    fn update_lyric_file(&mut self, path: &Path) {
+        let Some(file_name) = path.file_name().and_then(|n| n.to_str()) else {
+            return;
+        };
+        let Some(kind) = LyricFileKind::of(file_name) else {
+            return;
+        };
+        let files = self.lyric_files_mut(kind);
+        if path.is_file() {
+            files.insert(path.to_path_buf(), file_name);
+        } else {
+            files.remove(path);
+        }
+    }
+
+    fn update_picture(&mut self, path: &Path) {
+        let Some((album_mid, large)) = path.file_name().and_then(|n| n.to_str()).and_then(parse_picture_name) else {
+            return;
+        };
+        let exists = path.is_file();
+        let pics = self.pictures.entry(album_mid.to_string()).or_default();
+        let slot = if large { &mut pics.pic_500 } else { &mut pics.pic_150 };
+        if exists {
+            // 同一 mid 可能有多张（不同 seq），保留已有的那张
+            slot.get_or_insert_with(|| path.to_path_buf());
+        } else if slot.as_deref() == Some(path) {
+            *slot = None;
+        }
+        if pics.pic_500.is_none() && pics.pic_150.is_none() {
+            self.pictures.remove(album_mid);
+        }
+    }
+}
+
+/// 解析本地封面文件名 `T002R{W}x{H}M000{album_mid}_{seq}.jpg`，返回 (album_mid, 是否 500x500)；
+/// 只识别 500x500 与 150x150 两种尺寸
+fn parse_picture_name(file_name: &str) -> Option<(&str, bool)> {
+    let rest = file_name.strip_suffix(".jpg")?;
+    let (rest, large) = match rest.strip_prefix("T002R500x500M000") {
+        Some(rest) => (rest, true),
+        None => (rest.strip_prefix("T002R150x150M000")?, false),
+    };
+    let album_mid = rest.split('_').next().unwrap_or_default();
+    (!album_mid.is_empty()).then_some((album_mid, large))
+}
+
+fn read_dir_paths(dir: &Path) -> Vec<PathBuf> {
+    std::fs::read_dir(dir)
+        .map(|entries| entries.flatten().map(|e| e.path()).collect())
+        .unwrap_or_default()
+}
+
+/// QQ 音乐本地缓存（QQMusicLyricNew / QQMusicPicture）的内存索引。
+///
+/// 启动时扫描一次目录，之后由文件监听增量更新：切歌时查找歌词文件与封面不再读目录，
+/// QQ 音乐新下载的歌词也能立即被找到。
+pub struct LocalIndex {
+    lyric_dir: PathBuf,
+    picture_dir: PathBuf,
+    data: Arc<RwLock<IndexData>>,
+    _watcher: RecommendedWatcher,
+}
+
+impl LocalIndex {
+    /// 扫描目录并开始监听；歌词目录不存在或无法监听时返回错误，调用方应退回逐次扫描目录
+    pub fn open(lyric_dir: &Path, picture_dir: &Path, debug: bool) -> Result<Self> {
+        let data = Arc::new(RwLock::new(IndexData::default()));
+
+        let (watch_lyric_dir, watch_picture_dir) = (lyric_dir.to_path_buf(), picture_dir.to_path_buf());
+        let watch_data = data.clone();
+        let mut watcher = notify::recommended_watcher(move |res: notify::Result<Event>| {
+            let event = match res {
+                Ok(event) => event,
+                Err(e) => {
+                    eprintln!("⚠️  本地缓存监听出错: {}", e);
+                    return;
+                }
+            };
+            let Ok(mut data) = watch_data.write() else {
+                return;
+            };
+            // 事件丢失（缓冲区溢出等）时整体重扫
+            if event.need_rescan() {
+                data.scan_lyric_dir(&watch_lyric_dir);
+                data.scan_picture_dir(&watch_picture_dir);
+                if debug { eprintln!("[index] Rescanned local cache"); }
+                return;
+            }
+            for path in &event.paths {
+                match path.parent() {
+                    Some(parent) if parent == watch_lyric_dir => data.update_lyric_file(path),
+                    Some(parent) if parent == watch_picture_dir => data.update_picture(path),
+                    _ => {}
+                }
+            }
+        })
+        .context("Failed to create file watcher")?;
+
+        // 先监听再扫描：扫描期间新建的文件也不会漏掉
+        watcher
+            .watch(lyric_dir, RecursiveMode::NonRecursive)
+            .with_context(|| format!("Failed to watch {:?}", lyric_dir))?;
+        // 封面目录可能尚未创建，监听失败时只是不索引封面
+        let pictures_watched = watcher.watch(picture_dir, RecursiveMode::NonRecursive).is_ok();
+
+        {
+            let mut data = data.write().unwrap_or_else(|e| e.into_inner());
+            data.scan_lyric_dir(lyric_dir);
+            if pictures_watched {
+                data.scan_picture_dir(picture_dir);
+            }
+            if debug {
+                eprintln!("[index] Indexed {} QRC, {} LRC, {} album pictures",
+                    data.qrc.files.len(), data.lrc.files.len(), data.pictures.len());
+            }
+        }
+
+        Ok(Self {
+            lyric_dir: lyric_dir.to_path_buf(),
+            picture_dir: picture_dir.to_path_buf(),
+            data,
+            _watcher: watcher,
+        })
+    }
+
+    pub fn lyric_dir(&self) -> &Path {
+        &self.lyric_dir
+    }
+
+    pub fn picture_dir(&self) -> &Path {
+        &self.picture_dir
+    }
+
+    fn read(&self) -> std::sync::RwLockReadGuard<'_, IndexData> {
+        self.data.read().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// 与 `local_qrc::find_qrc_file` / `find_lrc_file` 相同的匹配规则
+    pub fn find_lyric_file(&self, kind: LyricFileKind, query: &LyricsQuery) -> Option<PathBuf> {
+        self.read().lyric_files(kind).find(query)
+    }
+
+    /// 与 `local_qrc::find_album_pic` 相同：500x500 优先，回退 150x150
+    pub fn find_album_pic(&self, album_mid: &str) -> Option<PathBuf> {
+        let data = self.read();
+        let pics = data.pictures.get(album_mid)?;
+        pics.pic_500.clone().or_else(|| pics.pic_150.clone())
+    }
+
+    pub fn lyric_file_count(&self, kind: LyricFileKind) -> usize {
+        self.read().lyric_files(kind).files.len()
+    }
+
+    /// 按路径顺序遍历某类歌词文件，供诊断输出使用
+    pub fn for_each_lyric_file(&self, kind: LyricFileKind, mut f: impl FnMut(&str, &LyricFileName)) {
+        for file in self.read().lyric_files(kind).files.values() {
+            f(&file.file_name, &file.parsed);
+        }
+    }
+}
+
+/// 全局索引覆盖该目录时返回索引，否则调用方退回直接读目录
+fn index_for(lyric_dir: Option<&Path>, picture_dir: Option<&Path>) -> Option<&'static LocalIndex> {
+    crate::local_index().filter(|index| {
+        !matches!(lyric_dir, Some(dir) if dir != index.lyric_dir())
+            && !matches!(picture_dir, Some(dir) if dir != index.picture_dir())
+    })
+}
+
+/// 在歌词缓存目录中查找主歌词文件：有索引时查索引，否则扫描目录
+pub fn find_lyric_file(lyric_dir: &Path, kind: LyricFileKind, query: &LyricsQuery) -> Option<PathBuf> {
+    if let Some(index) = index_for(Some(lyric_dir), None) {
+        return index.find_lyric_file(kind, query);
+    }
+    match kind {
+        LyricFileKind::Qrc => local_qrc::find_qrc_file(lyric_dir, query),
+        LyricFileKind::Lrc => local_qrc::find_lrc_file(lyric_dir, query),
+    }
+}
+
+/// 在封面缓存目录中查找 album_mid 对应的本地封面：有索引时查索引，否则扫描目录
+pub fn find_album_pic(picture_dir: &Path, album_mid: &str) -> Option<PathBuf> {
+    if let Some(index) = index_for(None, Some(picture_dir)) {
+        return index.find_album_pic(album_mid);
+    }
+    local_qrc::find_album_pic(picture_dir, album_mid)
+}
+
+/// 遍历歌词缓存目录中的某类歌词文件（文件名, 解析结果）：有索引时遍历索引，否则读目录
+pub fn for_each_lyric_file(lyric_dir: &Path, kind: LyricFileKind, mut f: impl FnMut(&str, &LyricFileName)) {
+    if let Some(index) = index_for(Some(lyric_dir), None) {
+        index.for_each_lyric_file(kind, f);
+        return;
+    }
+    for path in read_dir_paths(lyric_dir) {
+        if let Some(file_name) = path.file_name().and_then(|n| n.to_str()).filter(|n| n.ends_with(kind.suffix())) {
+            f(file_name, &local_qrc::parse_lyric_filename(file_name));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{song_query, temp_dir};
+
+
+    #[test]
+    fn test_index_matches_directory_scan() {
+        let dir = temp_dir("local_index_lyrics");
+        for name in [
+            "徐良 - 那时雨 - 253 - 那时雨_qm.qrc",
+            "徐良 - 那时雨 - 281 - 徐良 2012 演唱会_qm.qrc",
+            "徐良 - 那时雨 - 253 - 那时雨_qmts.qrc",
+            "群星 - 那时雨 (伴奏) - 253 - 合辑_qm.lrc",
+            "说明.txt",
+        ] {
+            std::fs::write(dir.join(name), b"").unwrap();
+        }
+        let mut data = IndexData::default();
+        data.scan_lyric_dir(&dir);
+        assert_eq!(data.qrc.files.len(), 2);
+        assert_eq!(data.lrc.files.len(), 1);
+
+        for q in [song_query(253_000), song_query(281_000)] {
+            assert_eq!(data.qrc.find(&q), local_qrc::find_qrc_file(&dir, &q));
+            assert_eq!(data.lrc.find(&q), local_qrc::find_lrc_file(&dir, &q));
+        }
+        assert_eq!(data.qrc.find(&song_query(281_000)), Some(dir.join("徐良 - 那时雨 - 281 - 徐良 2012 演唱会_qm.qrc")));
+        // 标题桶中歌手不匹配时仍能命中部分匹配的标题
+        assert_eq!(data.lrc.find(&song_query(0)), Some(dir.join("群星 - 那时雨 (伴奏) - 253 - 合辑_qm.lrc")));
+
+        // 监听事件：删除后不再命中，新下载的文件立即可见
+        let live = dir.join("徐良 - 那时雨 - 281 - 徐良 2012 演唱会_qm.qrc");
+        std::fs::remove_file(&live).unwrap();
+        data.update_lyric_file(&live);
+        assert_eq!(data.qrc.find(&song_query(281_000)), Some(dir.join("徐良 - 那时雨 - 253 - 那时雨_qm.qrc")));
+        let new_file = dir.join("徐良 - 不写完 - 200 - 不写完_qm.qrc");
+        std::fs::write(&new_file, b"").unwrap();
+        data.update_lyric_file(&new_file);
+        let q = LyricsQuery { title: "不写完".to_string(), ..song_query(0) };
+        assert_eq!(data.qrc.find(&q), Some(new_file));
+        assert_eq!(data.qrc.by_title.len(), 2);
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn test_partial_title_can_beat_title_bucket() {
+        let dir = temp_dir("local_index_partial_title");
+        let exact = dir.join("徐良 - 那时雨 - 100 - 别的专辑_qm.qrc");
+        let live = dir.join("徐良 - 那时雨 (Live) - 253 - 那时雨_qm.qrc");
+        std::fs::write(&exact, b"").unwrap();
+        std::fs::write(&live, b"").unwrap();
+        let mut data = IndexData::default();
+        data.scan_lyric_dir(&dir);
+
+        // 标题一致但时长、专辑都不对（150 分）不如标题部分匹配而其余都对（180 分）
+        let q = song_query(253_000);
+        assert_eq!(data.qrc.find(&q), Some(live));
+        assert_eq!(data.qrc.find(&q), local_qrc::find_qrc_file(&dir, &q));
+        // 标题一致且其余字段足够好时走标题桶
+        let q = LyricsQuery { album: "别的专辑".to_string(), ..song_query(100_000) };
+        assert_eq!(data.qrc.find(&q), Some(exact));
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn test_picture_index() {
+        assert_eq!(parse_picture_name("T002R500x500M000003kQTm81fCMvb_1.jpg"), Some(("003kQTm81fCMvb", true)));
+        assert_eq!(parse_picture_name("T002R150x150M000003kQTm81fCMvb.jpg"), Some(("003kQTm81fCMvb", false)));
+        assert_eq!(parse_picture_name("T002R300x300M000003kQTm81fCMvb.jpg"), None);
+
+        let dir = temp_dir("local_index_pictures");
+        let small = dir.join("T002R150x150M000003kQTm81fCMvb_1.jpg");
+        let large = dir.join("T002R500x500M000003kQTm81fCMvb_1.jpg");
+        std::fs::write(&small, b"").unwrap();
+        let mut data = IndexData::default();
+        data.scan_picture_dir(&dir);
+        assert_eq!(data.pictures["003kQTm81fCMvb"].pic_150.as_ref(), Some(&small));
+
+        std::fs::write(&large, b"").unwrap();
+        data.update_picture(&large);
+        assert_eq!(data.pictures["003kQTm81fCMvb"].pic_500.as_ref(), Some(&large));
+
+        std::fs::remove_file(&large).unwrap();
+        std::fs::remove_file(&small).unwrap();
+        data.update_picture(&large);
+        data.update_picture(&small);
+        assert!(data.pictures.is_empty());
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
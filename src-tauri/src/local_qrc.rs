@@ -1,5 +1,13 @@
 use std::path::{Path, PathBuf};
 
+use crate::song_match::{normalize, LyricsQuery, NormalizedQuery, DURATION_EXACT_MS};
+
+/// 相差在此范围内视为可能是同一版本（不同来源的时长略有出入）
+const DURATION_TOLERANCE_MS: u64 = 5000;
+
+/// `score_normalized` 在标题只部分匹配时能得到的最高分（60 + 50 + 40 + 30）
+pub const MAX_PARTIAL_TITLE_SCORE: u32 = 180;
+
 /// 探测 QQ 音乐缓存根目录（如 `D:\QQMusicCache`）。
 /// 歌词在 `QQMusicLyricNew` 子目录，封面图在 `QQMusicPicture` 子目录。
 pub fn auto_detect_cache_root() -> Option<PathBuf> {
@@ -44,64 +52,8 @@ fn detect_cache_root_from_config() -> Option<PathBuf> {
 }
 
 // Function to find the main QRC lyric file for a given song
-pub fn find_qrc_file(cache_dir: &Path, title: &str, artist: &str) -> Option<PathBuf> {
-    let entries = std::fs::read_dir(cache_dir).ok()?;
-
-    // Normalize search terms for fuzzy matching
-    let norm_title = normalize(title);
-    let norm_artist = normalize(artist);
-
-    let mut best_match: Option<PathBuf> = None;
-    let mut best_score: u32 = 0;
-
-    for entry in entries.flatten() {
-        let path = entry.path();
-        let file_name = match path.file_name().and_then(|n| n.to_str()) {
-            Some(n) => n.to_string(),
-            None => continue,
-        };
-
-        // Only match _qm.qrc files (main lyrics, not translation)
-        if !file_name.ends_with("_qm.qrc") {
-            continue;
-        }
-
-        // Parse filename: "Artist - Title - Duration - Album_qm.qrc"
-        let base = file_name.trim_end_matches("_qm.qrc");
-        let parts: Vec<&str> = base.splitn(4, " - ").collect();
-        if parts.len() < 2 {
-            continue;
-        }
-
-        let file_artist = normalize(parts[0]);
-        let file_title = normalize(parts[1]);
-
-        // Score matching using contains-based logic for robustness
-        let mut score: u32 = 0;
-
-        // Exact title match is highest priority
-        if file_title == norm_title {
-            score += 100;
-        } else if file_title.contains(&norm_title) || norm_title.contains(&file_title) {
-            score += 60;
-        } else {
-            continue; // title must at least partially match
-        }
-
-        // Artist matching
-        if file_artist == norm_artist {
-            score += 50;
-        } else if file_artist.contains(&norm_artist) || norm_artist.contains(&file_artist) {
-            score += 30;
-        }
-
-        if score > best_score {
-            best_score = score;
-            best_match = Some(path);
-        }
-    }
-
-    best_match
+pub fn find_qrc_file(cache_dir: &Path, query: &LyricsQuery) -> Option<PathBuf> {
+    find_cached_lyric_file(cache_dir, "_qm.qrc", query)
 }
 
 // Function to find the translation QRC file corresponding to a main QRC file
@@ -120,12 +72,14 @@ pub fn find_qrc_trans_file(qrc_file: &Path) -> Option<PathBuf> {
 }
 
 // Function to find the main LRC lyric file for a given song
-pub fn find_lrc_file(cache_dir: &Path, title: &str, artist: &str) -> Option<PathBuf> {
-    let entries = std::fs::read_dir(cache_dir).ok()?;
+pub fn find_lrc_file(cache_dir: &Path, query: &LyricsQuery) -> Option<PathBuf> {
+    find_cached_lyric_file(cache_dir, "_qm.lrc", query)
+}
 
-    // Normalize search terms for fuzzy matching
-    let norm_title = normalize(title);
-    let norm_artist = normalize(artist);
+// Scan the cache dir for main lyric files with the given suffix and keep the best-scoring one
+fn find_cached_lyric_file(cache_dir: &Path, suffix: &str, query: &LyricsQuery) -> Option<PathBuf> {
+    let entries = std::fs::read_dir(cache_dir).ok()?;
+    let query = NormalizedQuery::new(query);
 
     let mut best_match: Option<PathBuf> = None;
     let mut best_score: u32 = 0;
@@ -137,47 +91,76 @@ pub fn find_lrc_file(cache_dir: &Path, title: &str, artist: &str) -> Option<Path
             None => continue,
         };
 
-        // Only match _qm.lrc files (main lyrics, not translation)
-        if !file_name.ends_with("_qm.lrc") {
+        // Only match main lyric files (not translation)
+        if !file_name.ends_with(suffix) {
             continue;
         }
 
-        // Parse filename: "Artist - Title - Duration - Album_qm.lrc"
-        let base = file_name.trim_end_matches("_qm.lrc");
-        let parts: Vec<&str> = base.splitn(4, " - ").collect();
-        if parts.len() < 2 {
+        let Some(score) = score_normalized(&parse_lyric_filename(&file_name).normalized(), &query) else {
             continue;
+        };
+        if score > best_score {
+            best_score = score;
+            best_match = Some(path);
         }
+    }
+
+    best_match
+}
 
-        let file_artist = normalize(parts[0]);
-        let file_title = normalize(parts[1]);
+/// 本地缓存文件与当前歌曲的匹配分数；标题连部分匹配都没有时返回 None。
+///
+/// 标题、歌手之外再比较文件名中的时长与专辑：同名的 "Song" 与 "Song (Live)"
+/// 往往来自不同专辑、时长也不同，只比标题和歌手时会取到目录中先出现的那一个。
+/// 任一侧时长或专辑未知时该项不加分。
+pub fn score_lyric_file(file: &LyricFileName, query: &LyricsQuery) -> Option<u32> {
+    score_normalized(&file.normalized(), &NormalizedQuery::new(query))
+}
 
-        // Score matching using contains-based logic for robustness
-        let mut score: u32 = 0;
+/// 与 `score_lyric_file` 相同，但两侧均已归一化（见 `LyricFileName::normalized`）
+pub fn score_normalized(file: &LyricFileName, query: &NormalizedQuery) -> Option<u32> {
+    if file.title.is_empty() {
+        return None;
+    }
 
-        // Exact title match is highest priority
-        if file_title == norm_title {
-            score += 100;
-        } else if file_title.contains(&norm_title) || norm_title.contains(&file_title) {
-            score += 60;
-        } else {
-            continue; // title must at least partially match
-        }
+    let mut score: u32 = 0;
 
-        // Artist matching
-        if file_artist == norm_artist {
-            score += 50;
-        } else if file_artist.contains(&norm_artist) || norm_artist.contains(&file_artist) {
-            score += 30;
+    // Exact title match is highest priority
+    if file.title == query.title {
+        score += 100;
+    } else if file.title.contains(&query.title) || query.title.contains(&file.title) {
+        score += 60;
+    } else {
+        return None; // title must at least partially match
+    }
+
+    // Artist matching
+    if file.artist == query.artist {
+        score += 50;
+    } else if file.artist.contains(&query.artist) || query.artist.contains(&file.artist) {
+        score += 30;
+    }
+
+    // Duration matching (filename stores whole seconds)
+    if file.duration_secs > 0 && query.duration_ms > 0 {
+        let diff_ms = (file.duration_secs * 1000).abs_diff(query.duration_ms);
+        if diff_ms <= DURATION_EXACT_MS {
+            score += 40;
+        } else if diff_ms <= DURATION_TOLERANCE_MS {
+            score += 20;
         }
+    }
 
-        if score > best_score {
-            best_score = score;
-            best_match = Some(path);
+    // Album matching
+    if !file.album.is_empty() && !query.album.is_empty() {
+        if file.album == query.album {
+            score += 30;
+        } else if file.album.contains(&query.album) || query.album.contains(&file.album) {
+            score += 15;
         }
     }
 
-    best_match
+    Some(score)
 }
 
 // Function to find the translation LRC file corresponding to a main LRC file
@@ -195,34 +178,42 @@ pub fn find_lrc_trans_file(lrc_file: &Path) -> Option<PathBuf> {
     }
 }
 
-// Helper to normalize strings for fuzzy comparison.
-//
-// 将所有非字母数字字符（含标点、括号、`_`/`/`/`\\`、`(feat.` 的点和括号等）
-// 折叠为单个空格，解决 SMTC 报告 `Go Again (feat. ELYSA)` 与本地缓存文件名
-// `Go Again (feat_ ELYSA)` 因标点差异导致 contains/equal 匹配双双失败的问题。
-// CJK 字符属于 `is_alphanumeric`，故中日韩标题不受影响。
-pub fn normalize(s: &str) -> String {
-    s.to_lowercase()
-        .chars()
-        .map(|c| if c.is_alphanumeric() { c } else { ' ' })
-        .collect::<String>()
-        .split_whitespace()
-        .collect::<Vec<_>>()
-        .join(" ")
+/// QRC/LRC 缓存文件名中的各字段
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LyricFileName {
+    pub artist: String,
+    pub title: String,
+    /// 歌曲时长（秒），0 表示缺失或无法解析
+    pub duration_secs: u64,
+    pub album: String,
 }
 
 /// 从 QRC/LRC 文件名中解析出各字段。
-/// 文件名格式：`Artist - Title - Duration - Album_qm.{qrc,lrc}`
-/// 返回 `(artist, title, album)`；字段缺失时为空串。
-pub fn parse_lyric_filename(file_name: &str) -> (String, String, String) {
+/// 文件名格式：`Artist - Title - Duration - Album_qm.{qrc,lrc}`，Duration 为秒数。
+/// 字段缺失时为空串（时长为 0）。
+pub fn parse_lyric_filename(file_name: &str) -> LyricFileName {
     let base = file_name
         .trim_end_matches("_qm.qrc")
         .trim_end_matches("_qm.lrc");
     let parts: Vec<&str> = base.splitn(4, " - ").collect();
-    let artist = parts.first().copied().unwrap_or("").to_string();
-    let title = parts.get(1).copied().unwrap_or("").to_string();
-    let album = parts.get(3).copied().unwrap_or("").to_string();
-    (artist, title, album)
+    LyricFileName {
+        artist: parts.first().copied().unwrap_or("").to_string(),
+        title: parts.get(1).copied().unwrap_or("").to_string(),
+        duration_secs: parts.get(2).and_then(|d| d.trim().parse().ok()).unwrap_or(0),
+        album: parts.get(3).copied().unwrap_or("").to_string(),
+    }
+}
+
+impl LyricFileName {
+    /// 各文本字段经 `normalize` 处理后的副本
+    pub fn normalized(&self) -> Self {
+        Self {
+            artist: normalize(&self.artist),
+            title: normalize(&self.title),
+            duration_secs: self.duration_secs,
+            album: normalize(&self.album),
+        }
+    }
 }
 
 /// 在 QQ 音乐本地缓存目录中查找专辑封面图。
@@ -304,23 +295,50 @@ mod tests {
     /// 文件名解析：`Artist - Title - Duration - Album_qm.{qrc,lrc}` 各字段正确切分。
     #[test]
     fn test_parse_lyric_filename() {
-        let (artist, title, album) = parse_lyric_filename(
+        let parsed = parse_lyric_filename(
             "Glass Animals - The Other Side Of Paradise (Explicit) - 320 - How To Be A Human Being (Explicit)_qm.qrc"
         );
-        assert_eq!(artist, "Glass Animals");
-        assert_eq!(title, "The Other Side Of Paradise (Explicit)");
-        assert_eq!(album, "How To Be A Human Being (Explicit)");
+        assert_eq!(parsed.artist, "Glass Animals");
+        assert_eq!(parsed.title, "The Other Side Of Paradise (Explicit)");
+        assert_eq!(parsed.duration_secs, 320);
+        assert_eq!(parsed.album, "How To Be A Human Being (Explicit)");
 
         // LRC 后缀
-        let (a, t, al) = parse_lyric_filename("徐良 - 那时雨 - 200 - 不写完_qm.lrc");
-        assert_eq!(a, "徐良");
-        assert_eq!(t, "那时雨");
-        assert_eq!(al, "不写完");
-
-        // 缺少 album 段：返回空 album
-        let (a, t, al) = parse_lyric_filename("TYSM - Normal No More_qm.qrc");
-        assert_eq!(a, "TYSM");
-        assert_eq!(t, "Normal No More");
-        assert_eq!(al, "");
+        let parsed = parse_lyric_filename("徐良 - 那时雨 - 200 - 不写完_qm.lrc");
+        assert_eq!(parsed.artist, "徐良");
+        assert_eq!(parsed.title, "那时雨");
+        assert_eq!(parsed.duration_secs, 200);
+        assert_eq!(parsed.album, "不写完");
+
+        // 缺少 duration/album 段：返回空 album、时长 0
+        let parsed = parse_lyric_filename("TYSM - Normal No More_qm.qrc");
+        assert_eq!(parsed.artist, "TYSM");
+        assert_eq!(parsed.title, "Normal No More");
+        assert_eq!(parsed.duration_secs, 0);
+        assert_eq!(parsed.album, "");
+    }
+
+    /// 同名的录音室版与现场版来自不同专辑：按时长与专辑区分，而不是取目录中先出现的。
+    #[test]
+    fn test_score_lyric_file_duration_and_album() {
+        let studio = parse_lyric_filename("徐良 - 那时雨 - 253 - 那时雨_qm.qrc");
+        let live = parse_lyric_filename("徐良 - 那时雨 - 281 - 徐良 2012 演唱会_qm.qrc");
+        let query = LyricsQuery { title: "那时雨".to_string(), artist: "徐良".to_string(), album: "那时雨".to_string(), duration_ms: 253_400 };
+
+        let studio_score = score_lyric_file(&studio, &query).unwrap();
+        let live_score = score_lyric_file(&live, &query).unwrap();
+        assert!(studio_score > live_score);
+
+        // 只知道时长时也能区分
+        let by_duration = LyricsQuery { album: String::new(), duration_ms: 281_000, ..query.clone() };
+        assert!(score_lyric_file(&live, &by_duration).unwrap() > score_lyric_file(&studio, &by_duration).unwrap());
+
+        // 时长、专辑都未知时退化为标题 + 歌手
+        let unknown = LyricsQuery { album: String::new(), duration_ms: 0, ..query.clone() };
+        assert_eq!(score_lyric_file(&studio, &unknown), score_lyric_file(&live, &unknown));
+        assert_eq!(score_lyric_file(&studio, &unknown), Some(150));
+
+        let other = LyricsQuery { title: "不写完".to_string(), ..query };
+        assert_eq!(score_lyric_file(&studio, &other), None);
     }
 }
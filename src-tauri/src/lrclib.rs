@@ -0,0 +1,225 @@
+use anyhow::{Context, Result};
+use reqwest::{Client, StatusCode, Url};
+use serde_json::Value;
+
+use crate::lyric_provider::{LyricCandidate, LyricProvider, ProviderLyrics};
+use crate::lyrics_service::BoxFuture;
+use crate::song_match::{self, LyricsQuery, SongFields};
+
+const GET_URL: &str = "https://lrclib.net/api/get";
+const SEARCH_URL: &str = "https://lrclib.net/api/search";
+/// 按记录 id 获取：`/api/get/{id}`
+const GET_BY_ID_URL: &str = "https://lrclib.net/api/get/";
+
+/// LRCLIB 中的一条歌词记录
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LrclibRecord {
+    pub id: u64,
+    pub track_name: String,
+    pub artist_name: String,
+    pub album_name: String,
+    pub duration_ms: u64,
+    pub instrumental: bool,
+    pub plain_lyrics: String,
+    pub synced_lyrics: String,
+}
+
+/// LRCLIB（lrclib.net）：开放的社区歌词库，无需密钥。
+///
+/// 专辑与时长已知时先用 /api/get 精确查找，未命中再用 /api/search 按标题/歌手搜索。
+pub struct LrclibProvider {
+    client: Client,
+    debug: bool,
+}
+
+impl LrclibProvider {
+    pub fn new(debug: bool) -> Self {
+        Self {
+            // LRCLIB 要求客户端带可识别的 User-Agent
+            client: Client::builder()
+                .user_agent(concat!("qqmusic-monitor/", env!("CARGO_PKG_VERSION")))
+                .build()
+                .unwrap_or_default(),
+            debug,
+        }
+    }
+
+    /// 精确查找；404 表示没有这首歌
+    pub async fn get(&self, query: &LyricsQuery) -> Result<Option<LrclibRecord>> {
+        let duration = ((query.duration_ms + 500) / 1000).to_string();
+        let params = [
+            ("track_name", query.title.as_str()),
+            ("artist_name", query.artist.as_str()),
+            ("album_name", query.album.as_str()),
+            ("duration", duration.as_str()),
+        ];
+        let resp = self.client.get(Url::parse_with_params(GET_URL, params)?)
+            .send()
+            .await
+            .context("LRCLIB request failed")?;
+        if resp.status() == StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        let json: Value = resp.error_for_status()?.json().await.context("Failed to parse LRCLIB JSON")?;
+        Ok(parse_record(&json))
+    }
+
+    /// 按记录 id 获取；404 表示记录不存在
+    pub async fn get_by_id(&self, id: u64) -> Result<Option<LrclibRecord>> {
+        let resp = self.client.get(format!("{}{}", GET_BY_ID_URL, id))
+            .send()
+            .await
+            .context("LRCLIB request failed")?;
+        if resp.status() == StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        let json: Value = resp.error_for_status()?.json().await.context("Failed to parse LRCLIB JSON")?;
+        Ok(parse_record(&json))
+    }
+
+    pub async fn search(&self, query: &LyricsQuery) -> Result<Vec<LrclibRecord>> {
+        let params = [("track_name", query.title.as_str()), ("artist_name", query.artist.as_str())];
+        let json: Value = self.client.get(Url::parse_with_params(SEARCH_URL, params)?)
+            .send()
+            .await
+            .context("LRCLIB request failed")?
+            .error_for_status()?
+            .json()
+            .await
+            .context("Failed to parse LRCLIB JSON")?;
+        Ok(parse_search(&json))
+    }
+}
+
+impl LyricProvider for LrclibProvider {
+    fn id(&self) -> &'static str {
+        "lrclib"
+    }
+
+    fn fetch<'a>(&'a self, query: &'a LyricsQuery) -> BoxFuture<'a, Result<Option<ProviderLyrics>>> {
+        Box::pin(async move {
+            // /api/get 要求提供专辑与时长，缺少时直接搜索
+            let mut record = None;
+            if !query.album.is_empty() && query.duration_ms > 0 {
+                record = self.get(query).await?.filter(|r| r.has_lyrics());
+            }
+            if record.is_none() {
+                let records = self.search(query).await?;
+                record = pick_record(&records, query).cloned();
+            }
+            let Some(record) = record else {
+                return Ok(None);
+            };
+            if self.debug { eprintln!("[lrclib] Matched '{} - {}' (id={})", record.artist_name, record.track_name, record.id); }
+            Ok(record.into_lyrics())
+        })
+    }
+
+    fn search<'a>(&'a self, query: &'a LyricsQuery) -> BoxFuture<'a, Result<Vec<LyricCandidate>>> {
+        Box::pin(async move {
+            let records = self.search(query).await?;
+            Ok(records
+                .iter()
+                .filter(|r| r.has_lyrics())
+                .map(|r| LyricCandidate::song(self.id(), r.id.to_string(), &r.track_name, &r.artist_name, &r.album_name, r.duration_ms))
+                .collect())
+        })
+    }
+
+    fn fetch_by_id<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<Option<ProviderLyrics>>> {
+        Box::pin(async move {
+            let id = id.trim().parse().with_context(|| format!("Invalid LRCLIB record id '{}'", id))?;
+            Ok(self.get_by_id(id).await?.and_then(LrclibRecord::into_lyrics))
+        })
+    }
+}
+
+impl LrclibRecord {
+    pub fn has_lyrics(&self) -> bool {
+        !self.synced_lyrics.trim().is_empty() || !self.plain_lyrics.trim().is_empty()
+    }
+
+    /// 优先使用带时间轴的 syncedLyrics；只有 plainLyrics 时原样作为歌词，
+    /// 显示端按"无时间轴歌词"处理（只显示首行）。纯音乐没有歌词，返回 None。
+    pub fn into_lyrics(self) -> Option<ProviderLyrics> {
+        let lyrics = if !self.synced_lyrics.trim().is_empty() {
+            self.synced_lyrics
+        } else if !self.plain_lyrics.trim().is_empty() {
+            self.plain_lyrics
+        } else {
+            return None;
+        };
+        Some(ProviderLyrics { provider: "lrclib", lyrics, ..ProviderLyrics::default() })
+    }
+}
+
+/// 解析单条记录（/api/get 的响应或 /api/search 数组中的元素）
+pub fn parse_record(value: &Value) -> Option<LrclibRecord> {
+    let text = |key: &str| value[key].as_str().unwrap_or_default().to_string();
+    Some(LrclibRecord {
+        id: value["id"].as_u64()?,
+        track_name: value["trackName"].as_str()?.to_string(),
+        artist_name: text("artistName"),
+        album_name: text("albumName"),
+        duration_ms: value["duration"].as_f64().map_or(0, |secs| (secs * 1000.0).round() as u64),
+        instrumental: value["instrumental"].as_bool().unwrap_or(false),
+        plain_lyrics: text("plainLyrics"),
+        synced_lyrics: text("syncedLyrics"),
+    })
+}
+
+pub fn parse_search(value: &Value) -> Vec<LrclibRecord> {
+    value.as_array().map(|records| records.iter().filter_map(parse_record).collect()).unwrap_or_default()
+}
+
+/// 从有歌词的搜索结果中选出同一首歌中匹配度最高的一条（见 `song_match::pick_best`）；
+/// 同分时带时间轴的优先。
+pub fn pick_record<'a>(records: &'a [LrclibRecord], query: &LyricsQuery) -> Option<&'a LrclibRecord> {
+    // pick_best 同分时取先出现的：把带时间轴的记录排在前面（稳定排序，保持相关度顺序）
+    let mut with_lyrics: Vec<&LrclibRecord> = records.iter().filter(|r| r.has_lyrics()).collect();
+    with_lyrics.sort_by_key(|r| r.synced_lyrics.trim().is_empty());
+    song_match::pick_best(with_lyrics, query, |r| SongFields {
+        title: &r.track_name,
+        artists: std::slice::from_ref(&r.artist_name),
+        album: &r.album_name,
+        duration_ms: r.duration_ms,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::song_query;
+
+    const SEARCH_FIXTURE: &str = include_str!("../tests/fixtures/lrclib_search.json");
+
+
+    #[test]
+    fn test_parse_search_and_pick() {
+        let records = parse_search(&serde_json::from_str(SEARCH_FIXTURE).unwrap());
+        assert_eq!(records.len(), 4);
+        assert_eq!(records[1].duration_ms, 253_440);
+        assert!(records[3].instrumental);
+
+        // 时长接近的优先；时长未知时带时间轴的优先于纯文本
+        assert_eq!(pick_record(&records, &song_query(253_000)).map(|r| r.id), Some(1002));
+        assert_eq!(pick_record(&records, &song_query(0)).map(|r| r.id), Some(1002));
+        let other_artist = LyricsQuery { artist: "群星".to_string(), ..song_query(0) };
+        assert!(pick_record(&records, &other_artist).is_none());
+
+        assert!(parse_search(&serde_json::json!({ "message": "error" })).is_empty());
+    }
+
+    #[test]
+    fn test_into_lyrics() {
+        let records = parse_search(&serde_json::from_str(SEARCH_FIXTURE).unwrap());
+        let synced = records[1].clone().into_lyrics().unwrap();
+        assert!(synced.lyrics.starts_with("[00:16.21] 雨落在窗前"));
+
+        // 只有纯文本歌词时原样使用
+        let plain = records[0].clone().into_lyrics().unwrap();
+        assert_eq!(plain.lyrics, "雨落在窗前\n你说再等一等");
+
+        assert_eq!(records[3].clone().into_lyrics(), None);
+    }
+}
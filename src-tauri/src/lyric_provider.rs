@@ -0,0 +1,300 @@
+use anyhow::{bail, Result};
+use serde::Serialize;
+use std::path::Path;
+use std::sync::Arc;
+
+use crate::kugou::KugouProvider;
+use crate::lrclib::LrclibProvider;
+use crate::lyrics::LyricFetcher;
+use crate::lyrics_pins::LyricsPin;
+use crate::netease::NeteaseProvider;
+use crate::lyrics_service::BoxFuture;
+use crate::song_match::{self, LyricsQuery, SongFields};
+use crate::song_info::QrcLine;
+
+/// 未配置 `lyric_providers` 时使用的在线歌词源
+pub const DEFAULT_PROVIDERS: &[&str] = &["qqmusic"];
+/// 可配置的歌词源 id
+pub const PROVIDER_IDS: &[&str] = &["qqmusic", "netease", "kugou", "lrclib"];
+
+/// 在线歌词源返回的歌词：逐行（LRC）或逐字，可附带翻译
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ProviderLyrics {
+    /// 歌词源 id（见 `LyricProvider::id`）
+    pub provider: &'static str,
+    /// LRC 逐行歌词
+    pub lyrics: String,
+    /// LRC 翻译
+    pub trans: String,
+    /// LRC 罗马音（歌词源提供时），目前不参与显示
+    pub roma: String,
+    /// QQ 音乐加密 QRC 原文，由流水线解密解析；其他歌词源为空
+    pub qrc_raw: String,
+    /// 已解析的逐字歌词（YRC/KRC 等格式由歌词源自行转换）
+    pub qrc_data: Vec<QrcLine>,
+    /// 专辑封面地址，歌词源不提供时为空
+    pub pic_url: String,
+    /// 命中歌曲与查询的匹配度（0~1），歌词源不打分时为 None
+    pub confidence: Option<f32>,
+}
+
+impl ProviderLyrics {
+    /// 是否有歌词（LRC、加密 QRC 或逐字）
+    pub fn has_lyrics(&self) -> bool {
+        !self.lyrics.is_empty() || !self.qrc_raw.is_empty() || !self.qrc_data.is_empty()
+    }
+}
+
+/// 每个歌词源最多列出的候选数
+const CANDIDATES_PER_PROVIDER: usize = 5;
+
+/// 供用户挑选的搜索候选；把 `pin` 原样提交即可固定这首歌（见 lyrics_pins）
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LyricCandidate {
+    pub pin: LyricsPin,
+    pub title: String,
+    /// 多个歌手以 `/` 分隔
+    pub artist: String,
+    pub album: String,
+    /// 0 表示未知
+    pub duration_ms: u64,
+    /// 与当前歌曲的匹配度（0~1，规则同 QQ 音乐候选打分）
+    pub score: f32,
+}
+
+impl LyricCandidate {
+    /// 在线歌词源中的一首歌，score 由 `search_all` 统一计算
+    pub fn song(provider: &str, id: impl Into<String>, title: &str, artist: &str, album: &str, duration_ms: u64) -> Self {
+        Self {
+            pin: LyricsPin::Song { provider: provider.to_string(), id: id.into() },
+            title: title.to_string(),
+            artist: artist.to_string(),
+            album: album.to_string(),
+            duration_ms,
+            score: 0.0,
+        }
+    }
+
+    /// 本地歌词文件，score 由调用方计算
+    pub fn file(path: &Path, title: &str, artist: &str, album: &str, duration_ms: u64) -> Self {
+        Self {
+            pin: LyricsPin::File { path: path.to_string_lossy().into_owned() },
+            title: title.to_string(),
+            artist: artist.to_string(),
+            album: album.to_string(),
+            duration_ms,
+            score: 0.0,
+        }
+    }
+
+    pub fn score_against(&mut self, query: &LyricsQuery) {
+        let artists: Vec<String> = self.artist.split('/').map(|s| s.trim().to_string()).filter(|s| !s.is_empty()).collect();
+        let fields = SongFields { title: &self.title, artists: &artists, album: &self.album, duration_ms: self.duration_ms };
+        self.score = song_match::score_candidate(&fields, query);
+    }
+}
+
+/// 在线歌词源。
+///
+/// `Ok(None)` 表示请求正常但没有这首歌的歌词，`Err` 表示请求出错（结果未知）；
+/// 两者的区别决定重试节奏（见 lyrics_retry）。
+pub trait LyricProvider: Send + Sync {
+    /// 配置中使用的 id，如 `qqmusic`
+    fn id(&self) -> &'static str;
+
+    fn fetch<'a>(&'a self, query: &'a LyricsQuery) -> BoxFuture<'a, Result<Option<ProviderLyrics>>>;
+
+    /// 搜索候选供用户挑选（不获取歌词）；不支持时返回空列表
+    fn search<'a>(&'a self, _query: &'a LyricsQuery) -> BoxFuture<'a, Result<Vec<LyricCandidate>>> {
+        Box::pin(async { Ok(Vec::new()) })
+    }
+
+    /// 按 `search` 返回的 id 获取歌词（用户固定的匹配）
+    fn fetch_by_id<'a>(&'a self, _id: &'a str) -> BoxFuture<'a, Result<Option<ProviderLyrics>>> {
+        Box::pin(async move { bail!("歌词源 {} 不支持按 id 获取", self.id()) })
+    }
+}
+
+/// QQ 音乐：SmartBox 多策略搜索、候选按时长/专辑/歌手打分 + musicu.fcg / fcg_query_lyric_new.fcg
+pub struct QqMusicProvider {
+    fetcher: Arc<LyricFetcher>,
+}
+
+impl QqMusicProvider {
+    pub fn new(fetcher: Arc<LyricFetcher>) -> Self {
+        Self { fetcher }
+    }
+}
+
+impl LyricProvider for QqMusicProvider {
+    fn id(&self) -> &'static str {
+        "qqmusic"
+    }
+
+    fn fetch<'a>(&'a self, query: &'a LyricsQuery) -> BoxFuture<'a, Result<Option<ProviderLyrics>>> {
+        Box::pin(async move {
+            let fetched = self.fetcher.fetch_best_lyrics(query).await?;
+            let found = ProviderLyrics {
+                provider: self.id(),
+                lyrics: fetched.lyrics,
+                trans: fetched.trans,
+                qrc_raw: fetched.qrc_raw,
+                pic_url: fetched.pic_url,
+                confidence: Some(fetched.confidence),
+                ..ProviderLyrics::default()
+            };
+            Ok(found.has_lyrics().then_some(found))
+        })
+    }
+
+    fn search<'a>(&'a self, query: &'a LyricsQuery) -> BoxFuture<'a, Result<Vec<LyricCandidate>>> {
+        Box::pin(async move {
+            let songs = self.fetcher.list_candidates(query).await?;
+            Ok(songs
+                .iter()
+                .map(|song| LyricCandidate::song(self.id(), &song.mid, &song.name, &song.singers.join("/"), &song.album, song.duration_ms))
+                .collect())
+        })
+    }
+
+    fn fetch_by_id<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<Option<ProviderLyrics>>> {
+        Box::pin(async move {
+            let (lyrics, trans, qrc_raw) = self.fetcher.get_lyric(id).await?;
+            let pic_url = self.fetcher.get_album_pic_url_by_mid(id).await;
+            let found = ProviderLyrics { provider: self.id(), lyrics, trans, qrc_raw, pic_url, ..ProviderLyrics::default() };
+            Ok(found.has_lyrics().then_some(found))
+        })
+    }
+}
+
+/// 按配置顺序创建歌词源；未知 id 输出警告后跳过
+pub fn build_providers(ids: &[String], fetcher: &Arc<LyricFetcher>, debug: bool) -> Vec<Box<dyn LyricProvider>> {
+    let mut providers: Vec<Box<dyn LyricProvider>> = Vec::new();
+    for id in ids {
+        let provider: Box<dyn LyricProvider> = match id.trim().to_ascii_lowercase().as_str() {
+            "qqmusic" | "qq" => Box::new(QqMusicProvider::new(fetcher.clone())),
+            "netease" | "163" => Box::new(NeteaseProvider::new(debug)),
+            "kugou" | "kg" => Box::new(KugouProvider::new(debug)),
+            "lrclib" => Box::new(LrclibProvider::new(debug)),
+            other => {
+                eprintln!("⚠️ 未知的歌词源 '{}'，已忽略（可用: {}）", other, PROVIDER_IDS.join(", "));
+                continue;
+            }
+        };
+        if providers.iter().any(|p| p.id() == provider.id()) {
+            continue;
+        }
+        providers.push(provider);
+    }
+    providers
+}
+
+/// 在所有歌词源中搜索候选，按匹配度从高到低排列（同分时保持歌词源顺序与搜索结果顺序）。
+/// 某个歌词源出错时跳过，不影响其余结果。
+pub async fn search_all(providers: &[Box<dyn LyricProvider>], query: &LyricsQuery, debug: bool) -> Vec<LyricCandidate> {
+    let mut candidates = Vec::new();
+    for provider in providers {
+        match provider.search(query).await {
+            Ok(found) => candidates.extend(found.into_iter().take(CANDIDATES_PER_PROVIDER)),
+            Err(e) => {
+                if debug { eprintln!("[provider] {}: search failed: {}", provider.id(), e); }
+            }
+        }
+    }
+    for candidate in &mut candidates {
+        candidate.score_against(query);
+    }
+    candidates.sort_by(|a, b| b.score.total_cmp(&a.score));
+    candidates
+}
+
+/// 按 id 查找歌词源（用户固定的匹配可以指向未启用的歌词源）
+pub fn find_provider<'a>(providers: &'a [Box<dyn LyricProvider>], id: &str) -> Option<&'a dyn LyricProvider> {
+    let id = id.trim().to_ascii_lowercase();
+    let canonical = match id.as_str() {
+        "qq" => "qqmusic",
+        "163" => "netease",
+        "kg" => "kugou",
+        other => other,
+    };
+    providers.iter().find(|p| p.id() == canonical).map(|p| p.as_ref())
+}
+
+/// 按优先级依次查询，返回第一个有歌词的结果。
+///
+/// 都没有歌词时：有歌词源出错则返回 Err（结果未知，应较快重试），否则返回 `Ok(None)`（未找到）。
+pub async fn fetch_first(providers: &[Box<dyn LyricProvider>], query: &LyricsQuery, debug: bool) -> Result<Option<ProviderLyrics>> {
+    let mut failure: Option<anyhow::Error> = None;
+    for provider in providers {
+        match provider.fetch(query).await {
+            Ok(Some(found)) => return Ok(Some(found)),
+            Ok(None) => {
+                if debug { eprintln!("[provider] {}: no lyrics for '{} - {}'", provider.id(), query.title, query.artist); }
+            }
+            Err(e) => {
+                if debug { eprintln!("[provider] {}: request failed: {}", provider.id(), e); }
+                failure = Some(e.context(format!("{} 请求失败", provider.id())));
+            }
+        }
+    }
+    match failure {
+        Some(e) => Err(e),
+        None => Ok(None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 固定返回结果的测试歌词源
+    struct FixedProvider {
+        id: &'static str,
+        result: fn() -> Result<Option<ProviderLyrics>>,
+    }
+
+    impl LyricProvider for FixedProvider {
+        fn id(&self) -> &'static str {
+            self.id
+        }
+
+        fn fetch<'a>(&'a self, _: &'a LyricsQuery) -> BoxFuture<'a, Result<Option<ProviderLyrics>>> {
+            Box::pin(async move { (self.result)() })
+        }
+    }
+
+    fn provider(id: &'static str, result: fn() -> Result<Option<ProviderLyrics>>) -> Box<dyn LyricProvider> {
+        Box::new(FixedProvider { id, result })
+    }
+
+    fn found() -> Result<Option<ProviderLyrics>> {
+        Ok(Some(ProviderLyrics { provider: "second", lyrics: "[00:01.00]那时雨".to_string(), ..ProviderLyrics::default() }))
+    }
+
+    /// 前一个歌词源出错或没有歌词时继续查询下一个；都没有时按是否出错区分失败与未找到
+    #[tokio::test]
+    async fn test_fetch_first_in_priority_order() {
+        let query = LyricsQuery { title: "那时雨".to_string(), artist: "徐良".to_string(), ..LyricsQuery::default() };
+        let failing = || Err(anyhow::anyhow!("timeout"));
+        let empty = || Ok(None);
+
+        let providers = vec![provider("first", failing), provider("second", found), provider("third", empty)];
+        let result = fetch_first(&providers, &query, false).await.unwrap().unwrap();
+        assert_eq!(result.provider, "second");
+
+        let providers = vec![provider("first", empty), provider("second", failing)];
+        assert!(fetch_first(&providers, &query, false).await.is_err());
+
+        let providers = vec![provider("first", empty)];
+        assert_eq!(fetch_first(&providers, &query, false).await.unwrap(), None);
+        assert_eq!(fetch_first(&[], &query, false).await.unwrap(), None);
+    }
+
+    #[test]
+    fn test_build_providers() {
+        let fetcher = Arc::new(LyricFetcher::new());
+        let ids = ["netease", "QQMusic", "unknown", "qq", "kg", "lrclib"].map(String::from);
+        let providers = build_providers(&ids, &fetcher, false);
+        assert_eq!(providers.iter().map(|p| p.id()).collect::<Vec<_>>(), ["netease", "qqmusic", "kugou", "lrclib"]);
+    }
+}
@@ -2,6 +2,45 @@ use anyhow::{Result, Context};
 use reqwest::{Client, Url};
 use serde_json::Value;
 use base64::{Engine as _, engine::general_purpose::STANDARD};
+use std::collections::{HashMap, HashSet};
+
+use crate::song_match::{self, clean_search_term, LyricsQuery, SongFields};
+
+/// 每个搜索策略最多获取详情并打分的候选数
+const MAX_CANDIDATES: usize = 5;
+/// 达到此匹配度即停止尝试后续搜索策略
+const ACCEPT_CONFIDENCE: f32 = 0.8;
+
+/// QQ 音乐搜索候选：SmartBox 只有 mid / 歌名 / 歌手，详情接口补全时长与专辑
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SongCandidate {
+    pub mid: String,
+    pub name: String,
+    pub singers: Vec<String>,
+    pub album: String,
+    pub album_mid: String,
+    /// 0 表示未知
+    pub duration_ms: u64,
+}
+
+impl SongCandidate {
+    pub fn fields(&self) -> SongFields<'_> {
+        SongFields { title: &self.name, artists: &self.singers, album: &self.album, duration_ms: self.duration_ms }
+    }
+}
+
+/// `fetch_best_lyrics` 的结果；未找到时各字段为空、confidence 为 0
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FetchedLyrics {
+    pub lyrics: String,
+    pub trans: String,
+    pub qrc_raw: String,
+    pub pic_url: String,
+    /// 命中的候选
+    pub candidate: SongCandidate,
+    /// 候选与查询的匹配度（0~1，见 `song_match::score_candidate`）
+    pub confidence: f32,
+}
 
 pub struct LyricFetcher {
     client: Client,
@@ -59,6 +98,15 @@ impl LyricFetcher {
 
     // Function to fetch albummid for a given songmid using get_song_detail_yqq API
     pub async fn get_album_mid(&self, songmid: &str) -> Result<String> {
+        let detail = self.get_song_detail(songmid).await?;
+        if detail.album_mid.is_empty() {
+            anyhow::bail!("album mid not found in response");
+        }
+        Ok(detail.album_mid)
+    }
+
+    // Function to fetch song details (duration, album, singers) via get_song_detail_yqq API
+    pub async fn get_song_detail(&self, songmid: &str) -> Result<SongCandidate> {
         let detail_data = serde_json::json!({
             "comm": {
                 "cv": 4747474,
@@ -94,100 +142,148 @@ impl LyricFetcher {
             .await
             .context("Failed to parse song detail JSON")?;
 
-        let album_mid = resp["req_1"]["data"]["track_info"]["album"]["mid"]
-            .as_str()
-            .ok_or_else(|| anyhow::anyhow!("album mid not found in response"))?;
+        parse_song_detail(&resp["req_1"]["data"]["track_info"])
+            .ok_or_else(|| anyhow::anyhow!("track info not found in response"))
+    }
 
-        Ok(album_mid.to_string())
+    // Function to search and fetch lyrics with multiple fallback strategies.
+    //
+    // Returns Ok with empty strings when every strategy completed but none found lyrics
+    // ("not found"), and Err when nothing was found and at least one request failed
+    // ("failed"), so callers can retry transient network errors sooner.
+    pub async fn fetch_lyrics(&self, query: &LyricsQuery) -> Result<(String, String, String, String)> {
+        let found = self.fetch_best_lyrics(query).await?;
+        Ok((found.lyrics, found.trans, found.qrc_raw, found.pic_url))
     }
 
-    // Function to search and fetch lyrics with multiple fallback strategies
-    pub async fn fetch_lyrics(&self, title: &str, artist: &str) -> Result<(String, String, String, String)> {
+    // Search with multiple fallback strategies and pick the candidate that best matches the query.
+    //
+    // Each strategy collects every SmartBox hit, fetches its details (duration, album, singers)
+    // and scores it against the query (see `song_match::score_candidate`). Candidates are tried in score order
+    // until one has lyrics; a strategy whose match reaches ACCEPT_CONFIDENCE ends the search,
+    // otherwise the best match over all strategies wins. Live versions, remixes and covers that
+    // SmartBox lists first no longer win just by being first.
+    pub async fn fetch_best_lyrics(&self, query: &LyricsQuery) -> Result<FetchedLyrics> {
         let log = |msg| { eprintln!("{}", msg); };
-        
-        // Strategy 1: Search with "artist title" keyword
-        let keyword = format!("{} {}", artist, title);
-        match self.search_song(&keyword).await {
-            Ok(Some(mid)) => {
-                match self.get_lyric(&mid).await {
-                    Ok(result) => {
-                        if !result.0.is_empty() || !result.2.is_empty() {
-                            let pic_url = self.get_album_pic_url_by_mid(&mid).await;
-                            log(format!("[lyrics] ✓ Found lyrics via strategy 1 ('{}')", keyword));
-                            return Ok((result.0, result.1, result.2, pic_url));
-                        }
-                        log(format!("[lyrics] Song found (mid={}) but lyric API returned empty", mid));
-                    },
-                    Err(e) => log(format!("[lyrics] Song found (mid={}) but lyric fetch failed: {}", mid, e)),
-                }
-            },
-            Ok(None) => log(format!("[lyrics] Search '{}' returned 0 results", keyword)),
-            Err(e) => log(format!("[lyrics] Search '{}' failed: {}", keyword, e)),
-        }
+        let (title, artist) = (query.title.as_str(), query.artist.as_str());
+        let mut failure: Option<anyhow::Error> = None;
+
+        let mut details: HashMap<String, SongCandidate> = HashMap::new();
+        let mut tried: HashSet<String> = HashSet::new();
+        let mut best: Option<FetchedLyrics> = None;
 
-        // Strategy 2: Search with just "title" (artist might contain multi-artist separators)
-        if let Ok(Some(mid)) = self.search_song(title).await {
-            if let Ok(result) = self.get_lyric(&mid).await {
-                if !result.0.is_empty() || !result.2.is_empty() {
-                    let pic_url = self.get_album_pic_url_by_mid(&mid).await;
-                    log(format!("[lyrics] Found lyrics for '{} - {}' via strategy 2", title, artist));
-                    return Ok((result.0, result.1, result.2, pic_url));
+        for (label, keyword) in search_strategies(title, artist) {
+            let candidates = match self.search_candidates(&keyword).await {
+                Ok(candidates) => candidates,
+                Err(e) => {
+                    log(format!("[lyrics] Search '{}' failed: {}", keyword, e));
+                    failure = Some(e);
+                    continue;
                 }
+            };
+            if candidates.is_empty() {
+                log(format!("[lyrics] Search '{}' returned 0 results", keyword));
+                continue;
             }
-        }
 
-        // Strategy 3: Try cleaned title (strip parentheses, suffixes)
-        let clean_title = clean_search_term(title);
-        let clean_artist = clean_search_term(artist);
-        if clean_title != title || clean_artist != artist {
-            // 3a: cleaned artist + cleaned title
-            let keyword = format!("{} {}", clean_artist, clean_title);
-            if let Ok(Some(mid)) = self.search_song(&keyword).await {
-                if let Ok(result) = self.get_lyric(&mid).await {
-                    if !result.0.is_empty() || !result.2.is_empty() {
-                        let pic_url = self.get_album_pic_url_by_mid(&mid).await;
-                        log(format!("[lyrics] Found lyrics for '{} - {}' via strategy 3a ('{}')", title, artist, keyword));
-                        return Ok((result.0, result.1, result.2, pic_url));
+            let mut scored = Vec::new();
+            for candidate in candidates.into_iter().take(MAX_CANDIDATES) {
+                let detailed = match details.get(&candidate.mid) {
+                    Some(cached) => cached.clone(),
+                    None => {
+                        // 详情获取失败时用 SmartBox 的字段（时长、专辑未知）继续打分
+                        let detailed = self.get_song_detail(&candidate.mid).await.unwrap_or(candidate);
+                        details.insert(detailed.mid.clone(), detailed.clone());
+                        detailed
                     }
-                }
+                };
+                scored.push((song_match::score_candidate(&detailed.fields(), query), detailed));
             }
+            // 稳定排序：同分时保持 SmartBox 的相关度顺序
+            scored.sort_by(|a, b| b.0.total_cmp(&a.0));
 
-            // 3b: cleaned title alone (artist + non-ASCII title often fails SmartBox,
-            // e.g. "G-Dragon 삐딱하게" returns 0, but "삐딱하게" hits the right song.
-            // Must run before parenthetical strategy so the original-language title
-            // wins over an English alternative that may point to a different version.)
-            if !clean_title.is_empty() {
-                if let Ok(Some(mid)) = self.search_song(&clean_title).await {
-                    if let Ok(result) = self.get_lyric(&mid).await {
-                        if !result.0.is_empty() || !result.2.is_empty() {
-                            let pic_url = self.get_album_pic_url_by_mid(&mid).await;
-                            log(format!("[lyrics] Found lyrics for '{} - {}' via strategy 3b (clean title only: '{}')", title, artist, clean_title));
-                            return Ok((result.0, result.1, result.2, pic_url));
+            for (confidence, candidate) in scored {
+                if best.as_ref().is_some_and(|b| confidence <= b.confidence) {
+                    break;
+                }
+                if !tried.insert(candidate.mid.clone()) {
+                    continue;
+                }
+                match self.get_lyric(&candidate.mid).await {
+                    Ok((lyrics, trans, qrc_raw)) if !lyrics.is_empty() || !qrc_raw.is_empty() => {
+                        if self.debug {
+                            eprintln!("[lyrics] Candidate '{} - {}' (mid={}, {}ms) scored {:.2} via strategy {}",
+                                candidate.singers.join("/"), candidate.name, candidate.mid, candidate.duration_ms, confidence, label);
                         }
+                        best = Some(FetchedLyrics { lyrics, trans, qrc_raw, pic_url: String::new(), candidate, confidence });
+                        break;
+                    }
+                    Ok(_) => log(format!("[lyrics] Song found (mid={}) but lyric API returned empty", candidate.mid)),
+                    Err(e) => {
+                        log(format!("[lyrics] Song found (mid={}) but lyric fetch failed: {}", candidate.mid, e));
+                        failure = Some(e);
                     }
                 }
             }
-        }
 
-        // Strategy 4: Try searching with parenthetical content (e.g. alternative/translated title inside brackets)
-        if let Some(alt_title) = extract_parentheses_content(title) {
-            let keyword = format!("{} {}", clean_artist, alt_title);
-            if let Ok(Some(mid)) = self.search_song(&keyword).await {
-                if let Ok(result) = self.get_lyric(&mid).await {
-                    if !result.0.is_empty() || !result.2.is_empty() {
-                        let pic_url = self.get_album_pic_url_by_mid(&mid).await;
-                        log(format!("[lyrics] Found lyrics via strategy 4 (parenthetical: '{}')", keyword));
-                        return Ok((result.0, result.1, result.2, pic_url));
-                    }
-                }
+            if best.as_ref().is_some_and(|b| b.confidence >= ACCEPT_CONFIDENCE) {
+                break;
             }
         }
 
-        // All strategies exhausted
+        if let Some(mut found) = best {
+            found.pic_url = if found.candidate.album_mid.is_empty() {
+                self.get_album_pic_url_by_mid(&found.candidate.mid).await
+            } else {
+                crate::lyrics_service::online_album_pic_url(&found.candidate.album_mid)
+            };
+            log(format!("[lyrics] ✓ Found lyrics for '{} - {}' (mid={}, confidence {:.2})", title, artist, found.candidate.mid, found.confidence));
+            return Ok(found);
+        }
+
+        // All strategies exhausted: a request error means the answer is unknown, not "no lyrics"
+        if let Some(e) = failure {
+            log(format!("[lyrics] No lyrics found for '{} - {}', some requests failed: {}", title, artist, e));
+            return Err(e.context("lyric lookup failed"));
+        }
         log(format!("[lyrics] No lyrics found for '{} - {}' (API returned no data)", title, artist));
-        Ok((String::new(), String::new(), String::new(), String::new()))
+        Ok(FetchedLyrics::default())
     }
 
+    // Collect candidates from every search strategy (deduplicated, with details) for the user to choose from.
+    //
+    // Unlike `fetch_best_lyrics` this neither scores nor fetches lyrics. Errors only surface
+    // when every search failed.
+    pub async fn list_candidates(&self, query: &LyricsQuery) -> Result<Vec<SongCandidate>> {
+        let mut failure: Option<anyhow::Error> = None;
+        let mut seen: HashSet<String> = HashSet::new();
+        let mut found: Vec<SongCandidate> = Vec::new();
+
+        for (_, keyword) in search_strategies(&query.title, &query.artist) {
+            if found.len() >= MAX_CANDIDATES {
+                break;
+            }
+            match self.search_candidates(&keyword).await {
+                Ok(candidates) => {
+                    let fresh = candidates.into_iter().filter(|c| seen.insert(c.mid.clone()));
+                    found.extend(fresh.take(MAX_CANDIDATES - found.len()));
+                }
+                Err(e) => failure = Some(e),
+            }
+        }
+        if found.is_empty() {
+            if let Some(e) = failure {
+                return Err(e.context("candidate search failed"));
+            }
+        }
+
+        let mut detailed = Vec::with_capacity(found.len());
+        for candidate in found {
+            // 详情获取失败时保留 SmartBox 的字段
+            detailed.push(self.get_song_detail(&candidate.mid).await.unwrap_or(candidate));
+        }
+        Ok(detailed)
+    }
 
     /// 多策略搜索歌曲，返回第一个命中候选的 songmid。
     ///
@@ -234,8 +330,25 @@ impl LyricFetcher {
         self.search_song_smartbox(&keyword).await
     }
 
-    // Function to search using SmartBox autocomplete API
+    // Function to search for a song and return every SmartBox hit (details not yet fetched)
+    async fn search_candidates(&self, keyword: &str) -> Result<Vec<SongCandidate>> {
+        let keyword = sanitize_search_keyword(keyword);
+        self.search_candidates_smartbox(&keyword).await
+    }
+
+    // Function to search using SmartBox autocomplete API, returning the first hit
     pub(crate) async fn search_song_smartbox(&self, keyword: &str) -> Result<Option<String>> {
+        let first = self.search_candidates_smartbox(keyword).await?.into_iter().next();
+        if let Some(song) = &first {
+            if self.debug {
+                eprintln!("[lyrics] ✓ Found: '{}' (mid={})", song.name, song.mid);
+            }
+        }
+        Ok(first.map(|song| song.mid))
+    }
+
+    // Function to search using SmartBox autocomplete API
+    async fn search_candidates_smartbox(&self, keyword: &str) -> Result<Vec<SongCandidate>> {
         let smartbox_url = "https://c.y.qq.com/splcloud/fcgi-bin/smartbox_new.fcg";
         let params = vec![
             ("key", keyword),
@@ -261,28 +374,15 @@ impl LyricFetcher {
                     eprintln!("[lyrics] Search returned non-JSON for '{}': {} — body: {}", 
                         keyword, e, preview);
                 }
-                return Ok(None);
+                return Ok(Vec::new());
             }
         };
 
-        // SmartBox response: data.song.itemlist[] → { mid, name, singer }
-        let item_list = parsed["data"]["song"]["itemlist"].as_array();
-        if let Some(list) = item_list {
-            if let Some(first) = list.first() {
-                if let Some(mid) = first["mid"].as_str() {
-                    let name = first["name"].as_str().unwrap_or("?");
-                    if self.debug {
-                        eprintln!("[lyrics] ✓ Found: '{}' (mid={})", name, mid);
-                    }
-                    return Ok(Some(mid.to_string()));
-                }
-            }
-        }
-
-        if self.debug {
+        let candidates = parse_smartbox_songs(&parsed);
+        if candidates.is_empty() && self.debug {
             eprintln!("[lyrics] No results for '{}'", keyword);
         }
-        Ok(None)
+        Ok(candidates)
     }
 
     // Function to fetch lyrics, translation, and QRC data by song mid
@@ -503,31 +603,6 @@ fn sanitize_search_keyword(s: &str) -> String {
      .to_string()
 }
 
-// Helper to clean search terms by removing parenthetical content and special chars
-pub fn clean_search_term(s: &str) -> String {
-    let mut result = s.to_string();
-    // Remove content in parentheses (both English and Chinese)
-    while let Some(start) = result.find('(') {
-        if let Some(end) = result[start..].find(')') {
-            result.replace_range(start..start + end + 1, "");
-        } else {
-            break;
-        }
-    }
-    while let Some(start) = result.find('（') {
-        if let Some(end) = result[start..].find('）') {
-            result.replace_range(start..start + end + '）'.len_utf8(), "");
-        } else {
-            break;
-        }
-    }
-    // Remove common suffixes
-    for suffix in &[" - Single", " (Explicit)", " (Remastered)", " (Deluxe)"] {
-        result = result.replace(suffix, "");
-    }
-    result.trim().to_string()
-}
-
 // Helper to extract text inside parentheses (both English and Chinese)
 fn extract_parentheses_content(s: &str) -> Option<String> {
     if let Some(start) = s.find('(') {
@@ -558,10 +633,134 @@ fn unescape_html(s: &str) -> String {
      .replace("&gt;", ">")
 }
 
+// Search keywords in fallback order, deduplicated:
+//   1. "artist title"
+//   2. "title" (artist might contain multi-artist separators)
+//   3a. "clean_artist clean_title" (strip parentheses, suffixes)
+//   3b. "clean_title" alone (artist + non-ASCII title often fails SmartBox,
+//       e.g. "G-Dragon 삐딱하게" returns 0, but "삐딱하게" hits the right song.
+//       Must run before the parenthetical strategy so the original-language title
+//       wins over an English alternative that may point to a different version.)
+//   4. "clean_artist alt_title" (alternative/translated title inside brackets)
+fn search_strategies(title: &str, artist: &str) -> Vec<(&'static str, String)> {
+    let clean_title = clean_search_term(title);
+    let clean_artist = clean_search_term(artist);
+
+    let mut strategies = vec![("1", format!("{} {}", artist, title)), ("2", title.to_string())];
+    if clean_title != title || clean_artist != artist {
+        strategies.push(("3a", format!("{} {}", clean_artist, clean_title)));
+        strategies.push(("3b", clean_title.clone()));
+    }
+    if let Some(alt_title) = extract_parentheses_content(title) {
+        strategies.push(("4", format!("{} {}", clean_artist, alt_title)));
+    }
+
+    let mut seen: HashSet<String> = HashSet::new();
+    strategies
+        .into_iter()
+        .map(|(label, keyword)| (label, keyword.trim().to_string()))
+        .filter(|(_, keyword)| !keyword.is_empty() && seen.insert(keyword.clone()))
+        .collect()
+}
+
+// SmartBox response: data.song.itemlist[] → { mid, name, singer }
+fn parse_smartbox_songs(parsed: &Value) -> Vec<SongCandidate> {
+    let Some(list) = parsed["data"]["song"]["itemlist"].as_array() else {
+        return Vec::new();
+    };
+    list.iter()
+        .filter_map(|item| {
+            Some(SongCandidate {
+                mid: item["mid"].as_str().filter(|mid| !mid.is_empty())?.to_string(),
+                name: item["name"].as_str().unwrap_or_default().to_string(),
+                singers: item["singer"].as_str().map(|s| s.split('/').map(|a| a.trim().to_string()).collect()).unwrap_or_default(),
+                ..SongCandidate::default()
+            })
+        })
+        .collect()
+}
+
+// get_song_detail_yqq: data.track_info → { mid, name, interval (s), album { mid, name }, singer[] { name } }
+fn parse_song_detail(track: &Value) -> Option<SongCandidate> {
+    Some(SongCandidate {
+        mid: track["mid"].as_str()?.to_string(),
+        name: track["name"].as_str().unwrap_or_default().to_string(),
+        singers: track["singer"]
+            .as_array()
+            .map(|list| list.iter().filter_map(|s| s["name"].as_str().map(str::to_string)).collect())
+            .unwrap_or_default(),
+        album: track["album"]["name"].as_str().unwrap_or_default().to_string(),
+        album_mid: track["album"]["mid"].as_str().unwrap_or_default().to_string(),
+        duration_ms: track["interval"].as_u64().unwrap_or(0) * 1000,
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    fn candidate(mid: &str, name: &str, album: &str, duration_ms: u64) -> SongCandidate {
+        SongCandidate {
+            mid: mid.to_string(),
+            name: name.to_string(),
+            singers: vec!["徐良".to_string()],
+            album: album.to_string(),
+            album_mid: String::new(),
+            duration_ms,
+        }
+    }
+
+    fn query(title: &str, artist: &str) -> LyricsQuery {
+        LyricsQuery { title: title.to_string(), artist: artist.to_string(), ..LyricsQuery::default() }
+    }
+
+    /// 录音室版优先于时长不同的现场版、混音版与翻唱
+    #[test]
+    fn test_score_candidate() {
+        let query = LyricsQuery { title: "那时雨".to_string(), artist: "徐良".to_string(), album: "那时雨".to_string(), duration_ms: 253_000 };
+        let score = |candidate: SongCandidate| song_match::score_candidate(&candidate.fields(), &query);
+        let studio = score(candidate("a", "那时雨", "那时雨", 253_440));
+        let live = score(candidate("b", "那时雨 (Live)", "现场合辑", 281_000));
+        let remix = score(candidate("c", "那时雨 (DJ版)", "那时雨", 261_000));
+        let cover = score(SongCandidate { singers: vec!["群星".to_string()], ..candidate("d", "那时雨", "翻唱合集", 253_000) });
+        assert!((studio - 1.0).abs() < 1e-6);
+        assert!(studio > remix && remix > live, "studio={} remix={} live={}", studio, remix, live);
+        assert!(studio > cover);
+
+        // 未知时长/专辑记中间分，标题与歌手一致时仍达到接受阈值
+        let unknown = LyricsQuery { album: String::new(), duration_ms: 0, ..query };
+        assert!(song_match::score_candidate(&candidate("a", "那时雨", "", 0).fields(), &unknown) >= ACCEPT_CONFIDENCE);
+    }
+
+    #[test]
+    fn test_parse_candidates() {
+        let smartbox = serde_json::json!({ "code": 0, "data": { "song": { "count": 2, "itemlist": [
+            { "docid": "1", "id": "1", "mid": "003aaa", "name": "那时雨", "singer": "徐良/小凌" },
+            { "docid": "2", "id": "2", "mid": "", "name": "无效", "singer": "" }
+        ] } } });
+        let songs = parse_smartbox_songs(&smartbox);
+        assert_eq!(songs.len(), 1);
+        assert_eq!(songs[0].singers, ["徐良", "小凌"]);
+        assert_eq!(songs[0].duration_ms, 0);
+
+        let detail = serde_json::json!({
+            "mid": "003aaa", "name": "那时雨", "interval": 253,
+            "album": { "mid": "003kQTm81fCMvb", "name": "那时雨" },
+            "singer": [{ "mid": "x", "name": "徐良" }]
+        });
+        let song = parse_song_detail(&detail).unwrap();
+        assert_eq!((song.duration_ms, song.album_mid.as_str(), song.singers.len()), (253_000, "003kQTm81fCMvb", 1));
+        assert!(parse_song_detail(&Value::Null).is_none());
+    }
+
+    #[test]
+    fn test_search_strategies() {
+        let keywords: Vec<String> = search_strategies("삐딱하게 (Crooked) (狂放)", "G-DRAGON").into_iter().map(|(_, k)| k).collect();
+        assert_eq!(keywords, ["G-DRAGON 삐딱하게 (Crooked) (狂放)", "삐딱하게 (Crooked) (狂放)", "G-DRAGON 삐딱하게", "삐딱하게", "G-DRAGON Crooked"]);
+        // 清洗后无变化时不重复搜索
+        assert_eq!(search_strategies("那时雨", "徐良").len(), 2);
+    }
+
     #[tokio::test]
     async fn test_search_smartbox() {
         let fetcher = LyricFetcher::new();
@@ -602,7 +801,7 @@ mod tests {
     #[tokio::test]
     async fn test_fetch_lyrics() {
         let fetcher = LyricFetcher::new();
-        match fetcher.fetch_lyrics("那时雨", "徐良").await {
+        match fetcher.fetch_lyrics(&query("那时雨", "徐良")).await {
             Ok((lyrics, trans, qrc, _pic)) => {
                 eprintln!("[test] lyrics.len={}, trans.len={}, qrc.len={}", lyrics.len(), trans.len(), qrc.len());
                 if !lyrics.is_empty() {
@@ -619,7 +818,7 @@ mod tests {
     #[tokio::test]
     async fn test_fetch_qrc_target_song() {
         let fetcher = LyricFetcher::new();
-        let (lyrics, trans, qrc_raw, _pic) = fetcher.fetch_lyrics("越来越不懂", "蔡健雅")
+        let (lyrics, trans, qrc_raw, _pic) = fetcher.fetch_lyrics(&query("越来越不懂", "蔡健雅"))
             .await
             .expect("fetch_lyrics should succeed");
         
@@ -673,7 +872,7 @@ mod tests {
     #[tokio::test]
     async fn test_fetch_qrc_delicate_weapon() {
         let fetcher = LyricFetcher::new();
-        let (lyrics, trans, qrc_raw, _pic) = fetcher.fetch_lyrics("Delicate Weapon", "Grimes/Lizzy Wizzy")
+        let (lyrics, trans, qrc_raw, _pic) = fetcher.fetch_lyrics(&query("Delicate Weapon", "Grimes/Lizzy Wizzy"))
             .await
             .expect("fetch_lyrics should succeed");
 
@@ -722,7 +921,7 @@ mod tests {
     async fn test_fetch_crooked_album_match() {
         let fetcher = LyricFetcher::with_debug(true);
         let (_lyrics, _trans, _qrc, pic_url) = fetcher
-            .fetch_lyrics("삐딱하게 (Crooked) (狂放)", "G-DRAGON")
+            .fetch_lyrics(&query("삐딱하게 (Crooked) (狂放)", "G-DRAGON"))
             .await
             .expect("fetch_lyrics should succeed");
 
@@ -0,0 +1,611 @@
+use anyhow::{Context, Result};
+use notify::{Event, RecommendedWatcher, RecursiveMode, Watcher};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, OnceLock, RwLock};
+use xmltree::{Element, ParserConfig, XMLNode};
+
+use crate::local_qrc;
+use crate::lyric_provider::{LyricProvider, ProviderLyrics};
+use crate::lyrics_service::BoxFuture;
+use crate::song_match::{self, LyricsQuery, NormalizedQuery};
+use crate::qrc::lines_to_lrc;
+use crate::song_info::{QrcLine, QrcWord};
+
+/// 用户歌词目录（`lyrics_folder`）：读取人工校对的 `.lrc` / `.ttml` 文件。
+///
+/// 命中时覆盖磁盘缓存、QQ 音乐本地缓存与在线歌词源的结果（见 `LyricsStages::lookup_folder`）。
+/// 文件按文件名（`歌手 - 标题`、`标题` 或 QQ 音乐缓存格式）与文件内的标签
+/// （LRC 的 `[ti:]`/`[ar:]`/`[al:]`，TTML 的 `<ttm:title>` 等）匹配，子目录一并查找。
+pub struct LyricsFolderProvider {
+    dir: PathBuf,
+    debug: bool,
+    /// 目录存在后首次查找时建立；`Some(None)` 表示无法监听，之后每次查找都扫描目录
+    index: Arc<OnceLock<Option<FolderIndex>>>,
+}
+
+/// 歌词文件对应的歌曲信息（来自文件名或文件内标签）
+#[derive(Debug, Clone, Default, PartialEq)]
+struct FileMeta {
+    title: String,
+    artist: String,
+    album: String,
+}
+
+impl FileMeta {
+    fn normalized(&self) -> Self {
+        Self {
+            title: song_match::normalize(&self.title),
+            artist: song_match::normalize(&self.artist),
+            album: song_match::normalize(&self.album),
+        }
+    }
+}
+
+impl LyricsFolderProvider {
+    pub fn new(dir: impl Into<PathBuf>, debug: bool) -> Self {
+        Self { dir: dir.into(), debug, index: Arc::new(OnceLock::new()) }
+    }
+
+    /// 按配置创建；未配置时返回 None，目录不存在时输出警告（之后创建仍可生效）
+    pub fn from_config(dir: &str, debug: bool) -> Option<Self> {
+        let dir = dir.trim();
+        if dir.is_empty() {
+            return None;
+        }
+        if !Path::new(dir).is_dir() {
+            eprintln!("⚠️ 歌词目录 '{}' 不存在", dir);
+        }
+        Some(Self::new(dir, debug))
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+}
+
+impl LyricProvider for LyricsFolderProvider {
+    fn id(&self) -> &'static str {
+        "folder"
+    }
+
+    fn fetch<'a>(&'a self, query: &'a LyricsQuery) -> BoxFuture<'a, Result<Option<ProviderLyrics>>> {
+        Box::pin(async move {
+            let (dir, index, owned_query, debug) = (self.dir.clone(), self.index.clone(), query.clone(), self.debug);
+            tokio::task::spawn_blocking(move || {
+                if index.get().is_none() && dir.is_dir() {
+                    let opened = FolderIndex::open(&dir, debug)
+                        .map_err(|e| eprintln!("⚠️ 无法监听歌词目录，每次查找将扫描目录: {:#}", e))
+                        .ok();
+                    let _ = index.set(opened);
+                }
+                let found = match index.get() {
+                    Some(Some(index)) => index.find(&owned_query),
+                    _ => find_lyric_file(&dir, &owned_query),
+                };
+                let Some(path) = found else {
+                    return Ok(None);
+                };
+                if debug { eprintln!("[folder] Matched {}", path.display()); }
+                let lyrics = load_lyric_file(&path)?;
+                Ok(lyrics.has_lyrics().then_some(lyrics))
+            })
+            .await?
+        })
+    }
+}
+
+/// 目录中的一个歌词文件：文件名解析结果在登记时得到，文件内标签只在文件名不足以确定时读取并缓存
+#[derive(Debug, Clone, Default, PartialEq)]
+struct FolderEntry {
+    /// 归一化后的文件名字段
+    name: FileMeta,
+    /// 归一化后的标签字段，尚未读取时为 None；文件变化时清空
+    tags: Option<FileMeta>,
+}
+
+/// 目录（含子目录）中全部歌词文件，按路径有序保存（同分时结果稳定）
+#[derive(Debug, Default)]
+struct FolderFiles {
+    files: BTreeMap<PathBuf, FolderEntry>,
+}
+
+impl FolderFiles {
+    fn scan(dir: &Path) -> Self {
+        let mut files = Self::default();
+        files.add_dir(dir);
+        files
+    }
+
+    fn add_dir(&mut self, dir: &Path) {
+        let mut paths = Vec::new();
+        collect_lyric_files(dir, &mut paths);
+        for path in paths {
+            self.insert(path);
+        }
+    }
+
+    fn insert(&mut self, path: PathBuf) {
+        let name = path.file_stem().and_then(|s| s.to_str()).map(meta_from_filename).unwrap_or_default();
+        self.files.insert(path, FolderEntry { name: name.normalized(), tags: None });
+    }
+
+    /// 文件监听事件：新建的子目录整体登记；删除或改名的路径连同其下的文件一并移除，仍存在的文件重新登记
+    fn update(&mut self, path: &Path) {
+        if path.is_dir() {
+            self.add_dir(path);
+            return;
+        }
+        // 路径按组件排序，子路径紧跟在父路径之后
+        let removed: Vec<PathBuf> = self.files.range(path.to_path_buf()..).map(|(p, _)| p).take_while(|p| p.starts_with(path)).cloned().collect();
+        for p in removed {
+            self.files.remove(&p);
+        }
+        if path.is_file() && is_lyric_file(path) {
+            self.insert(path.to_path_buf());
+        }
+    }
+
+    /// 尚未读取标签、且文件名不足以确定的文件：文件名不匹配，或文件名中没有歌手
+    fn missing_tags(&self, query: &NormalizedQuery) -> Vec<PathBuf> {
+        self.files
+            .iter()
+            .filter(|(_, entry)| entry.tags.is_none() && (entry.name.artist.is_empty() || match_score(&entry.name, query).is_none()))
+            .map(|(path, _)| path.clone())
+            .collect()
+    }
+
+    /// 直接读取所需的标签（不经过共享锁时使用）
+    fn load_tags(&mut self, query: &NormalizedQuery) {
+        for path in self.missing_tags(query) {
+            let tags = read_tags(&path);
+            self.set_tags(&path, tags);
+        }
+    }
+
+    fn set_tags(&mut self, path: &Path, tags: FileMeta) {
+        if let Some(entry) = self.files.get_mut(path) {
+            entry.tags.get_or_insert(tags);
+        }
+    }
+
+    /// 文件名与（已读取的）标签分别打分取较高者；同分时 `.ttml`（逐字）优先于 `.lrc`，再取路径较小的一个
+    fn find(&self, query: &NormalizedQuery) -> Option<PathBuf> {
+        let mut best: Option<((u32, bool), &PathBuf)> = None;
+        for (path, entry) in &self.files {
+            let score = [Some(&entry.name), entry.tags.as_ref()].into_iter().flatten().filter_map(|meta| match_score(meta, query)).max();
+            let Some(score) = score else {
+                continue;
+            };
+            let key = (score, is_ttml(path));
+            let better = match &best {
+                Some((best_key, _)) => key > *best_key,
+                None => true,
+            };
+            if better {
+                best = Some((key, path));
+            }
+        }
+        best.map(|(_, path)| path.clone())
+    }
+}
+
+/// 歌词目录的内存索引：首次建立时扫描一次目录（含子目录），之后由文件监听增量更新，
+/// 切歌时不再遍历目录；文件内标签只在需要时读取一次（见 `FolderFiles::missing_tags`）。
+struct FolderIndex {
+    data: Arc<RwLock<FolderFiles>>,
+    _watcher: RecommendedWatcher,
+}
+
+impl FolderIndex {
+    /// 扫描目录并开始监听；无法监听时返回错误，调用方应退回逐次扫描目录
+    fn open(dir: &Path, debug: bool) -> Result<Self> {
+        let data = Arc::new(RwLock::new(FolderFiles::default()));
+
+        let watch_dir = dir.to_path_buf();
+        let watch_data = data.clone();
+        let mut watcher = notify::recommended_watcher(move |res: notify::Result<Event>| {
+            let event = match res {
+                Ok(event) => event,
+                Err(e) => {
+                    eprintln!("⚠️  歌词目录监听出错: {}", e);
+                    return;
+                }
+            };
+            let Ok(mut data) = watch_data.write() else {
+                return;
+            };
+            // 事件丢失（缓冲区溢出等）时整体重扫
+            if event.need_rescan() {
+                *data = FolderFiles::scan(&watch_dir);
+                if debug { eprintln!("[folder] Rescanned lyrics folder"); }
+                return;
+            }
+            for path in &event.paths {
+                data.update(path);
+            }
+        })
+        .context("Failed to create file watcher")?;
+
+        // 先监听再扫描：扫描期间新建的文件也不会漏掉
+        watcher
+            .watch(dir, RecursiveMode::Recursive)
+            .with_context(|| format!("Failed to watch {:?}", dir))?;
+        {
+            let mut data = data.write().unwrap_or_else(|e| e.into_inner());
+            *data = FolderFiles::scan(dir);
+            if debug { eprintln!("[folder] Indexed {} lyric files", data.files.len()); }
+        }
+
+        Ok(Self { data, _watcher: watcher })
+    }
+
+    fn find(&self, query: &LyricsQuery) -> Option<PathBuf> {
+        let query = NormalizedQuery::new(query);
+        let pending = self.data.read().unwrap_or_else(|e| e.into_inner()).missing_tags(&query);
+        // 读文件时不持有锁，避免阻塞监听线程
+        if !pending.is_empty() {
+            let tags: Vec<(PathBuf, FileMeta)> = pending.into_iter().map(|path| {
+                let tags = read_tags(&path);
+                (path, tags)
+            }).collect();
+            let mut data = self.data.write().unwrap_or_else(|e| e.into_inner());
+            for (path, tags) in tags {
+                data.set_tags(&path, tags);
+            }
+        }
+        self.data.read().unwrap_or_else(|e| e.into_inner()).find(&query)
+    }
+}
+
+/// 在目录（含子目录）中查找与歌曲匹配的歌词文件（不使用索引，逐个扫描）。
+///
+/// 标题必须一致；双方都有歌手时必须有交集。多个文件匹配时优先歌手、专辑一致的，
+/// 同分时 `.ttml`（逐字）优先于 `.lrc`，再按路径排序取第一个。
+pub fn find_lyric_file(dir: &Path, query: &LyricsQuery) -> Option<PathBuf> {
+    let query = NormalizedQuery::new(query);
+    let mut files = FolderFiles::scan(dir);
+    files.load_tags(&query);
+    files.find(&query)
+}
+
+/// 读取歌词文件：TTML 解析为逐字歌词（并生成 LRC 供文本输出），LRC 原样使用
+pub fn load_lyric_file(path: &Path) -> Result<ProviderLyrics> {
+    let text = read_file(path).with_context(|| format!("无法读取歌词文件 {}", path.display()))?;
+    if !is_ttml(path) {
+        return Ok(ProviderLyrics { provider: "folder", lyrics: text, ..ProviderLyrics::default() });
+    }
+    let ttml = parse_ttml(&text).with_context(|| format!("TTML 解析失败 {}", path.display()))?;
+    Ok(ProviderLyrics {
+        provider: "folder",
+        lyrics: lines_to_lrc(ttml.lines.iter().map(|l| (l.start_time_ms, l.content.as_str()))),
+        trans: lines_to_lrc(ttml.trans.iter().map(|(ms, text)| (*ms, text.as_str()))),
+        qrc_data: ttml.lines,
+        ..ProviderLyrics::default()
+    })
+}
+
+fn collect_lyric_files(dir: &Path, files: &mut Vec<PathBuf>) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            collect_lyric_files(&path, files);
+        } else if is_lyric_file(&path) {
+            files.push(path);
+        }
+    }
+}
+
+fn has_extension(path: &Path, ext: &str) -> bool {
+    path.extension().and_then(|e| e.to_str()).is_some_and(|e| e.eq_ignore_ascii_case(ext))
+}
+
+fn is_ttml(path: &Path) -> bool {
+    has_extension(path, "ttml")
+}
+
+fn is_lyric_file(path: &Path) -> bool {
+    is_ttml(path) || has_extension(path, "lrc")
+}
+
+/// 读取为 UTF-8 文本（去掉 BOM，非法字节替换而非报错）
+fn read_file(path: &Path) -> Option<String> {
+    let bytes = std::fs::read(path).ok()?;
+    let text = String::from_utf8_lossy(&bytes);
+    Some(text.trim_start_matches('\u{feff}').to_string())
+}
+
+/// `歌手 - 标题`（兼容 QQ 音乐缓存的 `歌手 - 标题 - 时长 - 专辑`）或单独的 `标题`
+fn meta_from_filename(stem: &str) -> FileMeta {
+    if !stem.contains(" - ") {
+        return FileMeta { title: stem.to_string(), ..FileMeta::default() };
+    }
+    let parsed = local_qrc::parse_lyric_filename(stem);
+    FileMeta { title: parsed.title, artist: parsed.artist, album: parsed.album }
+}
+
+/// 读取文件内标签（归一化后）；无法读取时为空
+fn read_tags(path: &Path) -> FileMeta {
+    read_file(path).map(|text| meta_from_tags(path, &text).normalized()).unwrap_or_default()
+}
+
+fn meta_from_tags(path: &Path, text: &str) -> FileMeta {
+    if is_ttml(path) {
+        return Element::parse(text.as_bytes()).map(|root| ttml_meta(&root)).unwrap_or_default();
+    }
+    let mut meta = FileMeta::default();
+    for line in text.lines().map(str::trim) {
+        let Some((tag, value)) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')).and_then(|l| l.split_once(':')) else {
+            continue;
+        };
+        let value = value.trim().to_string();
+        match tag.trim().to_ascii_lowercase().as_str() {
+            "ti" => meta.title = value,
+            "ar" => meta.artist = value,
+            "al" => meta.album = value,
+            _ => {}
+        }
+    }
+    meta
+}
+
+/// TTML 头部元数据：`<ttm:title>`，以及 AMLL 格式的 `<amll:meta key="musicName|artists|album" value="…"/>`
+fn ttml_meta(root: &Element) -> FileMeta {
+    let mut meta = FileMeta::default();
+    let Some(head) = root.get_child("head") else {
+        return meta;
+    };
+    for elem in descendants(head) {
+        match elem.name.as_str() {
+            "title" if meta.title.is_empty() => meta.title = elem.get_text().unwrap_or_default().trim().to_string(),
+            "meta" => {
+                let value = elem.attributes.get("value").cloned().unwrap_or_default();
+                match elem.attributes.get("key").map(String::as_str) {
+                    Some("musicName") if meta.title.is_empty() => meta.title = value,
+                    Some("artists") if meta.artist.is_empty() => meta.artist = value,
+                    Some("album") if meta.album.is_empty() => meta.album = value,
+                    _ => {}
+                }
+            }
+            _ => {}
+        }
+    }
+    meta
+}
+
+/// 两侧均已归一化
+fn match_score(meta: &FileMeta, query: &NormalizedQuery) -> Option<u32> {
+    if meta.title.is_empty() || meta.title != query.title {
+        return None;
+    }
+    let mut score = 1;
+    if !meta.artist.is_empty() && !query.artist.is_empty() {
+        if !(meta.artist.contains(&query.artist) || query.artist.contains(&meta.artist)) {
+            return None;
+        }
+        score += 2;
+    }
+    if !meta.album.is_empty() && meta.album == query.album {
+        score += 1;
+    }
+    Some(score)
+}
+
+/// TTML 解析结果
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TtmlLyrics {
+    pub lines: Vec<QrcLine>,
+    /// (行开始毫秒, 翻译)
+    pub trans: Vec<(u64, String)>,
+}
+
+/// 解析 TTML（Apple Music / AMLL 格式）：每个 `<p>` 为一行，带时间的 `<span>` 为一个字；
+/// 没有 `<span>` 的行作为整行一个字，第一个带时间的 `<span>` 之前的文本并入第一个字。`ttm:role="x-translation"` 的 span 为翻译，
+/// `x-bg`（和声）跳过。
+pub fn parse_ttml(xml: &str) -> Result<TtmlLyrics> {
+    // 保留 span 之间的空格（英文歌词的词间距）
+    let config = ParserConfig::new().whitespace_to_characters(true);
+    let root = Element::parse_with_config(xml.as_bytes(), config)?;
+    let body = root.get_child("body").context("TTML 缺少 <body>")?;
+
+    let mut lines = Vec::new();
+    let mut trans = Vec::new();
+    for p in descendants(body).filter(|e| e.name == "p") {
+        let begin = p.attributes.get("begin").and_then(|t| parse_ttml_time(t));
+        let end = p.attributes.get("end").and_then(|t| parse_ttml_time(t));
+        let mut words: Vec<QrcWord> = Vec::new();
+        let mut line_text = String::new();
+        for child in &p.children {
+            match child {
+                XMLNode::Element(span) => match span.attributes.get("role").map(String::as_str) {
+                    Some("x-translation") => {
+                        let text = span.get_text().unwrap_or_default().trim().to_string();
+                        if let (Some(begin), false) = (begin, text.is_empty()) {
+                            trans.push((begin, text));
+                        }
+                    }
+                    Some("x-bg") => {}
+                    _ => {
+                        let text = span.get_text().unwrap_or_default().to_string();
+                        let word_begin = span.attributes.get("begin").and_then(|t| parse_ttml_time(t));
+                        let word_end = span.attributes.get("end").and_then(|t| parse_ttml_time(t));
+                        match (word_begin, word_end) {
+                            (Some(start), Some(end)) => {
+                                // 第一个带时间的 span 之前的文本（如行首引号）并入第一个字
+                                let content = if words.is_empty() { format!("{}{text}", std::mem::take(&mut line_text).trim_start()) } else { text };
+                                words.push(QrcWord { content, start_time_ms: start, duration_ms: end.saturating_sub(start) })
+                            }
+                            _ => append_text(&mut words, &mut line_text, &text),
+                        }
+                    }
+                },
+                XMLNode::Text(text) => append_text(&mut words, &mut line_text, text),
+                _ => {}
+            }
+        }
+
+        let (Some(start), Some(end)) = (begin.or_else(|| words.first().map(|w| w.start_time_ms)), end) else {
+            continue;
+        };
+        if words.is_empty() {
+            words.push(QrcWord { content: line_text.trim().to_string(), start_time_ms: start, duration_ms: end.saturating_sub(start) });
+        }
+        if let Some(last) = words.last_mut() {
+            last.content.truncate(last.content.trim_end().len());
+        }
+        let content: String = words.iter().map(|w| w.content.as_str()).collect();
+        if content.trim().is_empty() {
+            continue;
+        }
+        lines.push(QrcLine { content, start_time_ms: start, duration_ms: end.saturating_sub(start), words });
+    }
+    Ok(TtmlLyrics { lines, trans })
+}
+
+/// span 外的文本：词间空格接到前一个字后面；还没有字时累积为整行文本
+fn append_text(words: &mut [QrcWord], line_text: &mut String, text: &str) {
+    match words.last_mut() {
+        Some(last) => last.content.push_str(text),
+        None => line_text.push_str(text),
+    }
+}
+
+/// 解析 TTML 时间：`hh:mm:ss.fff`、`mm:ss.fff`、`ss.fff`、`12.5s`、`1500ms`
+pub fn parse_ttml_time(s: &str) -> Option<u64> {
+    let s = s.trim();
+    if let Some(ms) = s.strip_suffix("ms") {
+        return ms.trim().parse::<f64>().ok().map(|ms| ms.round() as u64);
+    }
+    let s = s.strip_suffix('s').unwrap_or(s);
+    let mut secs = 0.0;
+    for part in s.split(':') {
+        let value: f64 = part.trim().parse().ok()?;
+        if value < 0.0 {
+            return None;
+        }
+        secs = secs * 60.0 + value;
+    }
+    Some((secs * 1000.0).round() as u64)
+}
+
+/// 深度优先遍历全部子元素（含自身）
+fn descendants(root: &Element) -> impl Iterator<Item = &Element> {
+    let mut stack = vec![root];
+    std::iter::from_fn(move || {
+        let elem = stack.pop()?;
+        stack.extend(elem.children.iter().rev().filter_map(XMLNode::as_element));
+        Some(elem)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::temp_dir;
+
+    const TTML_FIXTURE: &str = include_str!("../tests/fixtures/folder_lyrics.ttml");
+
+    fn query(title: &str, artist: &str) -> LyricsQuery {
+        LyricsQuery { title: title.to_string(), artist: artist.to_string(), album: "那时雨".to_string(), duration_ms: 0 }
+    }
+
+    #[test]
+    fn test_parse_ttml() {
+        let TtmlLyrics { lines, trans } = parse_ttml(TTML_FIXTURE).unwrap();
+        assert_eq!(lines.len(), 4);
+        assert_eq!((lines[0].start_time_ms, lines[0].duration_ms), (16210, 3460));
+        assert_eq!(lines[0].content, "雨落在窗前");
+        assert_eq!(lines[0].words[1], QrcWord { content: "落".to_string(), start_time_ms: 16610, duration_ms: 400 });
+
+        // span 之间的空格并入前一个字，和声不计入
+        assert_eq!(lines[1].content, "Rain falls again");
+        assert_eq!(lines[1].words.iter().map(|w| w.content.as_str()).collect::<Vec<_>>(), ["Rain ", "falls ", "again"]);
+
+        // 行首未标时间的文本并入第一个字
+        assert_eq!(lines[2].content, "「雨停了」");
+        assert_eq!(lines[2].words[0], QrcWord { content: "「雨".to_string(), start_time_ms: 58_000, duration_ms: 500 });
+
+        // 没有 span 的行作为整行
+        assert_eq!(lines[3].words.len(), 1);
+        assert_eq!(lines[3].start_time_ms, 62_000);
+        assert_eq!(trans, [(16210, "rain on the window".to_string())]);
+
+        assert_eq!(parse_ttml_time("1:02.5"), Some(62_500));
+        assert_eq!(parse_ttml_time("00:01:02.500"), Some(62_500));
+        assert_eq!(parse_ttml_time("3.25s"), Some(3250));
+        assert_eq!(parse_ttml_time("abc"), None);
+    }
+
+    #[test]
+    fn test_find_lyric_file() {
+        let dir = temp_dir("lyrics_folder_find");
+        std::fs::create_dir_all(dir.join("徐良")).unwrap();
+        std::fs::write(dir.join("徐良 - 那时雨.lrc"), "[00:16.21]雨落在窗前").unwrap();
+        std::fs::write(dir.join("群星 - 那时雨.lrc"), "[00:01.00]翻唱").unwrap();
+        // 文件名无法识别时按标签匹配
+        std::fs::write(dir.join("01.lrc"), "[ti:像风一样]\n[ar:薛之谦]\n[00:01.00]像风一样").unwrap();
+        std::fs::write(dir.join("notes.txt"), "那时雨").unwrap();
+
+        let found = find_lyric_file(&dir, &query("那时雨", "徐良")).unwrap();
+        assert_eq!(found, dir.join("徐良 - 那时雨.lrc"));
+        assert_eq!(find_lyric_file(&dir, &query("像风一样", "薛之谦")).unwrap(), dir.join("01.lrc"));
+        assert!(find_lyric_file(&dir, &query("那时雨", "周杰伦")).is_none());
+
+        // 同一首歌的 TTML（逐字）优先于 LRC
+        std::fs::write(dir.join("徐良").join("那时雨.ttml"), TTML_FIXTURE).unwrap();
+        let found = find_lyric_file(&dir, &query("那时雨", "徐良")).unwrap();
+        assert_eq!(found, dir.join("徐良").join("那时雨.ttml"));
+
+        let lyrics = load_lyric_file(&found).unwrap();
+        assert_eq!(lyrics.qrc_data.len(), 4);
+        assert!(lyrics.lyrics.starts_with("[00:16.21]雨落在窗前\n[00:20.00]Rain falls again"));
+        assert_eq!(lyrics.trans, "[00:16.21]rain on the window");
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_folder_index_updates() {
+        let dir = temp_dir("lyrics_folder_index");
+        let sub = dir.join("徐良");
+        std::fs::create_dir_all(&sub).unwrap();
+        let by_name = dir.join("徐良 - 那时雨.lrc");
+        let by_tags = dir.join("01.lrc");
+        std::fs::write(&by_name, "[00:16.21]雨落在窗前").unwrap();
+        std::fs::write(&by_tags, "[ti:像风一样]\n[ar:薛之谦]\n[00:01.00]像风一样").unwrap();
+
+        let mut files = FolderFiles::scan(&dir);
+        let q = NormalizedQuery::new(&query("那时雨", "徐良"));
+        assert_eq!(files.missing_tags(&q), std::slice::from_ref(&by_tags));
+        assert_eq!(files.find(&q), Some(by_name.clone()));
+
+        // 标签读取一次后缓存，文件变化时重新读取
+        let q = NormalizedQuery::new(&query("像风一样", "薛之谦"));
+        files.load_tags(&q);
+        assert_eq!(files.find(&q), Some(by_tags.clone()));
+        assert!(files.missing_tags(&q).is_empty());
+        std::fs::write(&by_tags, "[ti:别的歌]\n[00:01.00]别的歌").unwrap();
+        files.update(&by_tags);
+        assert_eq!(files.files[&by_tags].tags, None);
+
+        // 新建的子目录整体登记，删除目录后其下文件一并移除
+        let ttml = sub.join("那时雨.ttml");
+        std::fs::write(&ttml, TTML_FIXTURE).unwrap();
+        files.update(&sub);
+        let q = NormalizedQuery::new(&query("那时雨", "徐良"));
+        files.load_tags(&q);
+        assert_eq!(files.find(&q), Some(ttml));
+        std::fs::remove_dir_all(&sub).unwrap();
+        files.update(&sub);
+        assert_eq!(files.find(&q), Some(by_name.clone()));
+
+        std::fs::remove_file(&by_name).unwrap();
+        files.update(&by_name);
+        assert_eq!(files.find(&q), None);
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
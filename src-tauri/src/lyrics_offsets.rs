@@ -0,0 +1,56 @@
+use serde::{Deserialize, Serialize};
+
+use crate::song_map::SongMap;
+
+/// 单曲歌词偏移的映射文件：`{"title|artist": offset_ms}`。
+/// 正值让歌词提前显示（与 `smtc_offset_ms` 方向一致），负值推后
+pub type OffsetStore = SongMap<i64>;
+
+/// 单曲偏移的上限（毫秒），防止连点或误输入把歌词推到整首歌之外
+pub const MAX_OFFSET_MS: i64 = 30_000;
+
+/// 调整单曲偏移：`{"offset_ms": -300}` 设为指定值，`{"delta_ms": 50}` 在当前值上增减
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum OffsetChange {
+    Set { offset_ms: i64 },
+    Adjust { delta_ms: i64 },
+}
+
+impl OffsetChange {
+    /// 应用到当前偏移，结果限制在 ±MAX_OFFSET_MS
+    pub fn apply(self, current_ms: i64) -> i64 {
+        let offset_ms = match self {
+            OffsetChange::Set { offset_ms } => offset_ms,
+            OffsetChange::Adjust { delta_ms } => current_ms.saturating_add(delta_ms),
+        };
+        offset_ms.clamp(-MAX_OFFSET_MS, MAX_OFFSET_MS)
+    }
+}
+
+/// 播放位置加上单曲偏移得到歌词时间（不小于 0）
+pub fn apply_offset(time_ms: u64, offset_ms: i64) -> u64 {
+    time_ms.saturating_add_signed(offset_ms)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_offset_change() {
+        let set: OffsetChange = serde_json::from_str(r#"{"offset_ms":-300}"#).unwrap();
+        assert_eq!(set, OffsetChange::Set { offset_ms: -300 });
+        let adjust: OffsetChange = serde_json::from_str(r#"{"delta_ms":50}"#).unwrap();
+        assert_eq!(adjust, OffsetChange::Adjust { delta_ms: 50 });
+
+        assert_eq!(set.apply(1_000), -300);
+        assert_eq!(adjust.apply(-300), -250);
+        assert_eq!(OffsetChange::Adjust { delta_ms: 10_000 }.apply(25_000), MAX_OFFSET_MS);
+        assert_eq!(OffsetChange::Set { offset_ms: i64::MIN }.apply(0), -MAX_OFFSET_MS);
+
+        assert_eq!(apply_offset(60_000, -300), 59_700);
+        assert_eq!(apply_offset(100, -300), 0);
+        assert_eq!(apply_offset(60_000, 250), 60_250);
+    }
+}
@@ -0,0 +1,170 @@
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+use crate::krc;
+use crate::local_qrc;
+use crate::lyric_provider::ProviderLyrics;
+use crate::lyrics_folder;
+use crate::qrc;
+use crate::song_map::SongMap;
+
+/// 用户为某首歌固定的歌词，优先于所有自动匹配（歌词目录、磁盘缓存、本地缓存、在线搜索）。
+/// JSON 形如 `{"kind": "song", "provider": "qqmusic", "id": "0039MnYb0qxYhV"}` 或
+/// `{"kind": "file", "path": "D:\\Lyrics\\那时雨.ttml"}`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum LyricsPin {
+    /// 在线歌词源中的一首歌：`id` 为 QQ 音乐 songmid、网易云歌曲 id、
+    /// 酷狗 `id:accesskey` 或 LRCLIB 记录 id（见各歌词源的 `search`）
+    Song { provider: String, id: String },
+    /// 本地歌词文件：QQ 音乐缓存的 QRC/LRC、酷狗 KRC、TTML 或普通 LRC
+    File { path: String },
+}
+
+impl std::fmt::Display for LyricsPin {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LyricsPin::Song { provider, id } => write!(f, "{}:{}", provider, id),
+            LyricsPin::File { path } => write!(f, "{}", path),
+        }
+    }
+}
+
+/// 固定匹配的映射文件：`{"title|artist": LyricsPin}`
+pub type PinStore = SongMap<LyricsPin>;
+
+/// 可以固定的歌词文件扩展名（见 `load_pinned_file`）
+pub const PIN_FILE_EXTENSIONS: [&str; 4] = ["qrc", "lrc", "krc", "ttml"];
+
+/// 检查要固定的歌词文件，返回规范化后的路径。
+///
+/// 固定接口不需要鉴权，读到的内容会经 `/ws` 推送，所以只接受 `roots`
+/// （QQ 音乐歌词缓存目录与歌词目录）之内、扩展名为已知歌词格式的文件。
+/// 先规范化再比较，`..` 与符号链接不能绕过限制。
+pub fn check_pin_file(path: &Path, roots: &[PathBuf]) -> Result<PathBuf> {
+    let canonical = path.canonicalize().with_context(|| format!("无法读取歌词文件 {}", path.display()))?;
+    if !canonical.is_file() || !has_pin_extension(&canonical) {
+        bail!("不支持的歌词文件 {}（只支持 {}）", path.display(), PIN_FILE_EXTENSIONS.join(" / "));
+    }
+    let inside = roots
+        .iter()
+        .filter_map(|root| root.canonicalize().ok())
+        .any(|root| canonical.starts_with(root));
+    if !inside {
+        bail!("歌词文件不在 QQ 音乐歌词缓存目录或歌词目录中: {}", path.display());
+    }
+    Ok(canonical)
+}
+
+fn has_pin_extension(path: &Path) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| PIN_FILE_EXTENSIONS.iter().any(|known| e.eq_ignore_ascii_case(known)))
+}
+
+/// 读取固定的歌词文件，按扩展名解析：
+/// QQ 音乐缓存的 `.qrc`（加密，附带同名翻译文件）与 `.lrc`（可能加密）、酷狗 `.krc`，
+/// 其余（`.ttml`、普通 `.lrc`）与歌词目录相同；调用前先用 `check_pin_file` 检查路径。
+pub fn load_pinned_file(path: &Path) -> Result<ProviderLyrics> {
+    let ext = path.extension().and_then(|e| e.to_str()).unwrap_or_default().to_ascii_lowercase();
+    let mut lyrics = match ext.as_str() {
+        "qrc" => {
+            let xml = qrc::decode_qrc_from_file(path)?;
+            let trans = local_qrc::find_qrc_trans_file(path)
+                .and_then(|trans_file| qrc::decode_qrc_from_file(&trans_file).ok())
+                .unwrap_or_default();
+            ProviderLyrics {
+                lyrics: qrc::extract_lrc_from_xml(&xml).unwrap_or_default(),
+                trans,
+                qrc_data: qrc::parse_qrc_xml(&xml).unwrap_or_default(),
+                ..ProviderLyrics::default()
+            }
+        }
+        "krc" => {
+            let parsed = krc::parse_krc(&krc::decode_krc_from_file(path)?);
+            ProviderLyrics {
+                lyrics: qrc::lines_to_lrc(parsed.lines.iter().map(|l| (l.start_time_ms, l.content.as_str()))),
+                trans: qrc::lines_to_lrc(parsed.trans.iter().map(|(ms, text)| (*ms, text.as_str()))),
+                qrc_data: parsed.lines,
+                ..ProviderLyrics::default()
+            }
+        }
+        // QQ 音乐缓存的 LRC 是加密的，解密失败时按普通 LRC 读取
+        "lrc" => match qrc::decode_qrc_from_file(path) {
+            Ok(raw) => {
+                let trans = local_qrc::find_lrc_trans_file(path)
+                    .and_then(|trans_file| qrc::decode_qrc_from_file(&trans_file).ok())
+                    .map(|trans_raw| qrc::extract_lrc_from_xml(&trans_raw).unwrap_or(trans_raw))
+                    .unwrap_or_default();
+                ProviderLyrics { lyrics: qrc::extract_lrc_from_xml(&raw).unwrap_or(raw), trans, ..ProviderLyrics::default() }
+            }
+            Err(_) => lyrics_folder::load_lyric_file(path)?,
+        },
+        "ttml" => lyrics_folder::load_lyric_file(path)?,
+        _ => bail!("不支持的歌词文件 {}", path.display()),
+    };
+    lyrics.provider = "file";
+    Ok(lyrics)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::temp_dir;
+
+    #[test]
+    fn test_pin_json() {
+        let pin: LyricsPin = serde_json::from_str(r#"{"kind":"song","provider":"qqmusic","id":"0039MnYb0qxYhV"}"#).unwrap();
+        assert_eq!(pin, LyricsPin::Song { provider: "qqmusic".to_string(), id: "0039MnYb0qxYhV".to_string() });
+        let file = LyricsPin::File { path: "那时雨.ttml".to_string() };
+        assert_eq!(serde_json::to_string(&file).unwrap(), r#"{"kind":"file","path":"那时雨.ttml"}"#);
+    }
+
+    #[test]
+    fn test_load_pinned_file() {
+        let dir = temp_dir("lyrics_pins_load");
+
+        let krc_file = dir.join("那时雨.krc");
+        std::fs::write(&krc_file, include_bytes!("../tests/fixtures/kugou_sample.krc")).unwrap();
+        let lyrics = load_pinned_file(&krc_file).unwrap();
+        assert_eq!(lyrics.provider, "file");
+        assert_eq!(lyrics.qrc_data.len(), 3);
+        assert!(lyrics.lyrics.starts_with("[00:16.21]雨落在窗前"));
+
+        // 未加密的 LRC 原样使用
+        let lrc_file = dir.join("那时雨.lrc");
+        std::fs::write(&lrc_file, "[00:01.00]那时雨").unwrap();
+        assert_eq!(load_pinned_file(&lrc_file).unwrap().lyrics, "[00:01.00]那时雨");
+
+        assert!(load_pinned_file(&dir.join("不存在.lrc")).is_err());
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn test_check_pin_file() {
+        let dir = temp_dir("lyrics_pins_check");
+        let roots = vec![dir.join("QQMusicLyricNew"), dir.join("Lyrics")];
+        std::fs::create_dir_all(&roots[0]).unwrap();
+        std::fs::create_dir_all(&roots[1]).unwrap();
+
+        let qrc_file = roots[0].join("徐良 - 那时雨 - 333 - 那时雨_qm.qrc");
+        std::fs::write(&qrc_file, b"qrc").unwrap();
+        assert_eq!(check_pin_file(&qrc_file, &roots).unwrap(), qrc_file.canonicalize().unwrap());
+        let ttml_file = roots[1].join("那时雨.TTML");
+        std::fs::write(&ttml_file, b"ttml").unwrap();
+        assert!(check_pin_file(&ttml_file, &roots).is_ok());
+
+        // 目录之外、借 `..` 跳出目录、未知扩展名与不存在的文件都拒绝
+        let outside = dir.join("那时雨.lrc");
+        std::fs::write(&outside, b"secret").unwrap();
+        assert!(check_pin_file(&outside, &roots).is_err());
+        assert!(check_pin_file(&roots[1].join("..").join("那时雨.lrc"), &roots).is_err());
+        let config_file = roots[1].join("config.toml");
+        std::fs::write(&config_file, b"secret").unwrap();
+        assert!(check_pin_file(&config_file, &roots).is_err());
+        assert!(check_pin_file(&roots[1].join("不存在.lrc"), &roots).is_err());
+        assert!(check_pin_file(&qrc_file, &[]).is_err());
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
@@ -0,0 +1,62 @@
+use std::time::Duration;
+
+/// 首次失败后的重试间隔，之后每次翻倍
+const RETRY_BASE_DELAY: Duration = Duration::from_secs(5);
+/// 失败重试间隔上限
+const RETRY_MAX_DELAY: Duration = Duration::from_secs(10 * 60);
+
+/// 一次歌词查找的结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LookupOutcome {
+    /// 找到歌词（本地或在线）
+    Found,
+    /// 所有策略均正常完成但没有歌词
+    NotFound,
+    /// 没有歌词且有请求出错（网络故障等），结果未知
+    Failed,
+}
+
+/// 歌词查找的重试计划：失败按指数退避重试 `max_retries` 次，
+/// 未找到（或重试用尽）则在 `not_found_recheck` 之后再查一次。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetrySchedule {
+    pub max_retries: u32,
+    pub not_found_recheck: Duration,
+}
+
+impl RetrySchedule {
+    /// 下一次查找前的等待时间；找到歌词时不再重试（None）。
+    /// `failures` 为包含本次在内的连续失败次数。
+    pub fn next_attempt_in(&self, outcome: LookupOutcome, failures: u32) -> Option<Duration> {
+        match outcome {
+            LookupOutcome::Found => None,
+            LookupOutcome::NotFound => Some(self.not_found_recheck),
+            LookupOutcome::Failed if failures == 0 || failures > self.max_retries => Some(self.not_found_recheck),
+            LookupOutcome::Failed => {
+                let factor = 1u32.checked_shl(failures - 1).unwrap_or(u32::MAX);
+                Some(RETRY_BASE_DELAY.saturating_mul(factor).min(RETRY_MAX_DELAY))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_retry_schedule() {
+        let schedule = RetrySchedule { max_retries: 3, not_found_recheck: Duration::from_secs(3600) };
+        assert_eq!(schedule.next_attempt_in(LookupOutcome::Found, 0), None);
+        assert_eq!(schedule.next_attempt_in(LookupOutcome::NotFound, 0), Some(Duration::from_secs(3600)));
+
+        // 失败：5s → 10s → 20s，重试用尽后按"未找到"处理
+        assert_eq!(schedule.next_attempt_in(LookupOutcome::Failed, 1), Some(Duration::from_secs(5)));
+        assert_eq!(schedule.next_attempt_in(LookupOutcome::Failed, 2), Some(Duration::from_secs(10)));
+        assert_eq!(schedule.next_attempt_in(LookupOutcome::Failed, 3), Some(Duration::from_secs(20)));
+        assert_eq!(schedule.next_attempt_in(LookupOutcome::Failed, 4), Some(Duration::from_secs(3600)));
+
+        let many = RetrySchedule { max_retries: 40, ..schedule };
+        assert_eq!(many.next_attempt_in(LookupOutcome::Failed, 40), Some(RETRY_MAX_DELAY));
+    }
+}
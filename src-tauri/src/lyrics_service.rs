@@ -0,0 +1,1107 @@
+use anyhow::Result;
+use std::future::Future;
+use std::path::Path;
+use std::pin::Pin;
+use std::sync::Arc;
+use tokio::sync::mpsc::UnboundedSender;
+
+use crate::cover_store;
+use crate::local_index::{self, LyricFileKind};
+use crate::local_qrc;
+use crate::lyric_provider::{self, LyricCandidate, LyricProvider, ProviderLyrics, PROVIDER_IDS};
+use crate::lyrics::LyricFetcher;
+use crate::lyrics_folder::LyricsFolderProvider;
+use crate::lyrics_pins::{self, LyricsPin};
+use crate::lyrics_retry::LookupOutcome;
+use crate::lyrics_store::{LyricsSource, StoredLyrics};
+use crate::qrc;
+use crate::song_match::{self, LyricsQuery};
+use crate::song_info::QrcLine;
+
+/// 可跨线程的装箱 Future：歌词加载运行在 tokio::spawn 的任务中
+pub type BoxFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
+
+/// 歌词内容：各阶段的中间结果与最终结果
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LyricsData {
+    pub lyrics: String,
+    pub trans: String,
+    /// 在线 QRC 原文（本地解密成功时为 `[local]`）
+    pub qrc_raw: String,
+    pub qrc_data: Vec<QrcLine>,
+    pub album_pic_url: String,
+    /// 本地缓存文件名中解析出的专辑名（QQ 音乐索引时的原始名），
+    /// 比依赖 SMTC 报告的 album 更可靠，用作在线 album_mid 解析的搜索词。
+    pub local_album: String,
+    pub album_mid: String,
+    /// 在线搜索命中歌曲的匹配度（0~1），非搜索来源为 None
+    pub confidence: Option<f32>,
+}
+
+impl LyricsData {
+    /// 是否有可显示的歌词（LRC 或逐字）
+    pub fn has_lyrics(&self) -> bool {
+        !self.lyrics.is_empty() || !self.qrc_data.is_empty()
+    }
+
+    pub fn summary(&self) -> LyricsSummary {
+        LyricsSummary {
+            qrc_lines: self.qrc_data.len(),
+            lyrics: !self.lyrics.is_empty(),
+            trans: !self.trans.is_empty(),
+            cover: !self.album_pic_url.is_empty(),
+        }
+    }
+}
+
+/// 封面查找结果
+#[derive(Debug, Clone, PartialEq)]
+pub struct FoundCover {
+    pub url: String,
+    pub album_mid: String,
+    pub origin: CoverOrigin,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CoverOrigin {
+    /// QQMusicPicture 本地图片（已登记到封面存储）
+    Local,
+    /// QQ 音乐 CDN
+    Online,
+}
+
+/// 某阶段结果的概要，用于 TUI 日志
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LyricsSummary {
+    pub qrc_lines: usize,
+    pub lyrics: bool,
+    pub trans: bool,
+    pub cover: bool,
+}
+
+impl LyricsSummary {
+    pub fn is_empty(&self) -> bool {
+        self.qrc_lines == 0 && !self.lyrics && !self.trans
+    }
+}
+
+/// 查找过程中的进度事件（按发生顺序）
+#[derive(Debug, Clone, PartialEq)]
+pub enum LyricsProgress {
+    /// 用户固定的歌词获取成功，`pin` 为固定目标的描述
+    Pinned { pin: String, summary: LyricsSummary },
+    /// 用户固定的歌词没有获取到（请求出错或没有歌词），不再尝试自动匹配
+    PinnedFailed { pin: String, error: String },
+    /// 用户歌词目录命中，覆盖其他所有来源
+    Folder(LyricsSummary),
+    /// 磁盘缓存命中；`fresh` 为 false 时条目已过期，继续获取
+    DiskCache { fresh: bool },
+    /// 本地目录查找完成
+    Local(LyricsSummary),
+    /// 在线获取完成（已合并本地数据），`provider` 为命中的歌词源
+    Online { provider: &'static str, summary: LyricsSummary },
+    /// 所有歌词源都没有这首歌
+    OnlineNotFound,
+    /// 在线请求出错
+    OnlineFailed(String),
+    /// 本地已有逐字歌词但缺少封面，开始查找封面
+    CoverSearch,
+    Cover(Option<CoverOrigin>),
+    /// 非致命问题（QRC 解密/解析失败等）
+    Warning(String),
+    /// 已有可先行显示的数据（磁盘缓存、本地歌词），调用方可立即填充
+    Partial(LyricsData),
+}
+
+/// 最终结果
+#[derive(Debug, Clone, PartialEq)]
+pub struct LyricsResult {
+    pub data: LyricsData,
+    /// 本次实际获取到歌词的来源；为 None 时不应写回磁盘缓存（未过期的磁盘命中或未找到）
+    pub source: Option<LyricsSource>,
+    pub outcome: LookupOutcome,
+}
+
+/// 歌词获取的各个阶段，`LyricsService` 只负责编排与合并。
+///
+/// 默认实现为 `DefaultStages`；测试可替换任意阶段，无需访问网络或本地目录。
+pub trait LyricsStages: Send + Sync {
+    /// 用户为这首歌固定的歌词（见 lyrics_pins）；未固定时返回 None
+    fn pinned(&self, query: &LyricsQuery) -> Option<LyricsPin>;
+
+    /// 获取固定的歌词（`Ok(None)` 为没有歌词）
+    fn fetch_pinned<'a>(&'a self, pin: &'a LyricsPin) -> BoxFuture<'a, Result<Option<ProviderLyrics>>>;
+
+    /// 搜索候选供用户挑选固定（在线歌词源 + QQ 音乐本地缓存文件）
+    fn search_candidates<'a>(&'a self, query: &'a LyricsQuery) -> BoxFuture<'a, Vec<LyricCandidate>>;
+
+    /// 用户歌词目录（人工校对的歌词）；命中时跳过其余阶段
+    fn lookup_folder<'a>(&'a self, query: &'a LyricsQuery) -> BoxFuture<'a, Option<ProviderLyrics>>;
+
+    /// 磁盘歌词缓存，返回 (数据, 是否未过期)
+    fn load_disk<'a>(&'a self, query: &'a LyricsQuery) -> BoxFuture<'a, Option<(LyricsData, bool)>>;
+
+    /// QQ 音乐本地缓存目录（QRC 优先，LRC 兜底）
+    fn lookup_local<'a>(&'a self, query: &'a LyricsQuery) -> BoxFuture<'a, LyricsData>;
+
+    /// 在线歌词源；没有歌词时返回 `Ok(None)`，请求出错时返回 Err
+    fn fetch_online<'a>(&'a self, query: &'a LyricsQuery) -> BoxFuture<'a, Result<Option<ProviderLyrics>>>;
+
+    /// 解密在线 QRC，返回 XML
+    fn decrypt_qrc(&self, raw: &str) -> Result<String>;
+
+    /// 在线封面地址 → 本地封面（找不到时原样返回）
+    fn resolve_cover<'a>(&'a self, pic_url: &'a str, album: &'a str) -> BoxFuture<'a, String>;
+
+    /// 本地有逐字歌词但没有封面时单独查找封面；`local_album` 为本地文件名中的专辑名
+    fn find_cover<'a>(&'a self, query: &'a LyricsQuery, local_album: &'a str) -> BoxFuture<'a, Option<FoundCover>>;
+}
+
+/// 歌词获取流水线：用户固定的歌词 → 用户歌词目录 → 磁盘缓存 → 本地目录 → 在线获取 → QRC 解密/解析 → 文本兜底 → 合并 → 封面解析
+pub struct LyricsService {
+    stages: Box<dyn LyricsStages>,
+    debug: bool,
+}
+
+impl LyricsService {
+    pub fn new(stages: impl LyricsStages + 'static, debug: bool) -> Self {
+        Self { stages: Box::new(stages), debug }
+    }
+
+    /// 查找歌词，不关心中间进度
+    pub async fn resolve(&self, query: &LyricsQuery) -> LyricsResult {
+        self.run(query, None).await
+    }
+
+    /// 查找歌词并按顺序发送进度事件；返回时 `progress` 随之关闭
+    pub async fn resolve_with_progress(&self, query: &LyricsQuery, progress: UnboundedSender<LyricsProgress>) -> LyricsResult {
+        self.run(query, Some(progress)).await
+    }
+
+    /// 列出当前歌曲的候选，按匹配度从高到低排列
+    pub async fn candidates(&self, query: &LyricsQuery) -> Vec<LyricCandidate> {
+        self.stages.search_candidates(query).await
+    }
+
+    /// 在线封面地址 → 本地封面，供主循环对已缓存歌曲做即时重解析
+    pub async fn resolve_cover(&self, pic_url: &str, album: &str) -> String {
+        self.stages.resolve_cover(pic_url, album).await
+    }
+
+    async fn run(&self, query: &LyricsQuery, progress: Option<UnboundedSender<LyricsProgress>>) -> LyricsResult {
+        let emit = |event: LyricsProgress| {
+            if let Some(tx) = &progress {
+                let _ = tx.send(event);
+            }
+        };
+        let (t, a) = (&query.title, &query.artist);
+
+        // 用户固定的歌词：优先于歌词目录与所有自动匹配，获取失败时也不退回自动匹配
+        if let Some(pin) = self.stages.pinned(query) {
+            if self.debug { eprintln!("[lyrics] '{} - {}' is pinned to {}", t, a, pin); }
+            return self.run_pinned(query, pin, &emit).await;
+        }
+
+        // 用户歌词目录：人工校对的歌词优先于磁盘缓存、本地与在线结果，命中即返回。
+        // 不写回磁盘缓存，目录中的文件修改或删除后下次加载即生效
+        if let Some(found) = self.stages.lookup_folder(query).await {
+            let mut data = LyricsData { lyrics: found.lyrics, trans: found.trans, qrc_data: found.qrc_data, ..LyricsData::default() };
+            emit(LyricsProgress::Folder(data.summary()));
+            emit(LyricsProgress::Partial(data.clone()));
+            if self.debug { eprintln!("[lyrics] Lyrics folder overrides '{} - {}'", t, a); }
+            // 封面：沿用磁盘缓存中的封面，没有时单独查找
+            match self.stages.load_disk(query).await {
+                Some((stored, _)) if !stored.album_pic_url.is_empty() => {
+                    data.album_pic_url = stored.album_pic_url;
+                    data.album_mid = stored.album_mid;
+                    data.local_album = stored.local_album;
+                }
+                _ => self.attach_cover(&mut data, query, &emit).await,
+            }
+            return LyricsResult { data, source: None, outcome: LookupOutcome::Found };
+        }
+
+        // 当前可显示的数据：后续阶段在此基础上覆盖或补全
+        let mut current = LyricsData::default();
+
+        // 0. 磁盘缓存：命中即先填充；未过期则不再查找，过期则继续获取并覆盖（获取失败时保留旧数据）
+        if let Some((stored, fresh)) = self.stages.load_disk(query).await {
+            emit(LyricsProgress::DiskCache { fresh });
+            emit(LyricsProgress::Partial(stored.clone()));
+            if fresh {
+                return LyricsResult { data: stored, source: None, outcome: LookupOutcome::Found };
+            }
+            current = stored;
+        }
+
+        // 本次实际获取到的来源，只有获取成功时才写回磁盘缓存（避免刷新过期数据的时间戳）
+        let mut source: Option<LyricsSource> = None;
+        // 在线请求出错（区别于正常返回"未找到"），决定重试节奏
+        let mut online_failed = false;
+
+        // 1. 本地歌词快速查找并立即填充（~50ms，不阻塞在线获取）
+        let local = self.stages.lookup_local(query).await;
+        emit(LyricsProgress::Local(local.summary()));
+        let has_local_qrc = !local.qrc_data.is_empty();
+        if local.has_lyrics() {
+            source = Some(LyricsSource::Local);
+            emit(LyricsProgress::Partial(local.clone()));
+            current = local;
+            if self.debug { eprintln!("[lyrics] Local cache populated for '{} - {}'", t, a); }
+        }
+
+        // 2. 在线歌词获取（仅当本地无 QRC 时才请求，作为备用手段）
+        if !has_local_qrc {
+            match self.stages.fetch_online(query).await {
+                Ok(Some(online)) => {
+                    let provider = online.provider;
+                    let resolved_pic_url = self.stages.resolve_cover(&online.pic_url, &query.album).await;
+                    let mut data = LyricsData {
+                        lyrics: online.lyrics,
+                        trans: online.trans,
+                        qrc_raw: online.qrc_raw,
+                        qrc_data: online.qrc_data,
+                        album_pic_url: resolved_pic_url,
+                        album_mid: local_qrc::extract_album_mid_from_url(&online.pic_url).unwrap_or_default(),
+                        confidence: online.confidence,
+                        ..LyricsData::default()
+                    };
+                    self.decode_online_qrc(&mut data, query, &emit);
+                    fill_missing(&mut data, &current);
+                    // 歌词源不提供封面（LRCLIB、酷狗）且没有已有封面可沿用时单独查找
+                    if data.album_pic_url.is_empty() {
+                        self.attach_cover(&mut data, query, &emit).await;
+                    }
+                    emit(LyricsProgress::Online { provider, summary: data.summary() });
+
+                    // "未找到"不写入磁盘缓存，否则重启后会一直命中空条目
+                    if data.has_lyrics() {
+                        source = Some(LyricsSource::Online);
+                    }
+                    current = data;
+                    if self.debug { eprintln!("[lyrics] ✓ Background fetch complete for '{} - {}' ({})", t, a, provider); }
+                }
+                Ok(None) => emit(LyricsProgress::OnlineNotFound),
+                Err(e) => {
+                    // 本地/磁盘数据保留在 current 中，无需额外处理
+                    emit(LyricsProgress::OnlineFailed(e.to_string()));
+                    online_failed = true;
+                }
+            }
+        } else {
+            // 本地有 QRC 但缺少封面：单独查找封面
+            self.attach_cover(&mut current, query, &emit).await;
+        }
+
+        let outcome = classify_outcome(current.has_lyrics(), online_failed);
+        LyricsResult { data: current, source, outcome }
+    }
+
+    /// 用户固定的歌词：在线歌曲写回磁盘缓存，未过期时直接使用，过期后才重新请求；
+    /// 获取失败时沿用磁盘缓存中（上次固定时获取的）数据。
+    /// 固定或取消固定时会删除这首歌的磁盘缓存，因此缓存中的数据总是来自当前固定的歌曲
+    async fn run_pinned(&self, query: &LyricsQuery, pin: LyricsPin, emit: &(dyn Fn(LyricsProgress) + Sync)) -> LyricsResult {
+        let is_song = matches!(pin, LyricsPin::Song { .. });
+        if is_song {
+            if let Some((stored, true)) = self.stages.load_disk(query).await {
+                emit(LyricsProgress::DiskCache { fresh: true });
+                emit(LyricsProgress::Pinned { pin: pin.to_string(), summary: stored.summary() });
+                emit(LyricsProgress::Partial(stored.clone()));
+                return LyricsResult { data: stored, source: None, outcome: LookupOutcome::Found };
+            }
+        }
+        let found = match self.stages.fetch_pinned(&pin).await {
+            Ok(Some(found)) => found,
+            other => {
+                let failed = other.is_err();
+                let error = other.err().map_or_else(|| "没有歌词".to_string(), |e| format!("{:#}", e));
+                emit(LyricsProgress::PinnedFailed { pin: pin.to_string(), error });
+                let data = match self.stages.load_disk(query).await {
+                    Some((stored, _)) => {
+                        emit(LyricsProgress::Partial(stored.clone()));
+                        stored
+                    }
+                    None => LyricsData::default(),
+                };
+                let outcome = classify_outcome(data.has_lyrics(), failed);
+                return LyricsResult { data, source: None, outcome };
+            }
+        };
+        let mut data = LyricsData {
+            lyrics: found.lyrics,
+            trans: found.trans,
+            qrc_raw: found.qrc_raw,
+            qrc_data: found.qrc_data,
+            ..LyricsData::default()
+        };
+        self.decode_online_qrc(&mut data, query, emit);
+        emit(LyricsProgress::Pinned { pin: pin.to_string(), summary: data.summary() });
+        emit(LyricsProgress::Partial(data.clone()));
+        if found.pic_url.is_empty() {
+            self.attach_cover(&mut data, query, emit).await;
+        } else {
+            data.album_pic_url = self.stages.resolve_cover(&found.pic_url, &query.album).await;
+            data.album_mid = local_qrc::extract_album_mid_from_url(&found.pic_url).unwrap_or_default();
+        }
+        // 本地文件每次重新读取，修改后下次加载即生效
+        let source = is_song.then_some(LyricsSource::Online);
+        LyricsResult { data, source, outcome: LookupOutcome::Found }
+    }
+
+    /// 没有经过在线获取的歌词（本地 QRC、用户歌词目录）单独查找封面
+    async fn attach_cover(&self, data: &mut LyricsData, query: &LyricsQuery, emit: &(dyn Fn(LyricsProgress) + Sync)) {
+        emit(LyricsProgress::CoverSearch);
+        match self.stages.find_cover(query, &data.local_album).await {
+            Some(cover) => {
+                emit(LyricsProgress::Cover(Some(cover.origin)));
+                data.album_pic_url = cover.url;
+                data.album_mid = cover.album_mid;
+            }
+            None => emit(LyricsProgress::Cover(None)),
+        }
+    }
+
+    /// 在线 QRC（QQ 音乐）解密 → XML 解析；缺少 LRC 时从 XML 提取，XML 解析失败时按文本格式兜底
+    fn decode_online_qrc(&self, data: &mut LyricsData, query: &LyricsQuery, emit: &dyn Fn(LyricsProgress)) {
+        if data.qrc_raw.is_empty() {
+            return;
+        }
+        let (t, a) = (&query.title, &query.artist);
+        if self.debug { eprintln!("[QRC] Raw data: {} bytes", data.qrc_raw.len()); }
+        let xml = match self.stages.decrypt_qrc(&data.qrc_raw) {
+            Ok(xml) => xml,
+            Err(err) => {
+                emit(LyricsProgress::Warning(format!("[QRC] Decode failed for '{} - {}': {}", t, a, err)));
+                return;
+            }
+        };
+        if self.debug { eprintln!("[QRC] Decrypted XML: {} bytes", xml.len()); }
+        match qrc::parse_qrc_xml(&xml) {
+            Ok(lines) => {
+                if self.debug { eprintln!("[QRC] Parsed {} lines from XML", lines.len()); }
+                data.qrc_data = lines;
+            }
+            Err(err) => {
+                emit(LyricsProgress::Warning(format!("[QRC] XML parse failed for '{} - {}': {}", t, a, err)));
+            }
+        }
+        if data.lyrics.is_empty() {
+            data.lyrics = qrc::extract_lrc_from_xml(&xml).unwrap_or_default();
+            if self.debug && !data.lyrics.is_empty() { eprintln!("[QRC] Extracted LRC from XML: {} chars", data.lyrics.len()); }
+        }
+        if data.qrc_data.is_empty() && !data.lyrics.is_empty() {
+            let parsed = qrc::parse_qrc_text(&data.lyrics);
+            if !parsed.is_empty() {
+                if self.debug { eprintln!("[QRC] Text fallback parsed {} lines", parsed.len()); }
+                data.qrc_data = parsed;
+            }
+        }
+    }
+}
+
+/// 合并规则：在线结果中为空的字段用已有数据（本地歌词或过期的磁盘缓存）回填，非空字段不覆盖。
+/// 封面与 album_mid 成对处理：在线结果有封面时以在线为准，没有时沿用已有的。
+pub fn fill_missing(online: &mut LyricsData, current: &LyricsData) {
+    if online.lyrics.is_empty() { online.lyrics = current.lyrics.clone(); }
+    if online.qrc_data.is_empty() { online.qrc_data = current.qrc_data.clone(); }
+    if online.trans.is_empty() { online.trans = current.trans.clone(); }
+    if online.album_pic_url.is_empty() {
+        online.album_pic_url = current.album_pic_url.clone();
+        online.album_mid = current.album_mid.clone();
+    }
+    // 复用本地缓存条目中的专辑名（优于依赖 SMTC album）
+    if online.local_album.is_empty() { online.local_album = current.local_album.clone(); }
+}
+
+/// 请求出错即为失败（即使沿用了过期的磁盘缓存或本地歌词，数据照常显示，按退避重试）；
+/// 否则有歌词为找到，没有为未找到
+pub fn classify_outcome(found: bool, online_failed: bool) -> LookupOutcome {
+    match (found, online_failed) {
+        (_, true) => LookupOutcome::Failed,
+        (true, false) => LookupOutcome::Found,
+        (false, false) => LookupOutcome::NotFound,
+    }
+}
+
+/// 默认阶段实现：用户歌词目录（可选）+ 磁盘歌词缓存 + QQ 音乐本地缓存目录 +
+/// 按优先级排列的在线歌词源；封面查找使用 QQ 音乐接口
+pub struct DefaultStages {
+    fetcher: Arc<LyricFetcher>,
+    providers: Vec<Box<dyn LyricProvider>>,
+    /// 全部歌词源（含未启用的），供候选搜索与固定匹配使用
+    catalog: Vec<Box<dyn LyricProvider>>,
+    folder: Option<LyricsFolderProvider>,
+    debug: bool,
+}
+
+impl DefaultStages {
+    pub fn new(fetcher: Arc<LyricFetcher>, providers: Vec<Box<dyn LyricProvider>>, folder: Option<LyricsFolderProvider>, debug: bool) -> Self {
+        // 已启用的歌词源排在前面，同分候选按配置的优先级排列
+        let mut ids: Vec<String> = providers.iter().map(|p| p.id().to_string()).collect();
+        for id in PROVIDER_IDS {
+            if !ids.iter().any(|existing| existing == id) {
+                ids.push(id.to_string());
+            }
+        }
+        let catalog = lyric_provider::build_providers(&ids, &fetcher, debug);
+        Self { fetcher, providers, catalog, folder, debug }
+    }
+}
+
+/// QQ 音乐本地缓存中标题相近的歌词文件，作为固定候选
+fn local_file_candidates(query: &LyricsQuery) -> Vec<LyricCandidate> {
+    let Some(cache_dir) = crate::get_lyric_cache_dir() else {
+        return Vec::new();
+    };
+    let title = query.title.to_lowercase();
+    let mut candidates = Vec::new();
+    for kind in [LyricFileKind::Qrc, LyricFileKind::Lrc] {
+        local_index::for_each_lyric_file(&cache_dir, kind, |file_name, parsed| {
+            if title.is_empty() || !parsed.title.to_lowercase().contains(&title) {
+                return;
+            }
+            let mut candidate = LyricCandidate::file(
+                &cache_dir.join(file_name),
+                &parsed.title,
+                &parsed.artist,
+                &parsed.album,
+                parsed.duration_secs * 1000,
+            );
+            candidate.score_against(query);
+            candidates.push(candidate);
+        });
+    }
+    candidates
+}
+
+impl LyricsStages for DefaultStages {
+    fn pinned(&self, query: &LyricsQuery) -> Option<LyricsPin> {
+        crate::pin_store()?.get(&query.title, &query.artist)
+    }
+
+    fn fetch_pinned<'a>(&'a self, pin: &'a LyricsPin) -> BoxFuture<'a, Result<Option<ProviderLyrics>>> {
+        Box::pin(async move {
+            match pin {
+                LyricsPin::Song { provider, id } => match lyric_provider::find_provider(&self.catalog, provider) {
+                    Some(provider) => provider.fetch_by_id(id).await,
+                    None => Err(anyhow::anyhow!("未知的歌词源 {}", provider)),
+                },
+                LyricsPin::File { path } => {
+                    // 映射文件可能被手动编辑，读取前同样检查路径
+                    let (path, roots) = (std::path::PathBuf::from(path), crate::pin_file_roots().await);
+                    tokio::task::spawn_blocking(move || {
+                        lyrics_pins::check_pin_file(&path, &roots).and_then(|path| lyrics_pins::load_pinned_file(&path))
+                    })
+                    .await
+                    .map_err(anyhow::Error::from)
+                    .and_then(|loaded| loaded)
+                    .map(|found| found.has_lyrics().then_some(found))
+                }
+            }
+        })
+    }
+
+    fn search_candidates<'a>(&'a self, query: &'a LyricsQuery) -> BoxFuture<'a, Vec<LyricCandidate>> {
+        Box::pin(async move {
+            let mut candidates = lyric_provider::search_all(&self.catalog, query, self.debug).await;
+            let owned = query.clone();
+            let local = tokio::task::spawn_blocking(move || local_file_candidates(&owned)).await.unwrap_or_default();
+            candidates.extend(local);
+            candidates.sort_by(|a, b| b.score.total_cmp(&a.score));
+            candidates
+        })
+    }
+
+    fn lookup_folder<'a>(&'a self, query: &'a LyricsQuery) -> BoxFuture<'a, Option<ProviderLyrics>> {
+        Box::pin(async move {
+            let folder = self.folder.as_ref()?;
+            match folder.fetch(query).await {
+                Ok(found) => found,
+                Err(e) => {
+                    // 文件损坏等问题不影响其余来源
+                    eprintln!("  ⚠ 歌词目录读取失败: {:#}", e);
+                    None
+                }
+            }
+        })
+    }
+
+    fn load_disk<'a>(&'a self, query: &'a LyricsQuery) -> BoxFuture<'a, Option<(LyricsData, bool)>> {
+        Box::pin(async move {
+            crate::lyrics_store()?;
+            let (title, artist) = (query.title.clone(), query.artist.clone());
+            let stored = tokio::task::spawn_blocking(move || crate::lyrics_store()?.load(&title, &artist))
+                .await
+                .ok()
+                .flatten()?;
+            // 没有歌词的条目视为过期（旧版本可能写入过"未找到"的结果）
+            let has_lyrics = !stored.lyrics.is_empty() || !stored.qrc_data.is_empty();
+            let fresh = has_lyrics && crate::lyrics_store().is_some_and(|store| store.is_fresh(&stored));
+            Some((data_from_stored(stored), fresh))
+        })
+    }
+
+    fn lookup_local<'a>(&'a self, query: &'a LyricsQuery) -> BoxFuture<'a, LyricsData> {
+        Box::pin(async move {
+            let query = query.clone();
+            tokio::task::spawn_blocking(move || lookup_local_lyrics(&query))
+                .await
+                .unwrap_or_default()
+        })
+    }
+
+    fn fetch_online<'a>(&'a self, query: &'a LyricsQuery) -> BoxFuture<'a, Result<Option<ProviderLyrics>>> {
+        Box::pin(lyric_provider::fetch_first(&self.providers, query, self.debug))
+    }
+
+    fn decrypt_qrc(&self, raw: &str) -> Result<String> {
+        qrc::decode_qrc(raw)
+    }
+
+    fn resolve_cover<'a>(&'a self, pic_url: &'a str, album: &'a str) -> BoxFuture<'a, String> {
+        Box::pin(resolve_album_pic_url(pic_url, album, &self.fetcher))
+    }
+
+    // 1) 取 album_mid：
+    //    a. 多策略搜歌曲 songmid → get_album_mid（解决 "(Explicit)" 等括号后缀
+    //       导致单策略搜不到的问题，与 fetch_lyrics 策略对齐）
+    //    b. 仍失败则用专辑名搜 SmartBox 专辑，候选为本地文件名专辑名
+    //       （QQ 音乐索引原始名，比 SMTC album 更可靠）与 SMTC album，
+    //       均清洗去括号后再搜
+    // 2) 有了 album_mid：本地命中→登记到封面存储（与在线路径一致，
+    //    避免 file:// 在 webview 受限）；否则返回在线 CDN URL
+    fn find_cover<'a>(&'a self, query: &'a LyricsQuery, local_album: &'a str) -> BoxFuture<'a, Option<FoundCover>> {
+        Box::pin(async move {
+            let fetcher = &self.fetcher;
+            let mut album_mid = String::new();
+
+            // 1a) 多策略搜歌曲拿 album_mid
+            if let Some(songmid) = fetcher.search_song_mid(&query.title, &query.artist).await {
+                album_mid = fetcher.get_album_mid(&songmid).await.unwrap_or_default();
+            }
+
+            // 1b) 歌曲搜失败 → 用专辑名搜专辑（候选：本地文件名专辑 + SMTC album，
+            //     各自清洗去括号后作为额外候选）
+            if album_mid.is_empty() {
+                let mut album_variants: Vec<String> = Vec::new();
+                for name in [local_album, query.album.as_str()].into_iter().filter(|s| !s.is_empty()) {
+                    let cleaned = clean_album_name(name);
+                    album_variants.push(cleaned.clone());
+                    if !cleaned.is_empty() && cleaned != name {
+                        album_variants.push(name.to_string());
+                    }
+                }
+                let mut seen: std::collections::HashSet<String> = std::collections::HashSet::new();
+                for variant in album_variants {
+                    let v = variant.trim().to_string();
+                    if v.is_empty() || !seen.insert(v.clone()) { continue; }
+                    if let Some(am) = fetcher.search_album_mid_by_name(&v).await {
+                        album_mid = am;
+                        break;
+                    }
+                }
+            }
+
+            // 2) album_mid → 本地图片优先，否则在线 URL
+            if album_mid.is_empty() {
+                return None;
+            }
+            if let Some(pic_dir) = crate::get_picture_cache_dir() {
+                if let Some(url) = publish_local_album_pic(&pic_dir, &album_mid) {
+                    return Some(FoundCover { url, album_mid, origin: CoverOrigin::Local });
+                }
+            }
+            let url = online_album_pic_url(&album_mid);
+            Some(FoundCover { url, album_mid, origin: CoverOrigin::Online })
+        })
+    }
+}
+
+/// QQ 音乐 CDN 的 800x800 专辑封面地址
+pub fn online_album_pic_url(album_mid: &str) -> String {
+    format!("https://y.gtimg.cn/music/photo_new/T002R800x800M000{}.jpg?max_age=2592000", album_mid)
+}
+
+/// 磁盘条目转为歌词数据。封面存储地址在重启后可能已失效（仅内存存储时），
+/// 此时按 album_mid 退回在线地址，由主循环的即时封面重解析再换成本地封面。
+fn data_from_stored(stored: StoredLyrics) -> LyricsData {
+    let cover_id = stored.cover_url.strip_prefix(cover_store::COVER_PATH).and_then(|id| id.strip_prefix('/'));
+    let cover_alive = match cover_id {
+        Some(id) => cover_store::get(id).is_some(),
+        None => true,
+    };
+    let album_pic_url = if cover_alive {
+        stored.cover_url
+    } else if !stored.album_mid.is_empty() {
+        online_album_pic_url(&stored.album_mid)
+    } else {
+        String::new()
+    };
+    LyricsData {
+        lyrics: stored.lyrics,
+        trans: stored.trans,
+        qrc_data: stored.qrc_data,
+        album_pic_url,
+        local_album: stored.local_album,
+        album_mid: stored.album_mid,
+        confidence: stored.confidence,
+        ..LyricsData::default()
+    }
+}
+
+/// 清洗专辑名：去掉中英文括号内容及前后空格，用于 SmartBox 模糊匹配。
+/// 例：`ONE PIECE SUPER BEST (海贼王10周年 SUPER BEST)` → `ONE PIECE SUPER BEST`
+fn clean_album_name(raw: &str) -> String {
+    let mut s = raw.to_string();
+    while let Some(start) = s.find('(') {
+        if let Some(end) = s[start..].find(')') {
+            s.replace_range(start..start + end + 1, "");
+        } else {
+            break;
+        }
+    }
+    while let Some(start) = s.find('（') {
+        if let Some(end) = s[start..].find('）') {
+            let end_byte = start + end + '）'.len_utf8();
+            s.replace_range(start..end_byte, "");
+        } else {
+            break;
+        }
+    }
+    s.trim().to_string()
+}
+
+/// 读取 QQMusicPicture 中 album_mid 对应的本地封面并登记到封面存储，返回 `/api/cover/{album_mid}`
+/// （未启用 HTTP 服务时为 base64 data URI，避免 file:// 在 webview 受限）
+pub fn publish_local_album_pic(pic_dir: &Path, album_mid: &str) -> Option<String> {
+    let local_path = local_index::find_album_pic(pic_dir, album_mid)?;
+    let data = std::fs::read(&local_path).ok()?;
+    Some(cover_store::publish(Some(album_mid), data, None))
+}
+
+/// 优先使用本地缓存的专辑封面图，找不到则返回原始在线 URL。
+///
+/// 本地封面文件名含 album_mid，可从在线 URL 中提取 mid 后在 QQMusicPicture 目录查找。
+/// 找到则登记到封面存储，由 HTTP 服务按 album_mid 提供。
+///
+/// 当 API 返回的 album_mid 在本地没找到时（如单曲版 vs 合辑版不一致），
+/// 用 SMTC 报告的专辑名通过 SmartBox 搜索正确的 album_mid，再查本地。
+async fn resolve_album_pic_url(online_url: &str, album_name: &str, fetcher: &LyricFetcher) -> String {
+    if online_url.is_empty() {
+        return String::new();
+    }
+
+    // 只处理 QQ 音乐 CDN 的在线 URL（封面存储地址是 SMTC 缩略图，不替换）
+    if !online_url.contains("y.gtimg.cn") {
+        return online_url.to_string();
+    }
+
+    let Some(pic_dir) = crate::get_picture_cache_dir() else {
+        return online_url.to_string();
+    };
+
+    // 第一次尝试：用在线 URL 中的 album_mid 查本地
+    if let Some(album_mid) = local_qrc::extract_album_mid_from_url(online_url) {
+        if let Some(url) = publish_local_album_pic(&pic_dir, &album_mid) {
+            return url;
+        }
+    }
+
+    // 第二次尝试：用 SMTC 专辑名搜 SmartBox，拿到用户实际播放专辑的 album_mid。
+    // 尝试原始名和去括号清洗后的变体，处理 SMTC 返回"ONE PIECE SUPER BEST (海贼王…)" 但 SmartBox 只索引"ONE PIECE SUPER BEST"的情况。
+    if !album_name.is_empty() {
+        let mut variants: Vec<&str> = vec![album_name];
+        let cleaned = clean_album_name(album_name);
+        if cleaned != album_name {
+            variants.push(&cleaned);
+        }
+        for variant in &variants {
+            if let Some(correct_mid) = fetcher.search_album_mid_by_name(variant).await {
+                if let Some(url) = publish_local_album_pic(&pic_dir, &correct_mid) {
+                    return url;
+                }
+            }
+        }
+    }
+
+    online_url.to_string()
+}
+
+/// 从本地 QQ 音乐缓存目录查找歌词（QRC 优先，LRC 兜底）。
+/// 此函数包含文件 I/O 与 DES 解密，仅应在 spawn_blocking 中调用。
+fn lookup_local_lyrics(query: &LyricsQuery) -> LyricsData {
+    let title = query.title.as_str();
+    let mut entry = LyricsData::default();
+
+    let Some(cache_dir) = crate::get_lyric_cache_dir() else {
+        return entry;
+    };
+
+    // QRC 优先（逐字歌词）
+    if let Some(qrc_file) = local_index::find_lyric_file(&cache_dir, LyricFileKind::Qrc, query) {
+        // 记录本地文件名中的专辑名，供后续在线专辑封面解析复用
+        if let Some(fname) = qrc_file.file_name().and_then(|n| n.to_str()) {
+            entry.local_album = local_qrc::parse_lyric_filename(fname).album;
+        }
+        match qrc::decode_qrc_from_file(&qrc_file) {
+            Ok(xml) => {
+                entry.qrc_raw = "[local]".to_string();
+                if let Ok(lines) = qrc::parse_qrc_xml(&xml) {
+                    entry.qrc_data = lines;
+                }
+                if entry.lyrics.is_empty() {
+                    entry.lyrics = qrc::extract_lrc_from_xml(&xml).unwrap_or_default();
+                }
+                if let Some(trans_file) = local_qrc::find_qrc_trans_file(&qrc_file) {
+                    if let Ok(trans_xml) = qrc::decode_qrc_from_file(&trans_file) {
+                        entry.trans = trans_xml;
+                    }
+                }
+            }
+            Err(e) => {
+                eprintln!("  ⚠ QRC解密失败: {:?} — {}", qrc_file.file_name().unwrap_or_default(), e);
+            }
+        }
+    }
+
+    // LRC 兜底（普通歌词，无逐字）
+    if entry.lyrics.is_empty() {
+        if let Some(lrc_file) = local_index::find_lyric_file(&cache_dir, LyricFileKind::Lrc, query) {
+            let lrc_raw = match qrc::decode_qrc_from_file(&lrc_file) {
+                Ok(decrypted) => decrypted,
+                Err(_) => std::fs::read_to_string(&lrc_file).unwrap_or_default(),
+            };
+            entry.lyrics = qrc::extract_lrc_from_xml(&lrc_raw).unwrap_or(lrc_raw);
+            if let Some(trans_lrc_file) = local_qrc::find_lrc_trans_file(&lrc_file) {
+                let trans_raw = match qrc::decode_qrc_from_file(&trans_lrc_file) {
+                    Ok(decrypted) => decrypted,
+                    Err(_) => std::fs::read_to_string(&trans_lrc_file).unwrap_or_default(),
+                };
+                entry.trans = qrc::extract_lrc_from_xml(&trans_raw).unwrap_or(trans_raw);
+            }
+        } else {
+            // LRC 也未找到：打印诊断，帮助判断是文件名匹配问题还是真的没有文件
+            let norm_title = song_match::normalize(title);
+            let mut lrc_candidates: Vec<String> = Vec::new();
+            local_index::for_each_lyric_file(&cache_dir, LyricFileKind::Lrc, |fname, parsed| {
+                if !parsed.title.is_empty() && song_match::normalize(&parsed.title).contains(&norm_title) {
+                    lrc_candidates.push(fname.to_string());
+                }
+            });
+            if !lrc_candidates.is_empty() {
+                eprintln!("  ℹ LRC候选(未匹配): {}", lrc_candidates.join(", "));
+            }
+        }
+    }
+
+    entry
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::song_query;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    fn line(text: &str) -> QrcLine {
+        QrcLine { content: text.to_string(), start_time_ms: 1000, duration_ms: 500, words: Vec::new() }
+    }
+
+    /// 可替换各阶段的测试桩，记录在线接口是否被调用
+    #[derive(Default)]
+    struct FakeStages {
+        /// 固定目标与获取结果；结果为 None 时模拟请求出错
+        pinned: Option<(LyricsPin, Option<ProviderLyrics>)>,
+        folder: Option<ProviderLyrics>,
+        disk: Option<(LyricsData, bool)>,
+        local: LyricsData,
+        /// None 时模拟请求出错
+        online: Option<ProviderLyrics>,
+        cover: Option<FoundCover>,
+        online_calls: Arc<AtomicU32>,
+        pinned_calls: Arc<AtomicU32>,
+    }
+
+    impl LyricsStages for FakeStages {
+        fn pinned(&self, _: &LyricsQuery) -> Option<LyricsPin> {
+            self.pinned.as_ref().map(|(pin, _)| pin.clone())
+        }
+
+        fn fetch_pinned<'a>(&'a self, _: &'a LyricsPin) -> BoxFuture<'a, Result<Option<ProviderLyrics>>> {
+            Box::pin(async move {
+                self.pinned_calls.fetch_add(1, Ordering::Relaxed);
+                let found = self.pinned.as_ref().and_then(|(_, found)| found.clone());
+                let found = found.ok_or_else(|| anyhow::anyhow!("network down"))?;
+                Ok(found.has_lyrics().then_some(found))
+            })
+        }
+
+        fn search_candidates<'a>(&'a self, _: &'a LyricsQuery) -> BoxFuture<'a, Vec<LyricCandidate>> {
+            Box::pin(async { Vec::new() })
+        }
+
+        fn lookup_folder<'a>(&'a self, _: &'a LyricsQuery) -> BoxFuture<'a, Option<ProviderLyrics>> {
+            Box::pin(async move { self.folder.clone() })
+        }
+
+        fn load_disk<'a>(&'a self, _: &'a LyricsQuery) -> BoxFuture<'a, Option<(LyricsData, bool)>> {
+            Box::pin(async move { self.disk.clone() })
+        }
+
+        fn lookup_local<'a>(&'a self, _: &'a LyricsQuery) -> BoxFuture<'a, LyricsData> {
+            Box::pin(async move { self.local.clone() })
+        }
+
+        fn fetch_online<'a>(&'a self, _: &'a LyricsQuery) -> BoxFuture<'a, Result<Option<ProviderLyrics>>> {
+            Box::pin(async move {
+                self.online_calls.fetch_add(1, Ordering::Relaxed);
+                let online = self.online.clone().ok_or_else(|| anyhow::anyhow!("network down"))?;
+                Ok(online.has_lyrics().then_some(online))
+            })
+        }
+
+        fn decrypt_qrc(&self, raw: &str) -> Result<String> {
+            Ok(raw.to_string())
+        }
+
+        fn resolve_cover<'a>(&'a self, pic_url: &'a str, _: &'a str) -> BoxFuture<'a, String> {
+            Box::pin(async move { pic_url.to_string() })
+        }
+
+        fn find_cover<'a>(&'a self, _: &'a LyricsQuery, _: &'a str) -> BoxFuture<'a, Option<FoundCover>> {
+            Box::pin(async move { self.cover.clone() })
+        }
+    }
+
+
+    fn local_lrc() -> LyricsData {
+        LyricsData {
+            lyrics: "[00:01.00]本地".to_string(),
+            trans: "[00:01.00]local".to_string(),
+            local_album: "那时雨 (Live)".to_string(),
+            ..LyricsData::default()
+        }
+    }
+
+    /// 在线字段只有为空时才用本地数据回填
+    #[tokio::test]
+    async fn test_online_fills_empty_fields_from_local() {
+        let stages = FakeStages {
+            local: local_lrc(),
+            online: Some(ProviderLyrics {
+                lyrics: "[00:01.00]在线".to_string(),
+                pic_url: "https://y.gtimg.cn/music/photo_new/T002R800x800M000003kQTm81fCMvb.jpg".to_string(),
+                ..ProviderLyrics::default()
+            }),
+            ..FakeStages::default()
+        };
+        let service = LyricsService::new(stages, false);
+        let result = service.resolve(&song_query(200_000)).await;
+
+        assert_eq!(result.outcome, LookupOutcome::Found);
+        assert_eq!(result.source, Some(LyricsSource::Online));
+        assert_eq!(result.data.lyrics, "[00:01.00]在线");
+        assert_eq!(result.data.trans, "[00:01.00]local");
+        assert_eq!(result.data.local_album, "那时雨 (Live)");
+        assert_eq!(result.data.album_mid, "003kQTm81fCMvb");
+    }
+
+    /// 歌词源不提供封面时沿用本地封面，本地也没有时单独查找
+    #[tokio::test]
+    async fn test_online_without_cover_keeps_or_finds_cover() {
+        let online = ProviderLyrics { provider: "lrclib", lyrics: "[00:01.00]在线".to_string(), ..ProviderLyrics::default() };
+        let local_cover = LyricsData {
+            album_pic_url: "/api/cover/003kQTm81fCMvb".to_string(),
+            album_mid: "003kQTm81fCMvb".to_string(),
+            ..local_lrc()
+        };
+        let found = FoundCover { url: "/api/cover/0041ZBYb3mZqOn".to_string(), album_mid: "0041ZBYb3mZqOn".to_string(), origin: CoverOrigin::Local };
+        let stages = FakeStages { local: local_cover, online: Some(online.clone()), cover: Some(found.clone()), ..FakeStages::default() };
+        let result = LyricsService::new(stages, false).resolve(&song_query(200_000)).await;
+        assert_eq!(result.source, Some(LyricsSource::Online));
+        assert_eq!(result.data.lyrics, "[00:01.00]在线");
+        assert_eq!((result.data.album_pic_url.as_str(), result.data.album_mid.as_str()), ("/api/cover/003kQTm81fCMvb", "003kQTm81fCMvb"));
+
+        let stages = FakeStages { local: local_lrc(), online: Some(online), cover: Some(found), ..FakeStages::default() };
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        let result = LyricsService::new(stages, false).resolve_with_progress(&song_query(200_000), tx).await;
+        assert_eq!((result.data.album_pic_url.as_str(), result.data.album_mid.as_str()), ("/api/cover/0041ZBYb3mZqOn", "0041ZBYb3mZqOn"));
+        let mut events = Vec::new();
+        while let Some(event) = rx.recv().await {
+            events.push(event);
+        }
+        assert!(events.contains(&LyricsProgress::CoverSearch));
+        assert!(matches!(events.last(), Some(LyricsProgress::Online { provider: "lrclib", summary }) if summary.cover));
+    }
+
+    /// 本地有逐字歌词时不请求在线歌词，只查找封面
+    #[tokio::test]
+    async fn test_local_qrc_skips_online() {
+        let online_calls = Arc::new(AtomicU32::new(0));
+        let stages = FakeStages {
+            online_calls: online_calls.clone(),
+            local: LyricsData { qrc_data: vec![line("本地")], ..local_lrc() },
+            cover: Some(FoundCover {
+                url: "/api/cover/003kQTm81fCMvb".to_string(),
+                album_mid: "003kQTm81fCMvb".to_string(),
+                origin: CoverOrigin::Local,
+            }),
+            ..FakeStages::default()
+        };
+        let service = LyricsService::new(stages, false);
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        let result = service.resolve_with_progress(&song_query(200_000), tx).await;
+
+        assert_eq!(result.source, Some(LyricsSource::Local));
+        assert_eq!(online_calls.load(Ordering::Relaxed), 0);
+        assert_eq!(result.data.album_pic_url, "/api/cover/003kQTm81fCMvb");
+        assert_eq!(result.data.qrc_data.len(), 1);
+
+        let mut events = Vec::new();
+        while let Some(event) = rx.recv().await {
+            events.push(event);
+        }
+        assert!(matches!(events[0], LyricsProgress::Local(summary) if summary.qrc_lines == 1));
+        assert!(matches!(events[1], LyricsProgress::Partial(_)));
+        assert_eq!(events[2..], [LyricsProgress::CoverSearch, LyricsProgress::Cover(Some(CoverOrigin::Local))]);
+    }
+
+    /// 未过期的磁盘缓存直接返回，不写回；过期条目在在线失败时保留
+    #[tokio::test]
+    async fn test_disk_cache_and_failure() {
+        let stored = LyricsData { lyrics: "[00:01.00]磁盘".to_string(), ..LyricsData::default() };
+        let service = LyricsService::new(FakeStages { disk: Some((stored.clone(), true)), ..FakeStages::default() }, false);
+        let result = service.resolve(&song_query(200_000)).await;
+        assert_eq!((result.source, result.outcome), (None, LookupOutcome::Found));
+        assert_eq!(result.data, stored);
+
+        // 过期条目重新获取出错：沿用旧数据，但记为失败以便按退避重试
+        let service = LyricsService::new(FakeStages { disk: Some((stored.clone(), false)), ..FakeStages::default() }, false);
+        let result = service.resolve(&song_query(200_000)).await;
+        assert_eq!((result.source, result.outcome), (None, LookupOutcome::Failed));
+        assert_eq!(result.data.lyrics, stored.lyrics);
+
+        // 没有任何数据：在线出错为失败，在线正常返回空为未找到
+        let service = LyricsService::new(FakeStages::default(), false);
+        assert_eq!(service.resolve(&song_query(200_000)).await.outcome, LookupOutcome::Failed);
+        let service = LyricsService::new(FakeStages { online: Some(ProviderLyrics::default()), ..FakeStages::default() }, false);
+        let result = service.resolve(&song_query(200_000)).await;
+        assert_eq!((result.source, result.outcome), (None, LookupOutcome::NotFound));
+    }
+
+    /// 用户歌词目录命中时覆盖磁盘缓存与本地/在线结果，封面沿用磁盘缓存
+    #[tokio::test]
+    async fn test_folder_overrides_other_sources() {
+        let stored = LyricsData {
+            lyrics: "[00:01.00]磁盘".to_string(),
+            album_pic_url: "/api/cover/003kQTm81fCMvb".to_string(),
+            album_mid: "003kQTm81fCMvb".to_string(),
+            ..LyricsData::default()
+        };
+        let stages = FakeStages {
+            folder: Some(ProviderLyrics { provider: "folder", lyrics: "[00:01.00]校对".to_string(), ..ProviderLyrics::default() }),
+            disk: Some((stored, true)),
+            local: local_lrc(),
+            ..FakeStages::default()
+        };
+        let online_calls = stages.online_calls.clone();
+        let service = LyricsService::new(stages, false);
+        let result = service.resolve(&song_query(200_000)).await;
+
+        assert_eq!((result.source, result.outcome), (None, LookupOutcome::Found));
+        assert_eq!(online_calls.load(Ordering::Relaxed), 0);
+        assert_eq!(result.data.lyrics, "[00:01.00]校对");
+        assert_eq!(result.data.trans, "");
+        assert_eq!(result.data.album_mid, "003kQTm81fCMvb");
+
+        // 没有磁盘缓存时单独查找封面
+        let stages = FakeStages {
+            folder: Some(ProviderLyrics { provider: "folder", lyrics: "[00:01.00]校对".to_string(), ..ProviderLyrics::default() }),
+            cover: Some(FoundCover { url: "https://example.com/cover.jpg".to_string(), album_mid: String::new(), origin: CoverOrigin::Online }),
+            ..FakeStages::default()
+        };
+        let result = LyricsService::new(stages, false).resolve(&song_query(200_000)).await;
+        assert_eq!(result.data.album_pic_url, "https://example.com/cover.jpg");
+    }
+
+    /// 固定的歌词优先于歌词目录与自动匹配；获取失败时沿用磁盘缓存，不退回自动匹配
+    #[tokio::test]
+    async fn test_pinned_song() {
+        let pin = LyricsPin::Song { provider: "netease".to_string(), id: "1234".to_string() };
+        let stages = FakeStages {
+            pinned: Some((pin.clone(), Some(ProviderLyrics {
+                provider: "netease",
+                lyrics: "[00:01.00]固定".to_string(),
+                pic_url: "https://y.gtimg.cn/music/photo_new/T002R800x800M000003kQTm81fCMvb.jpg".to_string(),
+                ..ProviderLyrics::default()
+            }))),
+            folder: Some(ProviderLyrics { provider: "folder", lyrics: "[00:01.00]校对".to_string(), ..ProviderLyrics::default() }),
+            local: local_lrc(),
+            ..FakeStages::default()
+        };
+        let online_calls = stages.online_calls.clone();
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        let result = LyricsService::new(stages, false).resolve_with_progress(&song_query(200_000), tx).await;
+
+        assert_eq!((result.source, result.outcome), (Some(LyricsSource::Online), LookupOutcome::Found));
+        assert_eq!(online_calls.load(Ordering::Relaxed), 0);
+        assert_eq!(result.data.lyrics, "[00:01.00]固定");
+        assert_eq!(result.data.trans, "");
+        assert_eq!(result.data.album_mid, "003kQTm81fCMvb");
+        assert!(matches!(rx.recv().await, Some(LyricsProgress::Pinned { pin: p, .. }) if p == "netease:1234"));
+
+        // 磁盘缓存未过期时直接使用，不再请求；过期后重新请求，出错时保留磁盘缓存并记为失败
+        let stored = LyricsData { lyrics: "[00:01.00]磁盘".to_string(), ..LyricsData::default() };
+        let stages = FakeStages { pinned: Some((pin.clone(), None)), disk: Some((stored.clone(), true)), local: local_lrc(), ..FakeStages::default() };
+        let pinned_calls = stages.pinned_calls.clone();
+        let result = LyricsService::new(stages, false).resolve(&song_query(200_000)).await;
+        assert_eq!((result.source, result.outcome), (None, LookupOutcome::Found));
+        assert_eq!(result.data, stored);
+        assert_eq!(pinned_calls.load(Ordering::Relaxed), 0);
+
+        let stages = FakeStages { pinned: Some((pin.clone(), None)), disk: Some((stored.clone(), false)), local: local_lrc(), ..FakeStages::default() };
+        let pinned_calls = stages.pinned_calls.clone();
+        let result = LyricsService::new(stages, false).resolve(&song_query(200_000)).await;
+        assert_eq!((result.source, result.outcome), (None, LookupOutcome::Failed));
+        assert_eq!(result.data, stored);
+        assert_eq!(pinned_calls.load(Ordering::Relaxed), 1);
+
+        let stages = FakeStages { pinned: Some((pin, Some(ProviderLyrics::default()))), local: local_lrc(), ..FakeStages::default() };
+        let result = LyricsService::new(stages, false).resolve(&song_query(200_000)).await;
+        assert_eq!((result.source, result.outcome), (None, LookupOutcome::NotFound));
+
+        // 固定本地文件每次重新读取，不写回磁盘缓存
+        let stages = FakeStages {
+            pinned: Some((LyricsPin::File { path: "那时雨.lrc".to_string() }, Some(ProviderLyrics { lyrics: "[00:01.00]文件".to_string(), ..ProviderLyrics::default() }))),
+            disk: Some((stored, true)),
+            ..FakeStages::default()
+        };
+        let result = LyricsService::new(stages, false).resolve(&song_query(200_000)).await;
+        assert_eq!((result.source, result.outcome), (None, LookupOutcome::Found));
+        assert_eq!(result.data.lyrics, "[00:01.00]文件");
+    }
+
+    /// 在线 QRC 无法解析为 XML 时，从 LRC 文本兜底解析逐字歌词
+    #[tokio::test]
+    async fn test_online_qrc_text_fallback() {
+        let stages = FakeStages {
+            online: Some(ProviderLyrics {
+                lyrics: "[1000,500]那(1000,250)时(1250,250)".to_string(),
+                qrc_raw: "not xml".to_string(),
+                ..ProviderLyrics::default()
+            }),
+            ..FakeStages::default()
+        };
+        let service = LyricsService::new(stages, false);
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        let result = service.resolve_with_progress(&song_query(200_000), tx).await;
+        assert_eq!(result.data.qrc_data.len(), 1);
+        assert_eq!(result.data.qrc_data[0].content, "那时");
+
+        let mut warned = false;
+        while let Some(event) = rx.recv().await {
+            warned |= matches!(event, LyricsProgress::Warning(_));
+        }
+        assert!(warned);
+    }
+}
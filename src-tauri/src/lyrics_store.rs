@@ -0,0 +1,219 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::song_info::QrcLine;
+
+/// 清理超限缓存时删到上限的该比例以下，避免每次写入都触发清理
+const PRUNE_TARGET_RATIO: f64 = 0.9;
+
+/// 歌词来源
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LyricsSource {
+    /// QQ 音乐本地缓存目录（QQMusicLyricNew）
+    #[default]
+    Local,
+    /// 在线接口
+    Online,
+}
+
+/// 持久化的歌词条目（每首歌一个 JSON 文件）
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct StoredLyrics {
+    pub title: String,
+    pub artist: String,
+    pub lyrics: String,
+    pub trans: String,
+    /// 已解析的逐字歌词，重启后无需再次解密
+    pub qrc_data: Vec<QrcLine>,
+    #[serde(default)]
+    pub album_mid: String,
+    /// 封面引用：在线地址或封面存储地址（不保存 data URI）
+    #[serde(default)]
+    pub cover_url: String,
+    /// 本地缓存文件名中的专辑名
+    #[serde(default)]
+    pub local_album: String,
+    #[serde(default)]
+    pub source: LyricsSource,
+    /// 在线搜索命中歌曲的匹配度（0~1）
+    #[serde(default)]
+    pub confidence: Option<f32>,
+    /// 获取时间（Unix 秒），用于 TTL 判断
+    pub fetched_at: u64,
+}
+
+/// 磁盘歌词缓存：目录内每首歌一个 `{key}.json`，key 为 `title|artist` 的稳定摘要。
+///
+/// 超过 TTL 的条目仍会被读取（先显示，再后台重新获取覆盖）；
+/// 目录总大小超过上限时按修改时间淘汰最旧的文件。
+pub struct LyricsStore {
+    dir: PathBuf,
+    ttl: Duration,
+    max_bytes: u64,
+    /// 目录当前总大小的估计值（写入时累加，清理时重新统计）
+    total_bytes: AtomicU64,
+}
+
+impl LyricsStore {
+    pub fn open(dir: impl Into<PathBuf>, ttl: Duration, max_bytes: u64) -> Result<Self> {
+        let dir = dir.into();
+        std::fs::create_dir_all(&dir).with_context(|| format!("无法创建歌词缓存目录 {}", dir.display()))?;
+        let store = Self { dir, ttl, max_bytes, total_bytes: AtomicU64::new(0) };
+        store.prune()?;
+        Ok(store)
+    }
+
+    fn path_for(&self, title: &str, artist: &str) -> PathBuf {
+        self.dir.join(format!("{:016x}.json", store_key(title, artist)))
+    }
+
+    /// 读取条目；文件损坏或 key 碰撞（标题/歌手不一致）时返回 None
+    pub fn load(&self, title: &str, artist: &str) -> Option<StoredLyrics> {
+        let data = std::fs::read(self.path_for(title, artist)).ok()?;
+        let stored: StoredLyrics = serde_json::from_slice(&data).ok()?;
+        (stored.title == title && stored.artist == artist).then_some(stored)
+    }
+
+    /// 条目是否仍在 TTL 内
+    pub fn is_fresh(&self, stored: &StoredLyrics) -> bool {
+        now_secs().saturating_sub(stored.fetched_at) < self.ttl.as_secs()
+    }
+
+    pub fn save(&self, stored: &StoredLyrics) -> Result<()> {
+        let path = self.path_for(&stored.title, &stored.artist);
+        let data = serde_json::to_vec(stored)?;
+        let previous = std::fs::metadata(&path).map(|m| m.len()).unwrap_or(0);
+        // 先写临时文件再改名，避免退出时留下半截 JSON
+        let tmp = path.with_extension("json.tmp");
+        std::fs::write(&tmp, &data)?;
+        std::fs::rename(&tmp, &path)?;
+
+        let total = self.total_bytes.load(Ordering::Relaxed).saturating_sub(previous) + data.len() as u64;
+        self.total_bytes.store(total, Ordering::Relaxed);
+        if total > self.max_bytes {
+            self.prune()?;
+        }
+        Ok(())
+    }
+
+    /// 删除条目（固定或取消固定歌词后使旧结果失效）；key 碰撞的其他歌曲不受影响
+    pub fn remove(&self, title: &str, artist: &str) -> Result<()> {
+        if self.load(title, artist).is_none() {
+            return Ok(());
+        }
+        let path = self.path_for(title, artist);
+        let len = std::fs::metadata(&path).map(|m| m.len()).unwrap_or(0);
+        std::fs::remove_file(&path)?;
+        let total = self.total_bytes.load(Ordering::Relaxed).saturating_sub(len);
+        self.total_bytes.store(total, Ordering::Relaxed);
+        Ok(())
+    }
+
+    /// 重新统计目录大小，超过上限时删除最旧的条目
+    pub fn prune(&self) -> Result<()> {
+        let mut files: Vec<(SystemTime, u64, PathBuf)> = std::fs::read_dir(&self.dir)?
+            .flatten()
+            .filter(|e| e.path().extension().is_some_and(|ext| ext == "json"))
+            .filter_map(|e| {
+                let meta = e.metadata().ok()?;
+                Some((meta.modified().unwrap_or(UNIX_EPOCH), meta.len(), e.path()))
+            })
+            .collect();
+        let mut total: u64 = files.iter().map(|(_, len, _)| len).sum();
+        if total > self.max_bytes {
+            let target = (self.max_bytes as f64 * PRUNE_TARGET_RATIO) as u64;
+            files.sort_by_key(|(modified, _, _)| *modified);
+            for (_, len, path) in files {
+                if total <= target {
+                    break;
+                }
+                if std::fs::remove_file(&path).is_ok() {
+                    total -= len;
+                }
+            }
+        }
+        self.total_bytes.store(total, Ordering::Relaxed);
+        Ok(())
+    }
+}
+
+pub fn now_secs() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0)
+}
+
+/// FNV-1a 64：文件名需跨版本稳定，不能使用 DefaultHasher
+fn store_key(title: &str, artist: &str) -> u64 {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for byte in title.bytes().chain(std::iter::once(b'|')).chain(artist.bytes()) {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+    }
+    hash
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::temp_dir;
+
+    fn stored(title: &str, fetched_at: u64) -> StoredLyrics {
+        StoredLyrics {
+            title: title.to_string(),
+            artist: "徐良".to_string(),
+            lyrics: "[00:01.00]那时雨".to_string(),
+            album_mid: "003kQTm81fCMvb".to_string(),
+            source: LyricsSource::Online,
+            fetched_at,
+            ..StoredLyrics::default()
+        }
+    }
+
+    #[test]
+    fn test_round_trip_and_ttl() {
+        let dir = temp_dir("lyrics_store_round_trip");
+        let store = LyricsStore::open(&dir, Duration::from_secs(3600), 1 << 20).unwrap();
+        let fresh = stored("那时雨", now_secs());
+        store.save(&fresh).unwrap();
+        assert_eq!(store.load("那时雨", "徐良"), Some(fresh.clone()));
+        assert!(store.is_fresh(&fresh));
+        assert!(!store.is_fresh(&stored("那时雨", now_secs() - 7200)));
+        assert!(store.load("那时雨", "别人").is_none());
+
+        // 重启后仍可读取
+        drop(store);
+        let store = LyricsStore::open(&dir, Duration::from_secs(3600), 1 << 20).unwrap();
+        assert_eq!(store.load("那时雨", "徐良"), Some(fresh));
+
+        store.remove("那时雨", "别人").unwrap();
+        assert!(store.load("那时雨", "徐良").is_some());
+        store.remove("那时雨", "徐良").unwrap();
+        assert!(store.load("那时雨", "徐良").is_none());
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    /// 超过大小上限时淘汰最旧的条目
+    #[test]
+    fn test_prune_by_size() {
+        let dir = temp_dir("lyrics_store_prune");
+        let entry_len = serde_json::to_vec(&stored("歌曲 0", 0)).unwrap().len() as u64;
+        let store = LyricsStore::open(&dir, Duration::from_secs(3600), entry_len * 3).unwrap();
+        for i in 0..5 {
+            store.save(&stored(&format!("歌曲 {}", i), now_secs())).unwrap();
+            // 保证修改时间可区分
+            std::thread::sleep(Duration::from_millis(20));
+        }
+        assert!(store.load("歌曲 0", "徐良").is_none());
+        assert!(store.load("歌曲 4", "徐良").is_some());
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    /// 文件名在不同版本/平台间保持不变，升级后仍能命中旧缓存
+    #[test]
+    fn test_store_key_is_stable() {
+        assert_eq!(store_key("那时雨", "徐良"), 0xc178_ba44_d4bf_e4a5);
+    }
+}
@@ -7,6 +7,14 @@ use tokio::sync::RwLock;
 static IS_BACKGROUND: AtomicBool = AtomicBool::new(false);
 static CONFIG: OnceLock<RwLock<Config>> = OnceLock::new();
 static CACHED_CACHE_ROOT: OnceLock<Option<PathBuf>> = OnceLock::new();
+/// 磁盘歌词缓存（`lyrics_cache_dir` 为空或目录不可用时为 None）
+static LYRICS_STORE: OnceLock<Option<LyricsStore>> = OnceLock::new();
+/// QQ 音乐本地缓存索引（未找到缓存目录或无法监听时为 None，查找退回直接读目录）
+static LOCAL_INDEX: OnceLock<Option<LocalIndex>> = OnceLock::new();
+/// 手动固定的歌词（`lyrics_pins_file` 为空或文件无法读取时为 None）
+static LYRICS_PINS: OnceLock<Option<PinStore>> = OnceLock::new();
+/// 单曲歌词偏移（`lyrics_offsets_file` 为空或文件无法读取时为 None）
+static LYRICS_OFFSETS: OnceLock<Option<OffsetStore>> = OnceLock::new();
 
 #[derive(Debug, serde::Serialize, serde::Deserialize)]
 pub struct FrontConfig {
@@ -24,6 +32,72 @@ fn set_background_state(is_background: bool) {
     IS_BACKGROUND.store(is_background, Ordering::SeqCst);
 }
 
+#[tauri::command]
+// Function to toggle play/pause on the current media session.
+async fn play_pause() -> Result<(), String> {
+    control::send(PlaybackCommand::PlayPause).await.map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+// Function to skip to the next track.
+async fn next_track() -> Result<(), String> {
+    control::send(PlaybackCommand::Next).await.map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+// Function to skip to the previous track.
+async fn previous_track() -> Result<(), String> {
+    control::send(PlaybackCommand::Previous).await.map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+// Function to seek the current track to the given position.
+async fn seek_to(position_ms: u64) -> Result<(), String> {
+    control::send(PlaybackCommand::Seek { position_ms }).await.map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+// Function to seek to the start of a lyric line and report whether the player honored it.
+async fn seek_to_line(line: usize) -> Result<SeekResult, String> {
+    control::seek_to(SeekTarget::Line { line }).await.map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+// Function to seek to a lyric timestamp and report whether the player honored it.
+async fn seek_to_lyric_time(time_ms: u64) -> Result<SeekResult, String> {
+    control::seek_to(SeekTarget::Time { time_ms }).await.map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+// Function to list lyric candidates for the current track, best match first.
+async fn list_lyric_candidates() -> Result<Vec<LyricCandidate>, String> {
+    control::lyric_candidates().await.map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+// Function to pin the current track to a chosen candidate or lyric file.
+async fn pin_lyrics(pin: LyricsPin) -> Result<(), String> {
+    control::pin_lyrics(Some(pin)).await.map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+// Function to remove the current track's pin and go back to automatic matching.
+async fn unpin_lyrics() -> Result<(), String> {
+    control::pin_lyrics(None).await.map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+// Function to set the current track's lyric offset, returning the saved value.
+async fn set_lyrics_offset(offset_ms: i64) -> Result<i64, String> {
+    control::lyrics_offset(OffsetChange::Set { offset_ms }).await.map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+// Function to nudge the current track's lyric offset, returning the saved value.
+async fn adjust_lyrics_offset(delta_ms: i64) -> Result<i64, String> {
+    control::lyrics_offset(OffsetChange::Adjust { delta_ms }).await.map_err(|e| e.to_string())
+}
+
 #[tauri::command]
 // Function to get current configuration asynchronously.
 async fn get_app_config() -> Result<FrontConfig, String> {
@@ -80,17 +154,56 @@ use widestring::U16String;
 
 mod cli;
 mod config;
+mod media_source;
+#[cfg(windows)]
 mod smtc;
+#[cfg(target_os = "linux")]
+mod mpris;
 mod lyrics;
 mod song_info;
 mod qrc;
+mod krc;
 mod local_qrc; // Enable local QRC cache module
+mod local_index;
 mod server;
+mod replay;
+mod capture;
+mod control;
+mod session_filter;
+mod sessions;
+mod protocol;
+mod cover_store;
+mod cover_image;
+mod lyrics_store;
+mod lyrics_retry;
+mod lyrics_service;
+mod lyric_provider;
+mod netease;
+mod kugou;
+mod lrclib;
+mod lyrics_folder;
+mod lyrics_pins;
+mod lyrics_offsets;
+mod song_map;
+mod song_match;
+#[cfg(test)]
+mod test_support;
 
 use cli::Cli;
 use config::Config;
+use control::{ControlRequest, PendingSeek, PlaybackCommand, SeekResult, SeekTarget};
+use sessions::SessionsSnapshot;
 use song_info::{SongInfo, QrcLine};
 use lyrics::LyricFetcher;
+use local_index::{LocalIndex, LyricFileKind};
+use lyric_provider::LyricCandidate;
+use lyrics_pins::{LyricsPin, PinStore};
+use song_map::SongMap;
+use lyrics_offsets::{OffsetChange, OffsetStore};
+use lyrics_store::{LyricsSource, LyricsStore, StoredLyrics};
+use lyrics_retry::{LookupOutcome, RetrySchedule};
+use lyrics_service::{CoverOrigin, LyricsData, LyricsProgress, LyricsService, DefaultStages};
+use song_match::{LyricsQuery, NormalizedQuery};
 
 use std::collections::HashMap;
 
@@ -106,6 +219,7 @@ fn current_timestamp_ms() -> u64 {
 /// 每条含歌词文本 + QRC 逐字数据，按平均 8KB/条估算，128 条约 1MB 内存。
 const LYRICS_CACHE_MAX_ENTRIES: usize = 128;
 
+#[derive(Default)]
 struct LyricsCacheEntry {
     lyrics: String,
     trans: String,
@@ -115,10 +229,34 @@ struct LyricsCacheEntry {
     /// 本地缓存文件名中解析出的专辑名（QQ 音乐索引时的原始名），
     /// 比依赖 SMTC 报告的 album 更可靠，用作在线 album_mid 解析的搜索词。
     local_album: String,
+    /// 已解析的 album_mid，随磁盘缓存保存，重启后可直接重建封面地址
+    album_mid: String,
+    /// 在线搜索命中歌曲的匹配度，随 SongInfo 下发
+    confidence: Option<f32>,
+    /// 连续查找失败（网络错误等）的次数，找到或确认未找到后清零
+    failures: u32,
+    /// 下次重新查找的时间戳（毫秒），None 表示不再查找
+    retry_at: Option<u64>,
     /// 最后访问时间戳（毫秒），用于 LRU 淘汰
     last_accessed: u64,
 }
 
+impl From<LyricsData> for LyricsCacheEntry {
+    fn from(data: LyricsData) -> Self {
+        Self {
+            lyrics: data.lyrics,
+            trans: data.trans,
+            qrc_raw: data.qrc_raw,
+            qrc_data: data.qrc_data,
+            album_pic_url: data.album_pic_url,
+            local_album: data.local_album,
+            album_mid: data.album_mid,
+            confidence: data.confidence,
+            ..Self::default()
+        }
+    }
+}
+
 /// 后台歌词缓存 — 由主循环读取、后台任务写入 (使用 HashMap 支持多歌曲缓存，防止切歌竞态)
 /// 超过 LYRICS_CACHE_MAX_ENTRIES 时淘汰最久未访问的条目（近似 LRU）。
 struct LyricsCache {
@@ -160,9 +298,19 @@ impl LyricsCache {
             self.evict_oldest();
         }
 
+        // 重试期间条目可能被本地/过期数据替换，保留连续失败次数
+        if let Some(old) = self.entries.get(&key) {
+            entry.failures = old.failures;
+        }
         self.entries.insert(key, entry);
     }
 
+    /// 删除条目：下一帧按新切歌重新加载
+    fn remove_entry(&mut self, title: &str, artist: &str) {
+        let key = format!("{}|{}", title, artist);
+        self.entries.remove(&key);
+    }
+
     /// 部分更新：仅覆盖缓存中某首歌的 album_pic_url
     fn update_album_pic_url(&mut self, title: &str, artist: &str, url: String) {
         let key = format!("{}|{}", title, artist);
@@ -172,6 +320,41 @@ impl LyricsCache {
         }
     }
 
+    /// 部分更新：记录封面查找中解析出的 album_mid
+    fn update_album_mid(&mut self, title: &str, artist: &str, album_mid: String) {
+        let key = format!("{}|{}", title, artist);
+        if let Some(entry) = self.entries.get_mut(&key) {
+            entry.album_mid = album_mid;
+        }
+    }
+
+    /// 记录一次查找结果并安排下次查找（见 lyrics_retry）
+    fn record_outcome(&mut self, title: &str, artist: &str, outcome: LookupOutcome, schedule: &RetrySchedule) {
+        let key = format!("{}|{}", title, artist);
+        if let Some(entry) = self.entries.get_mut(&key) {
+            entry.failures = match outcome {
+                LookupOutcome::Failed => entry.failures.saturating_add(1),
+                _ => 0,
+            };
+            entry.retry_at = schedule
+                .next_attempt_in(outcome, entry.failures)
+                .map(|delay| current_timestamp_ms() + delay.as_millis() as u64);
+        }
+    }
+
+    /// 到达重新查找时间时返回 true 并清除计划，避免后续帧重复 spawn
+    fn take_due_retry(&mut self, title: &str, artist: &str) -> bool {
+        let key = format!("{}|{}", title, artist);
+        let now = current_timestamp_ms();
+        match self.entries.get_mut(&key) {
+            Some(entry) if entry.retry_at.is_some_and(|at| at <= now) => {
+                entry.retry_at = None;
+                true
+            }
+            _ => false,
+        }
+    }
+
     /// 淘汰 last_accessed 最小的条目
     fn evict_oldest(&mut self) {
         if let Some((oldest_key, _)) = self
@@ -195,170 +378,403 @@ fn get_lyric_cache_dir() -> Option<PathBuf> {
     get_cache_root().map(|root| root.join("QQMusicLyricNew"))
 }
 
+/// 允许固定的歌词文件所在目录：QQ 音乐歌词缓存目录与歌词目录（`lyrics_folder`）
+async fn pin_file_roots() -> Vec<PathBuf> {
+    let mut roots: Vec<PathBuf> = get_lyric_cache_dir().into_iter().collect();
+    if let Some(config) = CONFIG.get() {
+        let folder = config.read().await.settings.lyrics_folder.trim().to_string();
+        if !folder.is_empty() {
+            roots.push(PathBuf::from(folder));
+        }
+    }
+    roots
+}
+
 /// 获取封面图缓存目录（QQMusicPicture）
 fn get_picture_cache_dir() -> Option<PathBuf> {
     get_cache_root().map(|root| root.join("QQMusicPicture"))
 }
 
-/// 清洗专辑名：去掉中英文括号内容及前后空格，用于 SmartBox 模糊匹配。
-/// 例：`ONE PIECE SUPER BEST (海贼王10周年 SUPER BEST)` → `ONE PIECE SUPER BEST`
-fn clean_album_name(raw: &str) -> String {
-    let mut s = raw.to_string();
-    while let Some(start) = s.find('(') {
-        if let Some(end) = s[start..].find(')') {
-            s.replace_range(start..start + end + 1, "");
-        } else {
-            break;
+/// 检测切歌并修正切歌瞬间的陈旧时间轴，返回本帧是否切歌。
+///
+/// SMTC 切歌瞬间修正：media properties（title/artist）先更新，
+/// timeline（Position/EndTime）可能滞后 1~2 秒仍报旧歌数据。
+///
+/// 只靠 song_changed 一帧归零不够：后续帧 title 未变但 timeline 仍旧，
+/// 前端会收到"新歌名 + 旧进度"错配——旧歌词继续渲染，进度来回跳。
+///
+/// 解法：用 last_song_info.total_time_ms 做锚点——若当前 total 与旧歌
+/// 相同，判定 timeline 未更新，强制归零。total 变化后清除追踪。
+fn correct_stale_timeline(
+    last_song_info: Option<&SongInfo>,
+    stale_song_total: &mut Option<u64>,
+    current: &mut SongInfo,
+) -> bool {
+    let song_changed = match last_song_info {
+        Some(last) => last.title != current.title || last.artist != current.artist,
+        None => true,
+    };
+
+    if song_changed {
+        if let Some(old) = last_song_info {
+            if old.total_time_ms > 0 {
+                *stale_song_total = Some(old.total_time_ms);
+            }
         }
-    }
-    while let Some(start) = s.find('（') {
-        if let Some(end) = s[start..].find('）') {
-            let end_byte = start + end + '）'.len_utf8();
-            s.replace_range(start..end_byte, "");
+        zero_timeline(current);
+    } else if let Some(old_total) = *stale_song_total {
+        if current.total_time_ms == 0 || current.total_time_ms == old_total {
+            zero_timeline(current);
         } else {
-            break;
+            *stale_song_total = None;
         }
     }
-    s.trim().to_string()
+
+    song_changed
 }
 
-/// 优先使用本地缓存的专辑封面图，找不到则返回原始在线 URL。
-///
-/// 本地封面文件名含 album_mid，可从在线 URL 中提取 mid 后在 QQMusicPicture 目录查找。
-/// 找到则读取文件转为 base64 data URI（与 SMTC 缩略图格式一致，前端可直接加载）。
+/// 事件驱动源在暂停/无播放时的最长等待（毫秒）
+const EVENT_IDLE_WAIT_MS: u64 = 2000;
+/// 没有显著变化时的心跳推送间隔（毫秒），供前端校准插值
+const PUSH_HEARTBEAT_MS: u64 = 1000;
+/// 实际位置与按上次推送外推的位置相差超过此值视为 seek（毫秒）
+const PUSH_SEEK_THRESHOLD_MS: u64 = 500;
+
+/// 是否需要把当前状态推送给前端。
 ///
-/// 当 API 返回的 album_mid 在本地没找到时（如单曲版 vs 合辑版不一致），
-/// 用 SMTC 报告的专辑名通过 SmartBox 搜索正确的 album_mid，再查本地。
-async fn resolve_album_pic_url(online_url: &str, album_name: &str, fetcher: &LyricFetcher) -> String {
-    if online_url.is_empty() {
-        return String::new();
+/// 前端基于 `current_time_ms` + 本地时钟自行插值，因此只有歌曲/播放状态/时长/
+/// 歌词/封面变化、位置跳变（seek）或心跳到期时才推送。
+fn should_push(last: Option<&SongInfo>, current: &SongInfo) -> bool {
+    let Some(last) = last else {
+        return true;
+    };
+
+    if last.title != current.title
+        || last.artist != current.artist
+        || last.album != current.album
+        || last.is_playing != current.is_playing
+        || last.total_time_ms != current.total_time_ms
+        || last.lyrics != current.lyrics
+        || last.trans != current.trans
+        || last.qrc_data.len() != current.qrc_data.len()
+        || last.album_pic_url != current.album_pic_url
+        || last.source_app != current.source_app
+        || last.shuffle != current.shuffle
+        || last.repeat_mode != current.repeat_mode
+        || last.playback_rate != current.playback_rate
+        || last.lyrics_confidence != current.lyrics_confidence
+        || last.lyrics_offset_ms != current.lyrics_offset_ms
+    {
+        return true;
     }
 
-    // 只处理 QQ 音乐 CDN 的在线 URL（data:image base64 是 SMTC 缩略图，不替换）
-    if !online_url.contains("y.gtimg.cn") {
-        return online_url.to_string();
+    let since_last_ms = current.server_ts.saturating_sub(last.server_ts);
+    if since_last_ms >= PUSH_HEARTBEAT_MS {
+        return true;
     }
 
-    let Some(pic_dir) = get_picture_cache_dir() else {
-        return online_url.to_string();
+    let expected_ms = if last.is_playing {
+        last.current_time_ms + song_info::scale_elapsed(since_last_ms, last.rate())
+    } else {
+        last.current_time_ms
     };
+    current.current_time_ms.abs_diff(expected_ms) > PUSH_SEEK_THRESHOLD_MS
+}
 
-    // 第一次尝试：用在线 URL 中的 album_mid 查本地
-    if let Some(album_mid) = local_qrc::extract_album_mid_from_url(online_url) {
-        if let Some(local_path) = local_qrc::find_album_pic(&pic_dir, &album_mid) {
-            if let Ok(data) = std::fs::read(&local_path) {
-                use base64::{Engine as _, engine::general_purpose::STANDARD};
-                return format!("data:image/jpeg;base64,{}", STANDARD.encode(&data));
+fn zero_timeline(info: &mut SongInfo) {
+    info.current_time_ms = 0;
+    info.current_time = 0;
+    info.progress_percent = 0.0;
+    info.total_time_ms = 0;
+    info.total_time = 0;
+}
+
+/// 后台加载歌词：由 `LyricsService` 完成查找，阶段性结果（磁盘缓存、本地歌词）立即写入 `cache`，
+/// 完成后写入最终结果、写回磁盘缓存并安排下次查找。调用方负责先插入占位条目，避免重复加载。
+fn spawn_lyrics_load(
+    cache: Arc<RwLock<LyricsCache>>,
+    service: Arc<LyricsService>,
+    info: &SongInfo,
+    quiet: bool,
+    debug: bool,
+) {
+    let query = LyricsQuery::from_song(info);
+    tokio::spawn(async move {
+        let (t, a) = (&query.title, &query.artist);
+        let (progress_tx, mut progress_rx) = tokio::sync::mpsc::unbounded_channel();
+        let report = async {
+            while let Some(event) = progress_rx.recv().await {
+                match event {
+                    LyricsProgress::Partial(data) => cache.write().await.insert_entry(t, a, data.into()),
+                    event if !quiet => report_lyrics_progress(&query, &event),
+                    _ => {}
+                }
             }
+        };
+        let (result, ()) = tokio::join!(service.resolve_with_progress(&query, progress_tx), report);
+
+        cache.write().await.insert_entry(t, a, result.data.into());
+        if let Some(source) = result.source {
+            persist_lyrics(&cache, t, a, source).await;
         }
-    }
 
-    // 第二次尝试：用 SMTC 专辑名搜 SmartBox，拿到用户实际播放专辑的 album_mid。
-    // 尝试原始名和去括号清洗后的变体，处理 SMTC 返回"ONE PIECE SUPER BEST (海贼王…)" 但 SmartBox 只索引"ONE PIECE SUPER BEST"的情况。
-    if !album_name.is_empty() {
-        let mut variants: Vec<&str> = vec![album_name];
-        let cleaned = clean_album_name(album_name);
-        if cleaned != album_name {
-            variants.push(&cleaned);
+        // 安排下次查找：找到则不再查找，失败按退避重试，未找到则隔一段时间再查
+        let schedule = lyrics_retry_schedule().await;
+        let mut w = cache.write().await;
+        w.record_outcome(t, a, result.outcome, &schedule);
+        if debug {
+            if let Some(entry) = w.peek_entry(t, a) {
+                eprintln!("[lyrics] Lookup outcome for '{} - {}': {:?} (failures={}, retry_at={:?})",
+                    t, a, result.outcome, entry.failures, entry.retry_at);
+            }
+        }
+    });
+}
+
+/// 把歌词查找进度输出到 TUI 日志
+fn report_lyrics_progress(query: &LyricsQuery, event: &LyricsProgress) {
+    let mark = |ok: bool| if ok { "✓" } else { "✗" };
+    match event {
+        LyricsProgress::Pinned { pin, summary } => {
+            eprintln!("  已固定({}): 逐字 {}行 | 歌词 {} | 翻译 {}",
+                pin, summary.qrc_lines, mark(summary.lyrics), mark(summary.trans));
         }
-        for variant in &variants {
-            if let Some(correct_mid) = fetcher.search_album_mid_by_name(variant).await {
-                if let Some(local_path) = local_qrc::find_album_pic(&pic_dir, &correct_mid) {
-                    if let Ok(data) = std::fs::read(&local_path) {
-                        use base64::{Engine as _, engine::general_purpose::STANDARD};
-                        return format!("data:image/jpeg;base64,{}", STANDARD.encode(&data));
+        LyricsProgress::PinnedFailed { pin, error } => eprintln!("  已固定({}): ✗ {}", pin, error),
+        LyricsProgress::Folder(summary) => {
+            eprintln!("  歌词目录: 逐字 {}行 | 歌词 {} | 翻译 {}",
+                summary.qrc_lines, mark(summary.lyrics), mark(summary.trans));
+        }
+        LyricsProgress::DiskCache { fresh: true } => eprintln!("  磁盘缓存: ✓"),
+        LyricsProgress::DiskCache { fresh: false } => eprintln!("  磁盘缓存: 已过期，重新获取"),
+        LyricsProgress::Local(summary) => {
+            let cache_dir_info = match get_lyric_cache_dir() {
+                Some(ref dir) => match local_index() {
+                    Some(index) => format!("{} ({} QRC / {} LRC，已索引)", dir.display(),
+                        index.lyric_file_count(LyricFileKind::Qrc), index.lyric_file_count(LyricFileKind::Lrc)),
+                    None => {
+                        let file_count = std::fs::read_dir(dir).map(|d| d.count()).unwrap_or(0);
+                        format!("{} ({}个文件)", dir.display(), file_count)
                     }
-                }
+                },
+                None => "未找到本地缓存目录".to_string(),
+            };
+            if summary.is_empty() {
+                eprintln!("  本地: ✗ 无数据 ({})", cache_dir_info);
+                report_local_candidates(query);
+            } else {
+                eprintln!("  本地: QRC {}行 | 歌词 {} | 翻译 {} | {}",
+                    summary.qrc_lines, mark(summary.lyrics), mark(summary.trans), cache_dir_info);
             }
         }
+        LyricsProgress::Online { provider, summary } => {
+            eprintln!("  在线({}): QRC {}行 | 歌词 {} | 翻译 {} | 封面 {}",
+                provider, summary.qrc_lines, mark(summary.lyrics), mark(summary.trans), mark(summary.cover));
+        }
+        LyricsProgress::OnlineNotFound => eprintln!("  在线: ✗ 无数据"),
+        LyricsProgress::OnlineFailed(e) => eprintln!("  在线: ✗ 失败 ({})", e),
+        LyricsProgress::CoverSearch => eprintln!("  封面: 查找中..."),
+        LyricsProgress::Cover(Some(CoverOrigin::Local)) => eprintln!("  封面: ✓ 本地"),
+        LyricsProgress::Cover(Some(CoverOrigin::Online)) => eprintln!("  封面: ✓ 在线"),
+        LyricsProgress::Cover(None) => eprintln!("  封面: ✗ 未找到"),
+        LyricsProgress::Warning(message) => eprintln!("{}", message),
+        LyricsProgress::Partial(_) => {}
     }
-
-    online_url.to_string()
 }
 
-/// 从本地 QQ 音乐缓存目录查找歌词（QRC 优先，LRC 兜底），返回完整的缓存条目。
-/// 此函数包含文件 I/O 与 DES 解密，仅应在 spawn_blocking 中调用。
-fn lookup_local_lyrics(title: &str, artist: &str) -> LyricsCacheEntry {
-    let mut entry = LyricsCacheEntry {
-        lyrics: String::new(),
-        trans: String::new(),
-        qrc_raw: String::new(),
-        qrc_data: Vec::new(),
-        album_pic_url: String::new(),
-        local_album: String::new(),
-        last_accessed: 0,
+/// 本地无数据时的诊断：显示文件名格式 + 模糊匹配候选
+fn report_local_candidates(query: &LyricsQuery) {
+    let norm_query = NormalizedQuery::new(query);
+    eprintln!("       搜索词: artist='{}' title='{}'", norm_query.artist, norm_query.title);
+    let Some(dir) = get_lyric_cache_dir() else {
+        return;
     };
+    let mut qrc_count = 0u32;
+    let mut best_candidate: Option<(String, u32)> = None;
+    local_index::for_each_lyric_file(&dir, LyricFileKind::Qrc, |fname, parsed| {
+        qrc_count += 1;
+        // 与实际查找使用同一套打分
+        let Some(score) = local_qrc::score_normalized(&parsed.normalized(), &norm_query) else {
+            return;
+        };
+        let better = match &best_candidate {
+            Some((_, best)) => score > *best,
+            None => score > 0,
+        };
+        if better {
+            best_candidate = Some((fname.to_string(), score));
+        }
+    });
+    eprintln!("       目录QRC总数: {} | 最佳候选: {}", qrc_count,
+        best_candidate.map_or("无部分匹配".to_string(), |(f, s)| format!("'{}' (分数:{})", f, s)));
+}
 
-    let Some(cache_dir) = get_lyric_cache_dir() else {
-        return entry;
+/// 按当前配置构造重试计划
+async fn lyrics_retry_schedule() -> RetrySchedule {
+    let (max_retries, recheck_minutes) = match CONFIG.get() {
+        Some(config) => {
+            let config = config.read().await;
+            (config.settings.max_retries, config.settings.lyrics_not_found_recheck_minutes)
+        }
+        None => {
+            let settings = Config::default().settings;
+            (settings.max_retries, settings.lyrics_not_found_recheck_minutes)
+        }
     };
+    RetrySchedule {
+        max_retries,
+        not_found_recheck: std::time::Duration::from_secs(recheck_minutes.saturating_mul(60)),
+    }
+}
 
-    // QRC 优先（逐字歌词）
-    if let Some(qrc_file) = local_qrc::find_qrc_file(&cache_dir, title, artist) {
-        // 记录本地文件名中的专辑名，供后续在线专辑封面解析复用
-        if let Some(fname) = qrc_file.file_name().and_then(|n| n.to_str()) {
-            entry.local_album = local_qrc::parse_lyric_filename(fname).2;
-        }
-        match qrc::decode_qrc_from_file(&qrc_file) {
-            Ok(xml) => {
-                entry.qrc_raw = "[local]".to_string();
-                if let Ok(lines) = qrc::parse_qrc_xml(&xml) {
-                    entry.qrc_data = lines;
-                }
-                if entry.lyrics.is_empty() {
-                    entry.lyrics = qrc::extract_lrc_from_xml(&xml).unwrap_or_default();
-                }
-                if let Some(trans_file) = local_qrc::find_qrc_trans_file(&qrc_file) {
-                    if let Ok(trans_xml) = qrc::decode_qrc_from_file(&trans_file) {
-                        entry.trans = trans_xml;
-                    }
+fn lyrics_store() -> Option<&'static LyricsStore> {
+    LYRICS_STORE.get().and_then(Option::as_ref)
+}
+
+/// 打开磁盘歌词缓存，只在启动时调用一次
+fn init_lyrics_store(settings: &config::Settings) {
+    let store = match config::resolve_data_path(&settings.lyrics_cache_dir) {
+        None => None,
+        Some(dir) => {
+            let ttl = std::time::Duration::from_secs(settings.lyrics_cache_ttl_hours * 3600);
+            match LyricsStore::open(dir, ttl, settings.lyrics_cache_max_mb * 1024 * 1024) {
+                Ok(store) => Some(store),
+                Err(e) => {
+                    eprintln!("⚠️  磁盘歌词缓存不可用: {:#}", e);
+                    None
                 }
             }
+        }
+    };
+    let _ = LYRICS_STORE.set(store);
+}
+
+fn local_index() -> Option<&'static LocalIndex> {
+    LOCAL_INDEX.get().and_then(Option::as_ref)
+}
+
+/// 建立 QQ 音乐本地缓存索引并开始监听，只在启动时调用一次
+fn init_local_index(debug: bool) {
+    let index = match (get_lyric_cache_dir(), get_picture_cache_dir()) {
+        (Some(lyric_dir), Some(picture_dir)) => match LocalIndex::open(&lyric_dir, &picture_dir, debug) {
+            Ok(index) => Some(index),
             Err(e) => {
-                eprintln!("  ⚠ QRC解密失败: {:?} — {}", qrc_file.file_name().unwrap_or_default(), e);
+                eprintln!("⚠️  本地缓存索引不可用，将逐次扫描目录: {:#}", e);
+                None
             }
+        },
+        _ => None,
+    };
+    let _ = LOCAL_INDEX.set(index);
+}
+
+fn pin_store() -> Option<&'static PinStore> {
+    LYRICS_PINS.get().and_then(Option::as_ref)
+}
+
+fn offset_store() -> Option<&'static OffsetStore> {
+    LYRICS_OFFSETS.get().and_then(Option::as_ref)
+}
+
+/// 当前歌曲的单曲歌词偏移（毫秒），未设置时为 0
+fn song_lyrics_offset(info: &SongInfo) -> i64 {
+    offset_store().and_then(|store| store.get(&info.title, &info.artist)).unwrap_or(0)
+}
+
+/// 打开按歌曲保存的映射文件；路径为空（`path` 为 None）时不启用
+fn open_song_map<V>(path: Option<PathBuf>, name: &str) -> Option<SongMap<V>>
+where
+    V: Clone + serde::Serialize + serde::de::DeserializeOwned,
+{
+    match SongMap::open(path?) {
+        Ok(map) => Some(map),
+        Err(e) => {
+            // 文件格式错误时不启用，避免覆盖用户手动编辑的内容
+            eprintln!("⚠️  {}不可用: {:#}", name, e);
+            None
         }
     }
+}
 
-    // LRC 兜底（普通歌词，无逐字）
-    if entry.lyrics.is_empty() {
-        if let Some(lrc_file) = local_qrc::find_lrc_file(&cache_dir, title, artist) {
-            let lrc_raw = match qrc::decode_qrc_from_file(&lrc_file) {
-                Ok(decrypted) => decrypted,
-                Err(_) => std::fs::read_to_string(&lrc_file).unwrap_or_default(),
-            };
-            entry.lyrics = qrc::extract_lrc_from_xml(&lrc_raw).unwrap_or(lrc_raw);
-            if let Some(trans_lrc_file) = local_qrc::find_lrc_trans_file(&lrc_file) {
-                let trans_raw = match qrc::decode_qrc_from_file(&trans_lrc_file) {
-                    Ok(decrypted) => decrypted,
-                    Err(_) => std::fs::read_to_string(&trans_lrc_file).unwrap_or_default(),
-                };
-                entry.trans = qrc::extract_lrc_from_xml(&trans_raw).unwrap_or(trans_raw);
-            }
+/// 读取手动固定歌词与单曲歌词偏移的映射文件，只在启动时调用一次
+fn init_song_maps(settings: &config::Settings) {
+    let _ = LYRICS_PINS.set(open_song_map(config::resolve_data_path(&settings.lyrics_pins_file), "歌词固定映射"));
+    let _ = LYRICS_OFFSETS.set(open_song_map(config::resolve_data_path(&settings.lyrics_offsets_file), "单曲歌词偏移"));
+}
+
+/// 把内存缓存中的条目写入磁盘缓存；没有歌词的条目不保存
+async fn persist_lyrics(cache: &Arc<RwLock<LyricsCache>>, title: &str, artist: &str, source: LyricsSource) {
+    if lyrics_store().is_none() {
+        return;
+    }
+    let stored = {
+        let cached = cache.read().await;
+        let Some(entry) = cached.peek_entry(title, artist) else {
+            return;
+        };
+        if entry.lyrics.is_empty() && entry.qrc_data.is_empty() {
+            return;
+        }
+        let album_mid = if entry.album_mid.is_empty() {
+            local_qrc::extract_album_mid_from_url(&entry.album_pic_url).unwrap_or_default()
         } else {
-            // LRC 也未找到：打印诊断，帮助判断是文件名匹配问题还是真的没有文件
-            if let Ok(entries) = std::fs::read_dir(&cache_dir) {
-                let norm_title = local_qrc::normalize(title);
-                let mut lrc_candidates: Vec<String> = Vec::new();
-                for e in entries.flatten() {
-                    let fname = match e.file_name().to_str() {
-                        Some(n) => n.to_string(), None => continue,
-                    };
-                    if !fname.ends_with("_qm.lrc") { continue; }
-                    let base = fname.trim_end_matches("_qm.lrc");
-                    let parts: Vec<&str> = base.splitn(4, " - ").collect();
-                    if parts.len() >= 2 && local_qrc::normalize(parts[1]).contains(&norm_title) {
-                        lrc_candidates.push(fname);
-                    }
-                }
-                if !lrc_candidates.is_empty() {
-                    eprintln!("  ℹ LRC候选(未匹配): {}", lrc_candidates.join(", "));
-                }
-            }
+            entry.album_mid.clone()
+        };
+        StoredLyrics {
+            title: title.to_string(),
+            artist: artist.to_string(),
+            lyrics: entry.lyrics.clone(),
+            trans: entry.trans.clone(),
+            qrc_data: entry.qrc_data.clone(),
+            album_mid,
+            // data URI（未启用同步服务时的封面）体积大，只保存 album_mid
+            cover_url: if entry.album_pic_url.starts_with("data:") { String::new() } else { entry.album_pic_url.clone() },
+            local_album: entry.local_album.clone(),
+            source,
+            confidence: entry.confidence,
+            fetched_at: lyrics_store::now_secs(),
         }
+    };
+    let result = tokio::task::spawn_blocking(move || lyrics_store().map(|store| store.save(&stored))).await;
+    if let Ok(Some(Err(e))) = result {
+        eprintln!("  ⚠ 写入磁盘歌词缓存失败: {}", e);
     }
+}
 
-    entry
+/// 为非活动会话附加歌词：已缓存时直接填充；`lookup` 为真时未缓存的歌曲插入占位并静默加载
+/// （不输出 TUI 日志）。只有允许列表中的会话（音乐播放器）查找，浏览器视频等会话的标题
+/// 不会触发在线请求，也不会写入磁盘缓存
+async fn attach_session_lyrics(
+    info: &mut SongInfo,
+    cache: &Arc<RwLock<LyricsCache>>,
+    service: &Arc<LyricsService>,
+    lookup: bool,
+    debug: bool,
+) {
+    if lookup {
+        if !cache.read().await.has_song(&info.title, &info.artist) {
+            cache.write().await.insert_entry(&info.title, &info.artist, LyricsCacheEntry::default());
+            spawn_lyrics_load(cache.clone(), service.clone(), info, true, debug);
+            return;
+        }
+        if cache.write().await.take_due_retry(&info.title, &info.artist) {
+            spawn_lyrics_load(cache.clone(), service.clone(), info, true, debug);
+        }
+    }
+
+    let cached = cache.read().await;
+    if let Some(entry) = cached.peek_entry(&info.title, &info.artist) {
+        info.lyrics = entry.lyrics.clone();
+        info.trans = entry.trans.clone();
+        info.qrc_data = entry.qrc_data.clone();
+        info.lyrics_confidence = entry.confidence;
+        info.set_lyrics_offset(song_lyrics_offset(info));
+        if !entry.album_pic_url.is_empty() {
+            info.album_pic_url = entry.album_pic_url.clone();
+        }
+    }
+    if info.lyrics.is_empty() && !info.trans.is_empty() {
+        info.lyrics = info.trans.clone();
+    }
 }
 
 // Function to run the main monitor loop in a background thread.
@@ -372,19 +788,57 @@ async fn run_monitor(app_handle: Option<tauri::AppHandle>, args: Cli, config: Co
         r.store(false, Ordering::SeqCst);
     })?;
     
+    // 封面存储：启用同步服务时封面以 /api/cover/{id} 短地址提供
+    let cover_dir = Some(config.settings.cover_cache_dir.trim())
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from);
+    cover_store::init(config.settings.enable_server, cover_dir);
+    init_lyrics_store(&config.settings);
+    init_local_index(config.settings.debug_mode);
+    init_song_maps(&config.settings);
+
+    // 创建媒体源（Windows 下默认 SMTC）
+    let source = media_source::create_media_source(&config)?;
+
+    // 录制模式：媒体源的每个原始采样追加写入 NDJSON，供 --replay 复现问题
+    if !config.settings.record_file.is_empty() {
+        match capture::start_recording(std::path::Path::new(&config.settings.record_file)) {
+            Ok(()) => {
+                if !args.quiet && config.settings.debug_mode {
+                    println!("⏺️  正在录制媒体源采样: {}", config.settings.record_file);
+                }
+            }
+            Err(e) => eprintln!("⚠️  无法开始录制: {}", e),
+        }
+    }
+
     if !args.quiet && config.settings.debug_mode {
-        println!("🎵 QQMusic Reader v{} (SMTC Mode)", env!("CARGO_PKG_VERSION"));
+        println!("🎵 QQMusic Reader v{} ({} Mode)", env!("CARGO_PKG_VERSION"), source.name());
         println!("========================================");
-        println!("⚠️  正在使用 Windows 系统媒体控制接口");
+        println!("⚠️  正在使用媒体源: {}", source.name());
         println!("========================================\n");
     }
 
-    // 初始化歌词获取器和后台缓存
+    // 播放控制通道：Tauri 命令与同步服务把请求转交给主循环执行
+    let control_rx = control::init_channel()?;
+
+    // 初始化歌词获取流水线和后台缓存
     let lyric_fetcher = Arc::new(LyricFetcher::with_debug(config.settings.debug_mode));
-    let lyrics_cache = Arc::new(RwLock::new(LyricsCache::new()));
+    let providers = lyric_provider::build_providers(&config.settings.lyric_providers, &lyric_fetcher, config.settings.debug_mode);
+    if config.settings.debug_mode {
+        eprintln!("[lyrics] Providers: {:?}", providers.iter().map(|p| p.id()).collect::<Vec<_>>());
+    }
+    let lyrics_folder = lyrics_folder::LyricsFolderProvider::from_config(&config.settings.lyrics_folder, config.settings.debug_mode);
+    if let Some(folder) = &lyrics_folder {
+        eprintln!("📁 歌词目录: {}（优先于其他歌词来源）", folder.dir().display());
+    }
+    let stages = DefaultStages::new(lyric_fetcher, providers, lyrics_folder, config.settings.debug_mode);
+    let lyrics_service = Arc::new(LyricsService::new(stages, config.settings.debug_mode));
 
     // 初始化数据广播通道并启动服务
     let (tx, rx) = tokio::sync::watch::channel(SongInfo::default());
+    let (sessions_tx, sessions_rx) = tokio::sync::watch::channel(SessionsSnapshot::default());
+    // 多会话快照只供同步服务使用，未启用服务时不读取其他会话
     if config.settings.enable_server {
         let port = config.settings.server_port;
         if config.settings.debug_mode {
@@ -392,22 +846,45 @@ async fn run_monitor(app_handle: Option<tauri::AppHandle>, args: Cli, config: Co
             println!("🚀 本地同步服务已启动: http://127.0.0.1:{}", port);
             println!("📡 WebSocket 接口: ws://127.0.0.1:{}/ws", port);
             println!("📄 当前状态接口: http://127.0.0.1:{}/api/current", port);
+            println!("🗂️  多会话接口: http://127.0.0.1:{}/api/sessions", port);
         }
         tokio::spawn(async move {
-            server::start_server(port, rx).await;
+            server::start_server(port, rx, sessions_rx).await;
         });
     }
 
+    let monitor = Monitor { source, lyrics_service, control_rx, tx, sessions_tx, running };
+    monitor_loop(monitor, app_handle, &args, config).await
+}
 
+/// 主循环的输入输出：由 run_monitor 按配置创建，测试中换成回放源与歌词流水线桩
+struct Monitor {
+    source: Box<dyn media_source::MediaSource>,
+    lyrics_service: Arc<LyricsService>,
+    control_rx: tokio::sync::mpsc::Receiver<ControlRequest>,
+    tx: tokio::sync::watch::Sender<SongInfo>,
+    sessions_tx: tokio::sync::watch::Sender<SessionsSnapshot>,
+    /// 置为 false 后主循环在本轮结束时退出
+    running: Arc<AtomicBool>,
+}
 
-
+/// 主循环：读取媒体源 → 后台加载歌词 → 广播 → 写入输出文件 → 等待下一轮并处理控制请求
+async fn monitor_loop(monitor: Monitor, app_handle: Option<tauri::AppHandle>, args: &Cli, config: Config) -> Result<()> {
+    let Monitor { mut source, lyrics_service, mut control_rx, tx, sessions_tx, running } = monitor;
+    let lyrics_cache = Arc::new(RwLock::new(LyricsCache::new()));
+    let server_enabled = config.settings.enable_server;
 
     let mut last_song_info: Option<SongInfo> = None;
+    // 最近一次广播给前端的状态，用于判断是否需要再次推送
+    let mut last_pushed: Option<SongInfo> = None;
+    let mut last_pushed_sessions = SessionsSnapshot::default();
     let mut update_count = 0;
     // 切歌后追踪旧歌 total_time_ms，用于跨帧检测 SMTC timeline 是否仍报旧值
     let mut stale_song_total: Option<u64> = None;
     // 去重歌词日志：同一首歌只打一次缓存命中/查找日志
     let mut last_logged_lyric_key = String::new();
+    // 等待时间轴确认的歌词跳转
+    let mut pending_seek: Option<PendingSeek> = None;
 
     // 主循环
     while running.load(Ordering::SeqCst) {
@@ -421,10 +898,12 @@ async fn run_monitor(app_handle: Option<tauri::AppHandle>, args: Cli, config: Co
             }
         };
         let loop_result: Result<()> = async {
-            // 使用 SMTC 读取媒体信息（含会话源过滤：只接受 QQ Music，过滤其他音源）
-            let mut current_song_info = match smtc::get_current_media_info().await {
+            // 从媒体源读取播放信息（SMTC 含会话源过滤：只接受 QQ Music，过滤其他音源）
+            let mut has_session = false;
+            let mut current_song_info = match source.read().await {
             Ok(info) => {
                 if let Some(mut info) = info {
+                    has_session = true;
                     // 后台歌词加载：切歌时后台请求，不阻塞 TUI
                     let quiet = args.quiet;
                     let debug = config.settings.debug_mode;
@@ -435,272 +914,20 @@ async fn run_monitor(app_handle: Option<tauri::AppHandle>, args: Cli, config: Co
                         // 立即插入占位条目，阻止后续帧重复 spawn
                         {
                             let mut w = lyrics_cache.write().await;
-                            w.insert_entry(&info.title, &info.artist, LyricsCacheEntry {
-                                lyrics: String::new(), trans: String::new(),
-                                qrc_raw: String::new(), qrc_data: Vec::new(),
-                                album_pic_url: String::new(),
-                                local_album: String::new(),
-                                last_accessed: 0,
-                            });
+                            w.insert_entry(&info.title, &info.artist, LyricsCacheEntry::default());
                         }
                         if !quiet && song_key != last_logged_lyric_key {
                             eprintln!("[歌词] {} - {}", info.artist, info.title);
                             last_logged_lyric_key = song_key;
                         }
-                        let cache = lyrics_cache.clone();
-                        let fetcher = lyric_fetcher.clone();
-                        let t = info.title.clone();
-                        let a = info.artist.clone();
-                        let album = info.album.clone();
-                        tokio::spawn(async move {
-                            let has_local_qrc;
-                            // 1. 本地歌词快速查找并立即插入缓存（~50ms，不阻塞在线获取）
-                            {
-                                let t2 = t.clone();
-                                let a2 = a.clone();
-                                let local = tokio::task::spawn_blocking(move || lookup_local_lyrics(&t2, &a2))
-                                    .await
-                                    .unwrap_or_else(|_| LyricsCacheEntry {
-                                        lyrics: String::new(), trans: String::new(),
-                                        qrc_raw: String::new(), qrc_data: Vec::new(),
-                                        album_pic_url: String::new(),
-                                        local_album: String::new(),
-                                        last_accessed: 0,
-                                    });
-
-                                let local_qrc = local.qrc_data.len();
-                                let local_lrc = !local.lyrics.is_empty();
-                                let local_trans = !local.trans.is_empty();
-                                if !quiet {
-                                    let cache_dir_info = match get_lyric_cache_dir() {
-                                        Some(ref dir) => {
-                                            let file_count = std::fs::read_dir(dir).map(|d| d.count()).unwrap_or(0);
-                                            format!("{} ({}个文件)", dir.display(), file_count)
-                                        }
-                                        None => "未找到本地缓存目录".to_string(),
-                                    };
-                                    if local_qrc > 0 || local_lrc || local_trans {
-                                        eprintln!("  本地: QRC {}行 | 歌词 {} | 翻译 {} | {}",
-                                            local_qrc,
-                                            if local_lrc { "✓" } else { "✗" },
-                                            if local_trans { "✓" } else { "✗" },
-                                            cache_dir_info);
-                                    } else {
-                                        eprintln!("  本地: ✗ 无数据 ({})", cache_dir_info);
-                                        // 诊断：显示文件名格式 + 模糊匹配候选
-                                        eprintln!("       搜索词: artist='{}' title='{}'", local_qrc::normalize(&a), local_qrc::normalize(&t));
-                                        if let Some(ref dir) = get_lyric_cache_dir() {
-                                            if let Ok(entries) = std::fs::read_dir(dir) {
-                                                let mut qrc_count = 0u32;
-                                                let mut best_candidate: Option<(String, u32)> = None;
-                                                let norm_title = local_qrc::normalize(&t);
-                                                let norm_artist = local_qrc::normalize(&a);
-                                                for entry in entries.flatten() {
-                                                    let fname = match entry.file_name().to_str() {
-                                                        Some(n) => n.to_string(),
-                                                        None => continue,
-                                                    };
-                                                    if !fname.ends_with("_qm.qrc") { continue; }
-                                                    qrc_count += 1;
-                                                    // 计算与当前歌曲的匹配度
-                                                    let base = fname.trim_end_matches("_qm.qrc");
-                                                    let parts: Vec<&str> = base.splitn(4, " - ").collect();
-                                                    if parts.len() >= 2 {
-                                                        let f_artist = local_qrc::normalize(parts[0]);
-                                                        let f_title = local_qrc::normalize(parts[1]);
-                                                        let mut score: u32 = 0;
-                                                        if f_title == norm_title { score += 100; }
-                                                        else if f_title.contains(&norm_title) || norm_title.contains(&f_title) { score += 60; }
-                                                        if f_artist == norm_artist { score += 50; }
-                                                        else if f_artist.contains(&norm_artist) || norm_artist.contains(&f_artist) { score += 30; }
-                                                        if score > 0 && (best_candidate.is_none() || score > best_candidate.as_ref().unwrap().1) {
-                                                            best_candidate = Some((fname, score));
-                                                        }
-                                                    }
-                                                }
-                                                eprintln!("       目录QRC总数: {} | 最佳候选: {}", qrc_count,
-                                                    best_candidate.map_or("无部分匹配".to_string(), |(f, s)| format!("'{}' (分数:{})", f, s)));
-                                            }
-                                        }
-                                    }
-                                }
-
-                                has_local_qrc = !local.qrc_data.is_empty();
-
-                                if !local.lyrics.is_empty() || has_local_qrc {
-                                    let mut w = cache.write().await;
-                                    w.insert_entry(&t, &a, local);
-                                    if debug { eprintln!("[lyrics] Local cache populated for '{} - {}'", t, a); }
-                                }
-                            }
-
-                            // 2. 在线歌词获取（仅当本地无 QRC 时才请求，作为备用手段）
-                            if !has_local_qrc {
-                            match fetcher.fetch_lyrics(&t, &a).await {
-                                Ok((l, trans, q, pic_url)) => {
-                                    let resolved_pic_url = resolve_album_pic_url(&pic_url, &album, &fetcher).await;
-                                    let mut entry = LyricsCacheEntry {
-                                        lyrics: l,
-                                        trans,
-                                        qrc_raw: q,
-                                        qrc_data: Vec::new(),
-                                        album_pic_url: resolved_pic_url,
-                                        local_album: String::new(),
-                                        last_accessed: 0,
-                                    };
-
-                                    // 在线 QRC 解密
-                                    if !entry.qrc_raw.is_empty() {
-                                        if debug { eprintln!("[QRC] Raw data: {} bytes", entry.qrc_raw.len()); }
-                                        match qrc::decode_qrc(&entry.qrc_raw) {
-                                            Ok(xml) => {
-                                                if debug { eprintln!("[QRC] Decrypted XML: {} bytes", xml.len()); }
-                                                match qrc::parse_qrc_xml(&xml) {
-                                                    Ok(lines) => {
-                                                        if debug { eprintln!("[QRC] Parsed {} lines from XML", lines.len()); }
-                                                        entry.qrc_data = lines;
-                                                    }
-                                                    Err(err) => {
-                                                        if !quiet { eprintln!("[QRC] XML parse failed for '{} - {}': {}", t, a, err); }
-                                                    }
-                                                }
-                                                if entry.lyrics.is_empty() {
-                                                    entry.lyrics = qrc::extract_lrc_from_xml(&xml).unwrap_or_default();
-                                                    if debug && !entry.lyrics.is_empty() { eprintln!("[QRC] Extracted LRC from XML: {} chars", entry.lyrics.len()); }
-                                                }
-                                                if entry.qrc_data.is_empty() && !entry.lyrics.is_empty() {
-                                                    let parsed = qrc::parse_qrc_text(&entry.lyrics);
-                                                    if !parsed.is_empty() {
-                                                        if debug { eprintln!("[QRC] Text fallback parsed {} lines", parsed.len()); }
-                                                        entry.qrc_data = parsed;
-                                                    }
-                                                }
-                                            },
-                                            Err(err) => {
-                                                if !quiet { eprintln!("[QRC] Decode failed for '{} - {}': {}", t, a, err); }
-                                            }
-                                        }
-                                    }
-
-                                    // 在线数据未覆盖的字段回填本地缓存
-                                    if entry.lyrics.is_empty() || entry.qrc_data.is_empty() || entry.trans.is_empty() {
-                                        let mut cached = cache.write().await;
-                                        if let Some(cur) = cached.get_entry(&t, &a) {
-                                            if entry.lyrics.is_empty() { entry.lyrics = cur.lyrics.clone(); }
-                                            if entry.qrc_data.is_empty() { entry.qrc_data = cur.qrc_data.clone(); }
-                                            if entry.trans.is_empty() { entry.trans = cur.trans.clone(); }
-                                        }
-                                    }
-
-                                    // 复用本地缓存条目中的专辑名（优于依赖 SMTC album）
-                                    if entry.local_album.is_empty() {
-                                        let mut cached = cache.write().await;
-                                        if let Some(cur) = cached.get_entry(&t, &a) {
-                                            entry.local_album = cur.local_album.clone();
-                                        }
-                                    }
-
-                                    // 汇总在线结果
-                                    if !quiet {
-                                        let qrc_lines = entry.qrc_data.len();
-                                        let lrc = !entry.lyrics.is_empty();
-                                        let has_trans = !entry.trans.is_empty();
-                                        let has_pic = !entry.album_pic_url.is_empty();
-                                        if qrc_lines > 0 || lrc || has_trans {
-                                            eprintln!("  在线: QRC {}行 | 歌词 {} | 翻译 {} | 封面 {}",
-                                                qrc_lines,
-                                                if lrc { "✓" } else { "✗" },
-                                                if has_trans { "✓" } else { "✗" },
-                                                if has_pic { "✓" } else { "✗" });
-                                        } else {
-                                            eprintln!("  在线: ✗ 无数据");
-                                        }
-                                    }
-
-                                    let mut w = cache.write().await;
-                                    w.insert_entry(&t, &a, entry);
-                                    if debug { eprintln!("[lyrics] ✓ Background fetch complete for '{} - {}'", t, a); }
-                                }
-                                Err(e) => {
-                                    if !quiet { eprintln!("  在线: ✗ 失败 ({})", e); }
-                                    // 本地数据已在步骤 1 插入，无需额外处理
-                                }
-                            }
-                            } // if !has_local_qrc
-                            else {
-                                // 本地有 QRC 但缺少封面：解析 album_mid → 优先查本地 QQMusicPicture → 在线 CDN 兜底
-                                //
-                                // 1) 取 album_mid：
-                                //    a. 多策略搜歌曲 songmid → get_album_mid（解决 "(Explicit)" 等括号后缀
-                                //       导致单策略搜不到的问题，与 fetch_lyrics 策略对齐）
-                                //    b. 仍失败则用专辑名搜 SmartBox 专辑，候选为本地文件名专辑名
-                                //       （QQ 音乐索引原始名，比 SMTC album 更可靠）与 SMTC album，
-                                //       均清洗去括号后再搜
-                                // 2) 有了 album_mid：本地命中→base64 data URI（与在线路径一致，
-                                //    避免 file:// 在 webview 受限）；否则返回在线 CDN URL
-                                let get_pic = async {
-                                    let mut album_mid = String::new();
-
-                                    // 1a) 多策略搜歌曲拿 album_mid
-                                    if let Some(songmid) = fetcher.search_song_mid(&t, &a).await {
-                                        album_mid = fetcher.get_album_mid(&songmid).await.unwrap_or_default();
-                                    }
-
-                                    // 1b) 歌曲搜失败 → 用专辑名搜专辑（候选：本地文件名专辑 + SMTC album，
-                                    //     各自清洗去括号后作为额外候选）
-                                    if album_mid.is_empty() {
-                                        // 从本地缓存条目取文件名解析的专辑名
-                                        let local_album = {
-                                            let cached = cache.read().await;
-                                            cached.peek_entry(&t, &a).map(|e| e.local_album.clone()).unwrap_or_default()
-                                        };
-                                        let mut album_variants: Vec<String> = Vec::new();
-                                        for name in [local_album, album.clone()].into_iter().filter(|s| !s.is_empty()) {
-                                            let cleaned = clean_album_name(&name);
-                                            album_variants.push(cleaned.clone());
-                                            if !cleaned.is_empty() && cleaned != name {
-                                                album_variants.push(name);
-                                            }
-                                        }
-                                        let mut seen: std::collections::HashSet<String> = std::collections::HashSet::new();
-                                        for variant in album_variants {
-                                            let v = variant.trim().to_string();
-                                            if v.is_empty() || !seen.insert(v.clone()) { continue; }
-                                            if let Some(am) = fetcher.search_album_mid_by_name(&v).await {
-                                                album_mid = am;
-                                                break;
-                                            }
-                                        }
-                                    }
-
-                                    // 2) album_mid → 本地图片优先，否则在线 URL
-                                    if !album_mid.is_empty() {
-                                        if let Some(pic_dir) = get_cache_root().map(|r| r.join("QQMusicPicture")) {
-                                            if let Some(local_pic) = local_qrc::find_album_pic(&pic_dir, &album_mid) {
-                                                if let Ok(data) = std::fs::read(&local_pic) {
-                                                    use base64::{Engine as _, engine::general_purpose::STANDARD};
-                                                    let data_uri = format!("data:image/jpeg;base64,{}", STANDARD.encode(&data));
-                                                    if !quiet { eprintln!("  封面: ✓ 本地"); }
-                                                    return Some(data_uri);
-                                                }
-                                            }
-                                        }
-                                        let pic_url = format!("https://y.gtimg.cn/music/photo_new/T002R800x800M000{}.jpg?max_age=2592000", album_mid);
-                                        if !quiet { eprintln!("  封面: ✓ 在线"); }
-                                        return Some(pic_url);
-                                    }
-                                    None
-                                };
-
-                                if !quiet { eprintln!("  封面: 查找中..."); }
-                                if let Some(pic_url) = get_pic.await {
-                                    let mut w = cache.write().await;
-                                    w.update_album_pic_url(&t, &a, pic_url);
-                                } else if !quiet {
-                                    eprintln!("  封面: ✗ 未找到");
-                                }
-                            }
-                        });
+                        spawn_lyrics_load(lyrics_cache.clone(), lyrics_service.clone(), &info, quiet, debug);
+                    } else if lyrics_cache.write().await.take_due_retry(&info.title, &info.artist) {
+                        // 未找到/失败的条目到期重新查找：保留已有数据，不插入占位
+                        if !quiet {
+                            eprintln!("[歌词] {} - {}  ◀ 重试", info.artist, info.title);
+                            last_logged_lyric_key = song_key;
+                        }
+                        spawn_lyrics_load(lyrics_cache.clone(), lyrics_service.clone(), &info, quiet, debug);
                     } else {
                         if !quiet && song_key != last_logged_lyric_key {
                             eprintln!("[歌词] {} - {}  ◀ 缓存", info.artist, info.title);
@@ -716,6 +943,7 @@ async fn run_monitor(app_handle: Option<tauri::AppHandle>, args: Cli, config: Co
                             info.trans = entry.trans.clone();
                             info.qrc_raw = entry.qrc_raw.clone();
                             info.qrc_data = entry.qrc_data.clone();
+                            info.lyrics_confidence = entry.confidence;
                             if !entry.album_pic_url.is_empty() {
                                 info.album_pic_url = entry.album_pic_url.clone();
                             }
@@ -724,9 +952,9 @@ async fn run_monitor(app_handle: Option<tauri::AppHandle>, args: Cli, config: Co
 
                     // 即时本地封面重解析：若缓存的 album_pic_url 是 QQ 音乐在线 URL，
                     // 尝试找本地缓存文件替代。对已缓存歌曲避免永远使用旧 URL。
-                    // resolve_album_pic_url 分两步：URL 中的 album_mid 查本地 → SmartBox 用专辑名查。
+                    // resolve_cover 分两步：URL 中的 album_mid 查本地 → SmartBox 用专辑名查。
                     if info.album_pic_url.contains("y.gtimg.cn") {
-                        let resolved = resolve_album_pic_url(&info.album_pic_url, &info.album, &lyric_fetcher).await;
+                        let resolved = lyrics_service.resolve_cover(&info.album_pic_url, &info.album).await;
                         if resolved != info.album_pic_url {
                             let mut w = lyrics_cache.write().await;
                             w.update_album_pic_url(&info.title, &info.artist, resolved.clone());
@@ -742,91 +970,45 @@ async fn run_monitor(app_handle: Option<tauri::AppHandle>, args: Cli, config: Co
                 } else {
                     SongInfo {
                         title: "No music playing".to_string(),
-                        artist: String::new(),
-                        album: String::new(),
-                        lyrics: String::new(),
-                        trans: String::new(),
-                        qrc_raw: String::new(),
-                        qrc_data: Vec::new(),
-                        current_time: 0,
-                        total_time: 0,
-                        current_time_ms: 0,
-                        total_time_ms: 0,
-                        progress_percent: 0.0,
-                        is_playing: false,
-                        album_pic_url: String::new(),
-                        server_ts: 0,
+                        ..SongInfo::default()
                     }
                 }
             },
             Err(e) => {
                 if !args.quiet {
-                    eprintln!("Error reading SMTC info: {}", e);
+                    eprintln!("Error reading {} info: {}", source.name(), e);
                 }
                 SongInfo {
                     title: "ERROR".to_string(),
-                    artist: String::new(),
-                    album: String::new(),
                     lyrics: format!("System Media Control Error: {}", e),
-                    trans: String::new(),
-                    qrc_raw: String::new(),
-                    qrc_data: Vec::new(),
-                    current_time: 0,
-                    total_time: 0,
-                    current_time_ms: 0,
-                    total_time_ms: 0,
-                    progress_percent: 0.0,
-                    is_playing: false,
-                    album_pic_url: String::new(),
-                    server_ts: 0,
+                    ..SongInfo::default()
                 }
             }
         };
 
-        // 检查歌曲是否有变化 (用于切歌修正与文件输出)
-        let song_changed = match &last_song_info {
-            Some(last) => last.title != current_song_info.title || last.artist != current_song_info.artist,
-            None => true,
-        };
+        // 检查歌曲是否有变化并修正切歌瞬间的陈旧时间轴 (用于切歌修正与文件输出)
+        let song_changed = correct_stale_timeline(
+            last_song_info.as_ref(),
+            &mut stale_song_total,
+            &mut current_song_info,
+        );
 
-        // SMTC 切歌瞬间修正：media properties（title/artist）先更新，
-        // timeline（Position/EndTime）可能滞后 1~2 秒仍报旧歌数据。
-        //
-        // 只靠 song_changed 一帧归零不够：后续帧 title 未变但 timeline 仍旧，
-        // 前端会收到"新歌名 + 旧进度"错配——旧歌词继续渲染，进度来回跳。
-        //
-        // 解法：用 last_song_info.total_time_ms 做锚点——若当前 total 与旧歌
-        // 相同（±500ms），判定 timeline 未更新，强制归零。total 变化后清除追踪。
-        if song_changed {
-            if let Some(old) = &last_song_info {
-                if old.total_time_ms > 0 {
-                    stale_song_total = Some(old.total_time_ms);
-                }
-            }
-            current_song_info.current_time_ms = 0;
-            current_song_info.current_time = 0;
-            current_song_info.progress_percent = 0.0;
-            current_song_info.total_time_ms = 0;
-            current_song_info.total_time = 0;
-        } else if let Some(old_total) = stale_song_total {
-            if current_song_info.total_time_ms == 0
-                || current_song_info.total_time_ms == old_total
-            {
-                current_song_info.current_time_ms = 0;
-                current_song_info.current_time = 0;
-                current_song_info.progress_percent = 0.0;
-                current_song_info.total_time_ms = 0;
-                current_song_info.total_time = 0;
-            } else {
-                stale_song_total = None;
-            }
+        let offset_ms = song_lyrics_offset(&current_song_info);
+        current_song_info.set_lyrics_offset(offset_ms);
+
+        // 用跳转后的采样确认歌词跳转是否生效
+        if let Some(seek) = pending_seek.take() {
+            pending_seek = seek.observe(&current_song_info);
         }
 
         // SMTC positions are now drift-corrected via LastUpdatedTime in smtc.rs.
-        // Only apply the user-configurable offset for fine-tuning.
+        // Only apply the user-configurable offset for fine-tuning. The offset is a wall-clock
+        // latency, so the song advances offset × playback rate during it.
+        // The per-song offset corrects the lyric file itself and applies even while paused.
         let smtc_offset_ms = config.settings.smtc_offset_ms;
-        let display_time_ms = if current_song_info.is_playing {
-            let adjusted = current_song_info.current_time_ms + smtc_offset_ms;
+        let player_time_ms = if current_song_info.is_playing {
+            let adjusted = current_song_info.current_time_ms
+                + song_info::scale_elapsed(smtc_offset_ms, current_song_info.rate());
             if current_song_info.total_time_ms > 0 {
                 adjusted.min(current_song_info.total_time_ms)
             } else {
@@ -835,14 +1017,43 @@ async fn run_monitor(app_handle: Option<tauri::AppHandle>, args: Cli, config: Co
         } else {
             current_song_info.current_time_ms
         };
+        let display_time_ms = lyrics_offsets::apply_offset(player_time_ms, current_song_info.lyrics_offset_ms);
+
+        // 只在状态真正变化时广播（前端自行插值进度），另有心跳兜底校准
+        if should_push(last_pushed.as_ref(), &current_song_info) {
+            cover_store::set_current(&current_song_info.album_pic_url);
+            // 广播最新状态给所有 WebSocket 客户端
+            let _ = tx.send(current_song_info.clone());
+
+            // 如果存在 Tauri app_handle，则广播给 GUI 前端
+            if let Some(ref handle) = app_handle {
+                use tauri::Emitter;
+                let _ = handle.emit("song-info", current_song_info.clone());
+            }
+            last_pushed = Some(current_song_info.clone());
+        }
 
-        // 广播最新状态给所有 WebSocket 客户端
-        let _ = tx.send(current_song_info.clone());
-
-        // 如果存在 Tauri app_handle，则广播给 GUI 前端
-        if let Some(ref handle) = app_handle {
-            use tauri::Emitter;
-            let _ = handle.emit("song-info", current_song_info.clone());
+        // 多会话快照：其他会话只附加已缓存的歌词，未缓存时静默后台加载
+        if server_enabled {
+            let mut others = match source.read_sessions().await {
+                Ok(others) => others,
+                Err(e) => {
+                    if config.settings.debug_mode && !args.quiet {
+                        eprintln!("Error reading {} sessions: {}", source.name(), e);
+                    }
+                    Vec::new()
+                }
+            };
+            for info in others.iter_mut().filter(|info| info.is_valid()) {
+                let lookup = config.sessions.allow_rank(&info.source_app).is_some();
+                attach_session_lyrics(info, &lyrics_cache, &lyrics_service, lookup, config.settings.debug_mode).await;
+            }
+            let active = has_session.then(|| current_song_info.clone());
+            let snapshot = SessionsSnapshot::build(others, active, &last_pushed_sessions);
+            if sessions::should_push_snapshot(&last_pushed_sessions, &snapshot) {
+                let _ = sessions_tx.send(snapshot.clone());
+                last_pushed_sessions = snapshot;
+            }
         }
 
         // 写入文件
@@ -878,7 +1089,7 @@ async fn run_monitor(app_handle: Option<tauri::AppHandle>, args: Cli, config: Co
             }
 
             if filtered_lyrics.is_empty() {
-                filtered_lyrics = filter_lyrics(&current_song_info.lyrics, &current_song_info.trans, current_song_info.current_time);
+                filtered_lyrics = filter_lyrics(&current_song_info.lyrics, &current_song_info.trans, precise_time_ms / 1000);
             }
 
             let display_lyric = if filtered_lyrics.trim().is_empty() {
@@ -911,13 +1122,121 @@ async fn run_monitor(app_handle: Option<tauri::AppHandle>, args: Cli, config: Co
             tokio::time::sleep(Duration::from_secs(2)).await;
         } else {
             let elapsed = loop_start.elapsed();
+            let is_playing = last_song_info.as_ref().is_some_and(|info| info.is_playing);
             let current_interval = if IS_BACKGROUND.load(Ordering::Relaxed) {
                 Duration::from_millis(2000)
+            } else if source.is_event_driven() && !is_playing {
+                // 暂停/无播放时进度不变，事件驱动源会在状态变化时立即唤醒
+                Duration::from_millis(EVENT_IDLE_WAIT_MS)
             } else {
                 Duration::from_millis(config.settings.update_interval_ms)
             };
-            if elapsed < current_interval {
-                tokio::time::sleep(current_interval - elapsed).await;
+            // 等待下一轮期间同时响应播放控制请求（来自 Tauri 命令 / HTTP / WebSocket）
+            let remaining = current_interval.saturating_sub(elapsed);
+            let control_request = tokio::select! {
+                request = control_rx.recv() => request,
+                _ = source.wait_for_change(remaining) => None,
+            };
+            match control_request {
+                Some(ControlRequest::Command { command, reply }) => {
+                    let result = source.control(command).await;
+                    if let Err(e) = &result {
+                        if config.settings.debug_mode && !args.quiet {
+                            eprintln!("Playback control {:?} failed: {}", command, e);
+                        }
+                    }
+                    let _ = reply.send(result.map_err(|e| e.to_string()));
+                }
+                Some(ControlRequest::SeekTo { target, reply }) => {
+                    let lyric_time_ms = last_song_info
+                        .as_ref()
+                        .and_then(|info| control::resolve_seek_target(info, target));
+                    let Some(lyric_time_ms) = lyric_time_ms else {
+                        let _ = reply.send(Err(format!("没有可跳转的歌词位置: {:?}", target)));
+                        continue;
+                    };
+                    let song_offset_ms = last_song_info.as_ref().map_or(0, |info| info.lyrics_offset_ms);
+                    let offset_ms = (config.settings.smtc_offset_ms as i64).saturating_add(song_offset_ms);
+                    let position_ms = control::player_position_for(lyric_time_ms, offset_ms);
+                    match source.control(PlaybackCommand::Seek { position_ms }).await {
+                        Ok(()) => {
+                            // 新的跳转覆盖尚未确认的旧跳转
+                            if let Some(previous) = pending_seek.take() {
+                                previous.finish(false);
+                            }
+                            pending_seek = Some(PendingSeek::new(position_ms, reply));
+                        }
+                        Err(e) => {
+                            if config.settings.debug_mode && !args.quiet {
+                                eprintln!("Seek to {:?} failed: {}", target, e);
+                            }
+                            let _ = reply.send(Err(e.to_string()));
+                        }
+                    }
+                }
+                Some(ControlRequest::LyricCandidates { reply }) => {
+                    let Some(info) = last_song_info.as_ref() else {
+                        let _ = reply.send(Err("当前没有播放的歌曲".to_string()));
+                        continue;
+                    };
+                    // 依次搜索所有歌词源较慢，放到后台任务，不阻塞主循环
+                    let query = LyricsQuery::from_song(info);
+                    let service = lyrics_service.clone();
+                    tokio::spawn(async move {
+                        let _ = reply.send(Ok(service.candidates(&query).await));
+                    });
+                }
+                Some(ControlRequest::PinLyrics { pin, reply }) => {
+                    let result = match (last_song_info.as_ref(), pin_store()) {
+                        (None, _) => Err("当前没有播放的歌曲".to_string()),
+                        (_, None) => Err("未启用歌词固定（lyrics_pins_file 为空或无法读取）".to_string()),
+                        (Some(info), Some(store)) => {
+                            let (title, artist) = (info.title.clone(), info.artist.clone());
+                            if !args.quiet {
+                                match &pin {
+                                    Some(pin) => eprintln!("[歌词] {} - {}  ◀ 固定为 {}", artist, title, pin),
+                                    None => eprintln!("[歌词] {} - {}  ◀ 取消固定", artist, title),
+                                }
+                            }
+                            let result = store.set(&title, &artist, pin).map_err(|e| format!("{:#}", e));
+                            if result.is_ok() {
+                                // 旧结果（含磁盘缓存）失效，下一帧重新加载
+                                lyrics_cache.write().await.remove_entry(&title, &artist);
+                                if let Some(store) = lyrics_store() {
+                                    if let Err(e) = store.remove(&title, &artist) {
+                                        eprintln!("⚠️  无法删除磁盘歌词缓存: {:#}", e);
+                                    }
+                                }
+                            }
+                            result
+                        }
+                    };
+                    let _ = reply.send(result);
+                }
+                Some(ControlRequest::LyricsOffset { change, reply }) => {
+                    let result = match (last_song_info.as_mut(), offset_store()) {
+                        (None, _) => Err("当前没有播放的歌曲".to_string()),
+                        (_, None) => Err("未启用单曲歌词偏移（lyrics_offsets_file 为空或无法读取）".to_string()),
+                        (Some(info), Some(store)) => {
+                            // 以映射文件为准：同一帧内连续调整时 info 中的值尚未刷新
+                            let offset_ms = change.apply(store.get(&info.title, &info.artist).unwrap_or(0));
+                            // 0 即恢复默认，不在映射文件中保留
+                            let value = (offset_ms != 0).then_some(offset_ms);
+                            match store.set(&info.title, &info.artist, value) {
+                                Ok(()) => {
+                                    info.set_lyrics_offset(offset_ms);
+                                    if !args.quiet {
+                                        eprintln!("[歌词] {} - {}  ◀ 偏移 {:+}ms", info.artist, info.title, offset_ms);
+                                    }
+                                    Ok(offset_ms)
+                                }
+                                Err(e) => Err(format!("{:#}", e)),
+                            }
+                        }
+                    };
+                    let _ = reply.send(result);
+                }
+                None => {}
             }
         }
     }
@@ -978,6 +1297,20 @@ fn apply_cli_overrides(mut config: Config, args: &Cli) -> Config {
         config.settings.max_retries = args.retries;
     }
 
+    // CLI --replay 指定回放脚本时改用回放媒体源
+    if let Some(replay) = &args.replay {
+        config.settings.media_source = "replay".to_string();
+        config.settings.replay_file = replay.clone();
+    }
+
+    if let Some(speed) = args.replay_speed {
+        config.settings.replay_speed = speed;
+    }
+
+    if let Some(record) = &args.record {
+        config.settings.record_file = record.clone();
+    }
+
     
     config
 }
@@ -1291,7 +1624,22 @@ async fn main() -> Result<()> {
 
     // 启动 Tauri 窗口
     tauri::Builder::default()
-        .invoke_handler(tauri::generate_handler![set_background_state, get_app_config, save_app_config])
+        .invoke_handler(tauri::generate_handler![
+            set_background_state,
+            get_app_config,
+            save_app_config,
+            play_pause,
+            next_track,
+            previous_track,
+            seek_to,
+            seek_to_line,
+            seek_to_lyric_time,
+            list_lyric_candidates,
+            pin_lyrics,
+            unpin_lyrics,
+            set_lyrics_offset,
+            adjust_lyrics_offset
+        ])
         .setup(move |app| {
             let app_handle = app.handle().clone();
             std::thread::spawn(move || {
@@ -1313,3 +1661,248 @@ async fn main() -> Result<()> {
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use media_source::MediaSource;
+    use replay::{ReplayClock, ReplayScript, ReplaySource, ReplayTrack};
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    fn track(title: &str, duration_ms: u64, timeline_lag_ms: u64) -> ReplayTrack {
+        ReplayTrack {
+            title: title.to_string(),
+            artist: "徐良".to_string(),
+            album: String::new(),
+            album_pic_url: String::new(),
+            duration_ms,
+            play_ms: Some(10_000),
+            timeline_lag_ms,
+            events: Vec::new(),
+        }
+    }
+
+    /// 用回放源复现 SMTC 切歌滞后：timeline 仍报旧歌期间进度保持归零，
+    /// 新歌 timeline 到位后恢复正常进度。
+    #[tokio::test]
+    async fn test_stale_timeline_zeroed_during_replayed_song_change() {
+        let clock = Rc::new(Cell::new(0));
+        let script = ReplayScript {
+            repeat: false,
+            tracks: vec![track("那时雨", 200_000, 0), track("不写完", 180_000, 1_500)],
+        };
+        let mut source = ReplaySource::new(script, ReplayClock::Manual(clock.clone()));
+
+        let mut last_song_info: Option<SongInfo> = None;
+        let mut stale_song_total: Option<u64> = None;
+        let mut changes = Vec::new();
+
+        for t in (0..14_000).step_by(250) {
+            clock.set(t);
+            let mut info = source.read().await.unwrap().unwrap();
+            if correct_stale_timeline(last_song_info.as_ref(), &mut stale_song_total, &mut info) {
+                changes.push((t, info.title.clone()));
+            }
+
+            if info.title == "不写完" && t < 11_500 {
+                assert_eq!(info.current_time_ms, 0, "stale timeline leaked at {}ms", t);
+                assert_eq!(info.total_time_ms, 0);
+            }
+            if t >= 11_500 {
+                assert_eq!(info.total_time_ms, 180_000);
+                assert_eq!(info.current_time_ms, t - 10_000);
+                assert!(stale_song_total.is_none());
+            }
+            last_song_info = Some(info);
+        }
+
+        assert_eq!(
+            changes,
+            vec![(0, "那时雨".to_string()), (10_000, "不写完".to_string())]
+        );
+    }
+
+    /// 连续播放只在心跳到期时推送；seek、暂停、歌词加载立即推送
+    #[test]
+    fn test_should_push() {
+        let last = SongInfo {
+            title: "那时雨".to_string(),
+            current_time_ms: 10_000,
+            total_time_ms: 200_000,
+            is_playing: true,
+            server_ts: 1_000,
+            ..SongInfo::default()
+        };
+        assert!(should_push(None, &last));
+
+        let tick = |current_time_ms: u64, server_ts: u64| SongInfo { current_time_ms, server_ts, ..last.clone() };
+        assert!(!should_push(Some(&last), &tick(10_050, 1_050)));
+        assert!(!should_push(Some(&last), &tick(10_900, 1_900)));
+        assert!(should_push(Some(&last), &tick(11_000, 2_000)));
+        assert!(should_push(Some(&last), &tick(60_000, 1_050)));
+
+        let paused = SongInfo { is_playing: false, ..tick(10_050, 1_050) };
+        assert!(should_push(Some(&last), &paused));
+
+        let with_lyrics = SongInfo { lyrics: "[00:01.00]那时雨".to_string(), ..tick(10_050, 1_050) };
+        assert!(should_push(Some(&last), &with_lyrics));
+    }
+
+    /// 失败次数跨重试累计，到期后只触发一次重新查找，找到后不再查找
+    #[test]
+    fn test_lyrics_cache_retry() {
+        let schedule = RetrySchedule { max_retries: 3, not_found_recheck: std::time::Duration::from_secs(3600) };
+        let mut cache = LyricsCache::new();
+        cache.insert_entry("那时雨", "徐良", LyricsCacheEntry::default());
+        assert!(!cache.take_due_retry("那时雨", "徐良"));
+
+        cache.record_outcome("那时雨", "徐良", LookupOutcome::Failed, &schedule);
+        cache.insert_entry("那时雨", "徐良", LyricsCacheEntry::default());
+        cache.record_outcome("那时雨", "徐良", LookupOutcome::Failed, &schedule);
+        let entry = cache.peek_entry("那时雨", "徐良").unwrap();
+        assert_eq!(entry.failures, 2);
+        let retry_at = entry.retry_at.unwrap();
+        assert!(retry_at > current_timestamp_ms() + 5_000 && retry_at <= current_timestamp_ms() + 10_000);
+
+        // 模拟到期
+        cache.entries.get_mut("那时雨|徐良").unwrap().retry_at = Some(0);
+        assert!(cache.take_due_retry("那时雨", "徐良"));
+        assert!(!cache.take_due_retry("那时雨", "徐良"));
+
+        cache.record_outcome("那时雨", "徐良", LookupOutcome::Found, &schedule);
+        let entry = cache.peek_entry("那时雨", "徐良").unwrap();
+        assert_eq!((entry.failures, entry.retry_at), (0, None));
+    }
+
+    /// 只有歌词目录阶段返回固定歌词的流水线桩，记录每次查找的歌名
+    struct FolderStages {
+        lyrics: HashMap<String, String>,
+        lookups: Arc<std::sync::Mutex<Vec<String>>>,
+    }
+
+    impl lyrics_service::LyricsStages for FolderStages {
+        fn pinned(&self, _: &LyricsQuery) -> Option<LyricsPin> {
+            None
+        }
+
+        fn fetch_pinned<'a>(&'a self, _: &'a LyricsPin) -> lyrics_service::BoxFuture<'a, Result<Option<lyric_provider::ProviderLyrics>>> {
+            Box::pin(async { Ok(None) })
+        }
+
+        fn search_candidates<'a>(&'a self, _: &'a LyricsQuery) -> lyrics_service::BoxFuture<'a, Vec<LyricCandidate>> {
+            Box::pin(async { Vec::new() })
+        }
+
+        fn lookup_folder<'a>(&'a self, query: &'a LyricsQuery) -> lyrics_service::BoxFuture<'a, Option<lyric_provider::ProviderLyrics>> {
+            Box::pin(async move {
+                self.lookups.lock().unwrap().push(query.title.clone());
+                let lyrics = self.lyrics.get(&query.title)?.clone();
+                Some(lyric_provider::ProviderLyrics { provider: "folder", lyrics, ..Default::default() })
+            })
+        }
+
+        fn load_disk<'a>(&'a self, _: &'a LyricsQuery) -> lyrics_service::BoxFuture<'a, Option<(LyricsData, bool)>> {
+            Box::pin(async { None })
+        }
+
+        fn lookup_local<'a>(&'a self, _: &'a LyricsQuery) -> lyrics_service::BoxFuture<'a, LyricsData> {
+            Box::pin(async { LyricsData::default() })
+        }
+
+        fn fetch_online<'a>(&'a self, _: &'a LyricsQuery) -> lyrics_service::BoxFuture<'a, Result<Option<lyric_provider::ProviderLyrics>>> {
+            Box::pin(async { Ok(None) })
+        }
+
+        fn decrypt_qrc(&self, raw: &str) -> Result<String> {
+            Ok(raw.to_string())
+        }
+
+        fn resolve_cover<'a>(&'a self, pic_url: &'a str, _: &'a str) -> lyrics_service::BoxFuture<'a, String> {
+            Box::pin(async move { pic_url.to_string() })
+        }
+
+        fn find_cover<'a>(&'a self, _: &'a LyricsQuery, _: &'a str) -> lyrics_service::BoxFuture<'a, Option<lyrics_service::FoundCover>> {
+            Box::pin(async { None })
+        }
+    }
+
+    /// 输出文件为 UTF-16 LE（带 BOM）
+    fn read_utf16(path: &std::path::Path) -> String {
+        let bytes = std::fs::read(path).unwrap_or_default();
+        let units: Vec<u16> = bytes.chunks_exact(2).skip(1).map(|c| u16::from_le_bytes([c[0], c[1]])).collect();
+        String::from_utf16_lossy(&units)
+    }
+
+    async fn wait_until(what: &str, mut done: impl FnMut() -> bool) {
+        for _ in 0..400 {
+            if done() {
+                return;
+            }
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+        panic!("timed out waiting for {}", what);
+    }
+
+    /// 用回放源驱动主循环：每首歌只后台加载一次歌词，加载完成后写入歌名、JSON 与当前歌词行
+    #[tokio::test]
+    async fn test_monitor_loop_loads_lyrics_and_writes_outputs() {
+        let dir = test_support::temp_dir("monitor_loop");
+        let mut config = Config::default();
+        config.settings.enable_server = false;
+        config.settings.update_interval_ms = 5;
+        (config.settings.output_txt, config.settings.output_json, config.settings.output_lyric) = (true, true, true);
+        config.settings.txt_filename = dir.join("now_playing.txt").to_string_lossy().into_owned();
+        config.settings.json_filename = dir.join("now_playing.json").to_string_lossy().into_owned();
+        config.settings.lyric_filename = dir.join("current_lyric.txt").to_string_lossy().into_owned();
+        let (txt, json, lyric) = (
+            PathBuf::from(&config.settings.txt_filename),
+            PathBuf::from(&config.settings.json_filename),
+            PathBuf::from(&config.settings.lyric_filename),
+        );
+
+        let lookups = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let stages = FolderStages {
+            lyrics: HashMap::from([("那时雨".to_string(), "[00:00.50]第一句\n[00:02.00]第二句".to_string())]),
+            lookups: lookups.clone(),
+        };
+        let clock = Rc::new(Cell::new(1_000));
+        let script = ReplayScript { repeat: false, tracks: vec![track("那时雨", 200_000, 0), track("不写完", 180_000, 0)] };
+        let running = Arc::new(AtomicBool::new(true));
+        let (tx, rx) = tokio::sync::watch::channel(SongInfo::default());
+        let (sessions_tx, _sessions_rx) = tokio::sync::watch::channel(SessionsSnapshot::default());
+        let (_control_tx, control_rx) = tokio::sync::mpsc::channel(1);
+        let monitor = Monitor {
+            source: Box::new(ReplaySource::new(script, ReplayClock::Manual(clock.clone()))),
+            lyrics_service: Arc::new(LyricsService::new(stages, false)),
+            control_rx,
+            tx,
+            sessions_tx,
+            running: running.clone(),
+        };
+        let args = <Cli as clap::Parser>::parse_from(["qqmusic-monitor", "--quiet"]);
+
+        let drive = async {
+            wait_until("lyric line of the first song", || read_utf16(&lyric) == "第一句").await;
+            assert_eq!(read_utf16(&txt), "那时雨");
+            let written: SongInfo = serde_json::from_slice(&std::fs::read(&json).unwrap()).unwrap();
+            assert_eq!((written.title.as_str(), written.current_time_ms), ("那时雨", 1_000));
+            assert!(written.lyrics.contains("第二句"));
+            assert_eq!(rx.borrow().title, "那时雨");
+
+            clock.set(2_500);
+            wait_until("next lyric line", || read_utf16(&lyric) == "第二句").await;
+
+            // 切歌：没有歌词的歌显示占位
+            clock.set(11_000);
+            wait_until("song change", || read_utf16(&txt) == "不写完").await;
+            wait_until("placeholder lyric", || read_utf16(&lyric) == "...\n\n").await;
+            running.store(false, Ordering::SeqCst);
+        };
+        let (result, ()) = tokio::join!(monitor_loop(monitor, None, &args, config), drive);
+        result.unwrap();
+
+        assert_eq!(*lookups.lock().unwrap(), vec!["那时雨".to_string(), "不写完".to_string()]);
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
@@ -0,0 +1,270 @@
+use anyhow::Result;
+use std::future::Future;
+use std::pin::Pin;
+use std::time::Duration;
+
+use crate::config::Config;
+use crate::control::PlaybackCommand;
+use crate::session_filter::SessionFilter;
+use crate::song_info::{effective_rate, scale_elapsed, RepeatMode, SongInfo};
+
+/// 非 Send 的装箱 Future：监控循环运行在 LocalSet 上，SMTC 的缩略图读取依赖 spawn_local。
+pub type LocalBoxFuture<'a, T> = Pin<Box<dyn Future<Output = T> + 'a>>;
+
+/// 媒体源在某一时刻上报的原始播放状态（尚未做漂移修正）。
+///
+/// 各平台后端（SMTC / MPRIS / 回放）只负责把原生数据翻译成此结构，
+/// 位置修正、进度计算统一由 `song_info_from_sample` 完成，保证行为一致。
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MediaSample {
+    pub title: String,
+    pub artist: String,
+    pub album: String,
+    /// 源报告的播放位置（毫秒），是 `last_updated_ms` 时刻的快照
+    pub position_ms: u64,
+    /// 总时长（毫秒），0 表示未知
+    pub end_time_ms: u64,
+    /// `position_ms` 的采样时刻（Unix 毫秒）；源不提供时为 None
+    pub last_updated_ms: Option<i64>,
+    pub is_playing: bool,
+    pub album_pic_url: String,
+    /// 播放速率；源不提供时为 None（按 1.0 外推）
+    pub playback_rate: Option<f64>,
+    pub shuffle: Option<bool>,
+    pub repeat_mode: Option<RepeatMode>,
+}
+
+/// 媒体源：提供当前播放的标题/歌手/专辑/时间轴/播放状态。
+///
+/// 主循环只依赖此 trait，歌词、文件输出与同步服务因此可以运行在任意输入之上。
+pub trait MediaSource {
+    /// 源名称，用于日志
+    fn name(&self) -> &'static str;
+
+    /// 读取当前播放状态；没有可用的播放会话时返回 `Ok(None)`
+    fn read(&mut self) -> LocalBoxFuture<'_, Result<Option<SongInfo>>>;
+
+    /// 读取所有会话（多会话监控），每个 SongInfo 的 `source_app` 为会话标识。
+    /// 返回空表示该源只有单一会话，由 `read` 的结果代表。
+    fn read_sessions(&mut self) -> LocalBoxFuture<'_, Result<Vec<SongInfo>>> {
+        Box::pin(async { Ok(Vec::new()) })
+    }
+
+    /// 向播放器发送控制命令；不支持控制的源返回错误
+    fn control(&mut self, command: PlaybackCommand) -> LocalBoxFuture<'_, Result<()>> {
+        let name = self.name();
+        Box::pin(async move { anyhow::bail!("{} 媒体源不支持播放控制: {:?}", name, command) })
+    }
+
+    /// 是否由事件驱动：状态变化时会唤醒 `wait_for_change`，主循环空闲时可以长时间休眠
+    fn is_event_driven(&self) -> bool {
+        false
+    }
+
+    /// 等待源状态变化，最多等待 `timeout`；返回 true 表示被变化事件唤醒。
+    /// 轮询式源没有变化通知，默认实现只是睡眠到超时。
+    fn wait_for_change(&mut self, timeout: Duration) -> LocalBoxFuture<'_, bool> {
+        Box::pin(async move {
+            tokio::time::sleep(timeout).await;
+            false
+        })
+    }
+}
+
+/// 漂移修正的最大补偿量，避免源时间戳异常陈旧时进度大幅跳变
+const MAX_DRIFT_CORRECTION_MS: u64 = 5000;
+
+/// 由原始采样生成 `SongInfo`。
+///
+/// 源报告的 Position 是 `last_updated_ms` 时刻的快照，播放中时真实位置 =
+/// Position + (now - LastUpdatedTime) × 播放速率，与 Windows 音量浮窗进度条的算法一致。
+pub fn song_info_from_sample(sample: MediaSample, now_ms: i64) -> SongInfo {
+    let total_time_ms = sample.end_time_ms;
+    let rate = effective_rate(sample.playback_rate);
+
+    let corrected_current_ms = match sample.last_updated_ms {
+        Some(last_updated_ms) if sample.is_playing && last_updated_ms > 0 => {
+            let elapsed_ms = scale_elapsed((now_ms - last_updated_ms).max(0) as u64, rate);
+            let corrected = sample.position_ms + elapsed_ms.min(MAX_DRIFT_CORRECTION_MS);
+            if total_time_ms > 0 { corrected.min(total_time_ms) } else { corrected }
+        }
+        _ => sample.position_ms,
+    };
+
+    let progress = if total_time_ms > 0 {
+        (corrected_current_ms as f64 / total_time_ms as f64) * 100.0
+    } else {
+        0.0
+    };
+
+    SongInfo {
+        title: sample.title,
+        artist: sample.artist,
+        album: sample.album,
+        current_time: corrected_current_ms / 1000,
+        total_time: total_time_ms / 1000,
+        current_time_ms: corrected_current_ms,
+        total_time_ms,
+        progress_percent: progress as f32,
+        is_playing: sample.is_playing,
+        album_pic_url: sample.album_pic_url,
+        server_ts: now_ms.max(0) as u64,
+        shuffle: sample.shuffle,
+        repeat_mode: sample.repeat_mode,
+        playback_rate: sample.playback_rate,
+        ..SongInfo::default()
+    }
+}
+
+/// 从上一次读取结果外推到 `now_ms`。
+///
+/// 事件驱动源在两次事件之间不访问底层 API，位置按 `server_ts`（读取时刻）前推；
+/// 基准是本进程自己的读取时间而非源的 LastUpdatedTime，因此不受漂移补偿上限约束。
+pub fn advance_song_info(info: &SongInfo, now_ms: i64) -> SongInfo {
+    let mut next = info.clone();
+    if info.is_playing && info.server_ts > 0 {
+        let elapsed_ms = scale_elapsed((now_ms - info.server_ts as i64).max(0) as u64, info.rate());
+        let current_ms = info.current_time_ms + elapsed_ms;
+        let current_ms = if info.total_time_ms > 0 { current_ms.min(info.total_time_ms) } else { current_ms };
+        next.current_time_ms = current_ms;
+        next.current_time = current_ms / 1000;
+        next.progress_percent = if info.total_time_ms > 0 {
+            (current_ms as f64 / info.total_time_ms as f64 * 100.0) as f32
+        } else {
+            0.0
+        };
+    }
+    next.server_ts = now_ms.max(0) as u64;
+    next
+}
+
+/// 根据配置创建媒体源。`auto` 选择当前平台的默认实现（Windows: SMTC，Linux: MPRIS）。
+pub fn create_media_source(config: &Config) -> Result<Box<dyn MediaSource>> {
+    let settings = &config.settings;
+    let filter = &config.sessions;
+    match settings.media_source.as_str() {
+        "auto" if cfg!(windows) => smtc_source(filter),
+        "auto" => mpris_source(filter),
+        "smtc" => smtc_source(filter),
+        "mpris" => mpris_source(filter),
+        "replay" => {
+            if settings.replay_file.is_empty() {
+                anyhow::bail!("回放媒体源需要指定 replay_file（或 --replay <文件>）");
+            }
+            let source = crate::replay::ReplaySource::from_file(
+                std::path::Path::new(&settings.replay_file),
+                settings.replay_speed,
+            )?;
+            Ok(Box::new(source))
+        }
+        other => anyhow::bail!("未知的媒体源: {}", other),
+    }
+}
+
+#[cfg(windows)]
+fn smtc_source(filter: &SessionFilter) -> Result<Box<dyn MediaSource>> {
+    Ok(Box::new(crate::smtc::SmtcSource::new(filter.clone())))
+}
+
+#[cfg(not(windows))]
+fn smtc_source(_filter: &SessionFilter) -> Result<Box<dyn MediaSource>> {
+    anyhow::bail!("SMTC 媒体源仅在 Windows 上可用")
+}
+
+#[cfg(target_os = "linux")]
+fn mpris_source(filter: &SessionFilter) -> Result<Box<dyn MediaSource>> {
+    Ok(Box::new(crate::mpris::MprisSource::new(filter.clone())))
+}
+
+#[cfg(not(target_os = "linux"))]
+fn mpris_source(_filter: &SessionFilter) -> Result<Box<dyn MediaSource>> {
+    anyhow::bail!("MPRIS 媒体源仅在 Linux 上可用")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(position_ms: u64, end_time_ms: u64, last_updated_ms: Option<i64>, is_playing: bool) -> MediaSample {
+        MediaSample {
+            title: "那时雨".to_string(),
+            artist: "徐良".to_string(),
+            position_ms,
+            end_time_ms,
+            last_updated_ms,
+            is_playing,
+            ..MediaSample::default()
+        }
+    }
+
+    /// 播放中：位置按 now - last_updated 前推，并同步计算秒级字段与进度
+    #[test]
+    fn test_drift_correction_while_playing() {
+        let info = song_info_from_sample(sample(10_000, 200_000, Some(1_000), true), 1_800);
+        assert_eq!(info.current_time_ms, 10_800);
+        assert_eq!(info.current_time, 10);
+        assert_eq!(info.total_time, 200);
+        assert_eq!(info.server_ts, 1_800);
+        assert!((info.progress_percent - 5.4).abs() < 0.01);
+    }
+
+    /// 陈旧时间戳最多补偿 5 秒，且不超过总时长
+    #[test]
+    fn test_drift_correction_is_clamped() {
+        let info = song_info_from_sample(sample(10_000, 200_000, Some(1_000), true), 60_000);
+        assert_eq!(info.current_time_ms, 15_000);
+
+        let info = song_info_from_sample(sample(199_000, 200_000, Some(1_000), true), 4_000);
+        assert_eq!(info.current_time_ms, 200_000);
+    }
+
+    /// 暂停或缺少时间戳时直接使用源报告的位置
+    #[test]
+    fn test_no_correction_when_paused_or_unknown() {
+        let info = song_info_from_sample(sample(10_000, 200_000, Some(1_000), false), 3_000);
+        assert_eq!(info.current_time_ms, 10_000);
+
+        let info = song_info_from_sample(sample(10_000, 0, None, true), 3_000);
+        assert_eq!(info.current_time_ms, 10_000);
+        assert_eq!(info.progress_percent, 0.0);
+    }
+
+    /// 外推：播放中按读取时刻前推（超过 5 秒也不截断），暂停时保持不变
+    #[test]
+    fn test_advance_song_info() {
+        let info = song_info_from_sample(sample(10_000, 200_000, None, true), 1_000);
+        let advanced = advance_song_info(&info, 9_000);
+        assert_eq!(advanced.current_time_ms, 18_000);
+        assert_eq!(advanced.current_time, 18);
+        assert_eq!(advanced.server_ts, 9_000);
+        assert!((advanced.progress_percent - 9.0).abs() < 0.01);
+
+        assert_eq!(advance_song_info(&info, 1_000_000).current_time_ms, 200_000);
+
+        let paused = song_info_from_sample(sample(10_000, 200_000, None, false), 1_000);
+        assert_eq!(advance_song_info(&paused, 9_000).current_time_ms, 10_000);
+    }
+
+    /// 变速播放：漂移修正与外推都按播放速率缩放流逝时间，其他字段原样带入
+    #[test]
+    fn test_playback_rate_scales_extrapolation() {
+        let slow = MediaSample {
+            playback_rate: Some(0.75),
+            shuffle: Some(true),
+            repeat_mode: Some(RepeatMode::List),
+            ..sample(10_000, 200_000, Some(1_000), true)
+        };
+        let info = song_info_from_sample(slow, 1_800);
+        assert_eq!(info.current_time_ms, 10_600);
+        assert_eq!(info.shuffle, Some(true));
+        assert_eq!(info.repeat_mode, Some(RepeatMode::List));
+        assert_eq!(advance_song_info(&info, 5_800).current_time_ms, 13_600);
+
+        let fast = MediaSample { playback_rate: Some(1.25), ..sample(10_000, 200_000, Some(1_000), true) };
+        assert_eq!(song_info_from_sample(fast, 1_800).current_time_ms, 11_000);
+
+        // 异常速率按 1.0 处理
+        let broken = MediaSample { playback_rate: Some(0.0), ..sample(10_000, 200_000, Some(1_000), true) };
+        assert_eq!(song_info_from_sample(broken, 1_800).current_time_ms, 10_800);
+    }
+}
@@ -0,0 +1,702 @@
+use anyhow::{Context, Result};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use zbus::export::futures_util::StreamExt;
+use zbus::fdo::{DBusProxy, PropertiesProxy};
+use zbus::names::InterfaceName;
+use zbus::zvariant::{Array, OwnedObjectPath, OwnedValue};
+use zbus::Connection;
+
+use crate::capture::{self, CaptureEntry};
+use crate::control::PlaybackCommand;
+use crate::cover_store;
+use crate::media_source::{song_info_from_sample, LocalBoxFuture, MediaSample, MediaSource};
+use crate::session_filter::SessionFilter;
+use crate::song_info::{RepeatMode, SongInfo};
+
+const MPRIS_BUS_PREFIX: &str = "org.mpris.MediaPlayer2.";
+const MPRIS_OBJECT_PATH: &str = "/org/mpris/MediaPlayer2";
+const MPRIS_ROOT_INTERFACE: &str = "org.mpris.MediaPlayer2";
+const MPRIS_PLAYER_INTERFACE: &str = "org.mpris.MediaPlayer2.Player";
+
+/// Linux MPRIS media source: reads `org.mpris.MediaPlayer2.Player` over the D-Bus session bus.
+pub struct MprisSource {
+    /// D-Bus 地址；None 表示使用默认会话总线
+    address: Option<String>,
+    connection: Option<Connection>,
+    /// 上次选中的播放器总线名，同一优先级的播放器中优先选它，避免在并列的播放器之间来回切换
+    current_player: Option<String>,
+    /// 缓存的封面：(mpris:artUrl, 转换后的地址)，避免每帧重复读取本地文件
+    cached_art: Option<(String, String)>,
+    /// 各播放器的过滤标识（见 player_label），总线名的所有者变化（NameOwnerChanged）时失效
+    labels: Arc<Mutex<HashMap<String, String>>>,
+    /// 监听 NameOwnerChanged 的后台任务，断开重连时随旧连接一起结束
+    label_watch: Option<LabelWatch>,
+    /// 本轮 read 的扫描结果，供紧随其后的 read_sessions 复用
+    last_scan: Option<PlayerScan>,
+    /// 主循环是否读取多会话快照（启用同步服务时）；否则每次只读取候选播放器
+    sessions_wanted: bool,
+    filter: SessionFilter,
+}
+
+/// NameOwnerChanged 监听任务，释放时结束（任务持有连接，不结束会一直占用）
+struct LabelWatch(tokio::task::JoinHandle<()>);
+
+impl Drop for LabelWatch {
+    fn drop(&mut self) {
+        self.0.abort();
+    }
+}
+
+/// 一次扫描：总线上的播放器与读取到的 Player 属性（未读取或读取失败为 None）
+struct PlayerScan {
+    players: Vec<String>,
+    props: Vec<Option<HashMap<String, OwnedValue>>>,
+    now_ms: i64,
+}
+
+impl MprisSource {
+    pub fn new(filter: SessionFilter) -> Self {
+        Self {
+            address: None,
+            connection: None,
+            current_player: None,
+            cached_art: None,
+            labels: Arc::new(Mutex::new(HashMap::new())),
+            label_watch: None,
+            last_scan: None,
+            sessions_wanted: false,
+            filter,
+        }
+    }
+
+    /// 连接到指定的 D-Bus 地址（测试时用于私有 dbus-daemon）
+    pub fn with_address(address: &str) -> Self {
+        Self {
+            address: Some(address.to_string()),
+            ..Self::new(SessionFilter::default())
+        }
+    }
+
+    async fn connection(&mut self) -> Result<Connection> {
+        if let Some(conn) = &self.connection {
+            return Ok(conn.clone());
+        }
+        let conn = match &self.address {
+            Some(address) => zbus::connection::Builder::address(address.as_str())?
+                .build()
+                .await
+                .context("Failed to connect to D-Bus address")?,
+            None => Connection::session()
+                .await
+                .context("Failed to connect to D-Bus session bus")?,
+        };
+        // 标识缓存属于这条连接：订阅失败时不缓存，每次重新读取
+        self.labels.lock().unwrap_or_else(|e| e.into_inner()).clear();
+        match watch_name_owners(&conn, self.labels.clone()).await {
+            Ok(watch) => self.label_watch = Some(watch),
+            Err(e) => eprintln!("[MPRIS] Failed to watch NameOwnerChanged: {:#}", e),
+        }
+        self.connection = Some(conn.clone());
+        Ok(conn)
+    }
+
+    /// 断开连接（总线出错后下次重新连接）
+    fn disconnect(&mut self) {
+        self.label_watch = None;
+        self.connection = None;
+        self.current_player = None;
+        self.last_scan = None;
+    }
+
+    /// 播放器的过滤标识：优先使用缓存，只为新出现的总线名读取根接口
+    async fn player_labels(&mut self, conn: &Connection, players: &[String]) -> Vec<String> {
+        let cached: Vec<Option<String>> = {
+            let mut labels = self.labels.lock().unwrap_or_else(|e| e.into_inner());
+            labels.retain(|player, _| players.contains(player));
+            players.iter().map(|player| labels.get(player).cloned()).collect()
+        };
+        let mut result = Vec::with_capacity(players.len());
+        for (player, label) in players.iter().zip(cached) {
+            let label = match label {
+                Some(label) => label,
+                None => {
+                    let label = player_label(conn, player).await;
+                    if self.label_watch.is_some() {
+                        self.labels.lock().unwrap_or_else(|e| e.into_inner()).insert(player.clone(), label.clone());
+                    }
+                    label
+                }
+            };
+            result.push(label);
+        }
+        result
+    }
+
+    async fn read_current(&mut self) -> Result<Option<SongInfo>> {
+        let conn = self.connection().await?;
+        let players = list_players(&conn).await?;
+        let now_ms = chrono::Utc::now().timestamp_millis();
+
+        // 需要多会话快照时每个播放器读取一次，选中的播放器直接复用；否则只读取候选
+        let mut props = Vec::with_capacity(players.len());
+        for player in &players {
+            let read = if self.sessions_wanted { player_properties(&conn, player).await.ok() } else { None };
+            props.push(read);
+        }
+
+        // Rank players by the session filter on every read (allow-list priority, then policy fallback),
+        // so a higher-priority player that appears later takes over. The player picked last time only
+        // wins ties. MPRIS has no system-wide "current session", so the fallback tries every player in bus order.
+        let labels = self.player_labels(&conn, &players).await;
+        let mut order = self.filter.candidates(&labels, None);
+        let previous = self.current_player.as_ref().and_then(|current| players.iter().position(|p| p == current));
+        self.filter.prefer_previous(&labels, &mut order, previous);
+        self.current_player = None;
+        let mut found = None;
+        for idx in order {
+            let player = &players[idx];
+            if props[idx].is_none() && !self.sessions_wanted {
+                props[idx] = player_properties(&conn, player).await.ok();
+            }
+            let Some(player_props) = &props[idx] else {
+                continue;
+            };
+            if let Some(info) = self.player_info(player, player_props, now_ms) {
+                self.current_player = Some(player.clone());
+                found = Some(info);
+                break;
+            }
+        }
+
+        self.last_scan = Some(PlayerScan { players, props, now_ms });
+        Ok(found)
+    }
+
+    /// 读取所有播放器（多会话监控）：不录制、不读取本地封面文件。
+    /// 复用本轮 read 的扫描结果，首次调用时自行扫描一次，之后 read 会读取所有播放器
+    async fn read_all_players(&mut self) -> Result<Vec<SongInfo>> {
+        let scan = match self.last_scan.take() {
+            Some(scan) if self.sessions_wanted => scan,
+            _ => {
+                self.sessions_wanted = true;
+                let conn = self.connection().await?;
+                let players = list_players(&conn).await?;
+                let mut props = Vec::with_capacity(players.len());
+                for player in &players {
+                    props.push(player_properties(&conn, player).await.ok());
+                }
+                PlayerScan { players, props, now_ms: chrono::Utc::now().timestamp_millis() }
+            }
+        };
+
+        let now_ms = scan.now_ms;
+        let mut infos = Vec::with_capacity(scan.players.len());
+        for (player, props) in scan.players.into_iter().zip(scan.props) {
+            let Some(mut sample) = props.as_ref().and_then(|props| sample_from_properties(props, now_ms)) else {
+                continue;
+            };
+            if !sample.album_pic_url.starts_with("http") {
+                sample.album_pic_url = String::new();
+            }
+            let mut info = song_info_from_sample(sample, now_ms);
+            info.source_app = player;
+            infos.push(info);
+        }
+        Ok(infos)
+    }
+
+    /// 选中播放器的属性转为 SongInfo：录制采样并解析本地封面；没有标题时返回 None
+    fn player_info(&mut self, player: &str, props: &HashMap<String, OwnedValue>, now_ms: i64) -> Option<SongInfo> {
+        let mut sample = sample_from_properties(props, now_ms)?;
+        if capture::is_recording() {
+            let status = props
+                .get("PlaybackStatus")
+                .and_then(|v| <&str>::try_from(v).ok())
+                .unwrap_or("Unknown");
+            capture::record(&CaptureEntry::from_sample(now_ms, player, &sample, status));
+        }
+        sample.album_pic_url = self.resolve_art_url(&sample.album_pic_url);
+
+        let mut info = song_info_from_sample(sample, now_ms);
+        info.source_app = player.to_string();
+        Some(info)
+    }
+
+    /// 向当前选中的播放器发送控制命令（PlayPause / Next / Previous / SetPosition）
+    async fn control_player(&mut self, command: PlaybackCommand) -> Result<()> {
+        let conn = self.connection().await?;
+        let player = self.current_player.clone().context("没有可控制的 MPRIS 播放器")?;
+
+        let (method, position_us) = match command {
+            PlaybackCommand::PlayPause => ("PlayPause", None),
+            PlaybackCommand::Next => ("Next", None),
+            PlaybackCommand::Previous => ("Previous", None),
+            PlaybackCommand::Seek { position_ms } => ("SetPosition", Some(position_ms as i64 * 1000)),
+        };
+
+        let Some(position_us) = position_us else {
+            conn.call_method(Some(player.as_str()), MPRIS_OBJECT_PATH, Some(MPRIS_PLAYER_INTERFACE), method, &())
+                .await
+                .with_context(|| format!("MPRIS {} failed", method))?;
+            return Ok(());
+        };
+
+        // SetPosition 需要当前曲目的 mpris:trackid；播放器未提供时退回相对 Seek
+        let props = PropertiesProxy::builder(&conn)
+            .destination(player.clone())?
+            .path(MPRIS_OBJECT_PATH)?
+            .build()
+            .await?;
+        let interface = InterfaceName::from_static_str_unchecked(MPRIS_PLAYER_INTERFACE);
+        let track_id = props
+            .get(interface.clone(), "Metadata")
+            .await
+            .ok()
+            .and_then(|v| HashMap::<String, OwnedValue>::try_from(v).ok())
+            .and_then(|metadata| metadata.get("mpris:trackid").and_then(|v| v.try_clone().ok()))
+            .and_then(|v| OwnedObjectPath::try_from(v).ok());
+
+        match track_id {
+            Some(track_id) => {
+                conn.call_method(
+                    Some(player.as_str()),
+                    MPRIS_OBJECT_PATH,
+                    Some(MPRIS_PLAYER_INTERFACE),
+                    "SetPosition",
+                    &(track_id, position_us),
+                )
+                .await
+                .context("MPRIS SetPosition failed")?;
+            }
+            None => {
+                let current_us = props
+                    .get(interface, "Position")
+                    .await
+                    .ok()
+                    .and_then(|v| value_as_i64(&v))
+                    .unwrap_or(0);
+                conn.call_method(
+                    Some(player.as_str()),
+                    MPRIS_OBJECT_PATH,
+                    Some(MPRIS_PLAYER_INTERFACE),
+                    "Seek",
+                    &(position_us - current_us),
+                )
+                .await
+                .context("MPRIS Seek failed")?;
+            }
+        }
+        Ok(())
+    }
+
+    /// `file://` 封面读取并登记到封面存储（webview 无法直接加载本地路径），其他地址原样返回
+    fn resolve_art_url(&mut self, art_url: &str) -> String {
+        if let Some((cached_url, resolved)) = &self.cached_art {
+            if cached_url == art_url {
+                return resolved.clone();
+            }
+        }
+        let resolved = match art_url.strip_prefix("file://") {
+            Some(path) => read_local_art(path).unwrap_or_default(),
+            None => art_url.to_string(),
+        };
+        self.cached_art = Some((art_url.to_string(), resolved.clone()));
+        resolved
+    }
+}
+
+impl MediaSource for MprisSource {
+    fn name(&self) -> &'static str {
+        "MPRIS"
+    }
+
+    fn control(&mut self, command: PlaybackCommand) -> LocalBoxFuture<'_, Result<()>> {
+        Box::pin(self.control_player(command))
+    }
+
+    fn read(&mut self) -> LocalBoxFuture<'_, Result<Option<SongInfo>>> {
+        Box::pin(async move {
+            let result = self.read_current().await;
+            if result.is_err() {
+                // 总线断开后下次重新连接
+                self.disconnect();
+            }
+            result
+        })
+    }
+
+    fn read_sessions(&mut self) -> LocalBoxFuture<'_, Result<Vec<SongInfo>>> {
+        Box::pin(self.read_all_players())
+    }
+}
+
+// Function to list MPRIS player bus names on the bus.
+async fn list_players(conn: &Connection) -> Result<Vec<String>> {
+    Ok(DBusProxy::new(conn)
+        .await?
+        .list_names()
+        .await?
+        .into_iter()
+        .map(|name| name.to_string())
+        .filter(|name| name.starts_with(MPRIS_BUS_PREFIX))
+        .collect())
+}
+
+// Function to drop cached player labels whenever a bus name changes owner
+// (a player exits, restarts or registers the name for the first time).
+async fn watch_name_owners(conn: &Connection, labels: Arc<Mutex<HashMap<String, String>>>) -> Result<LabelWatch> {
+    let mut changes = DBusProxy::new(conn).await?.receive_name_owner_changed().await?;
+    Ok(LabelWatch(tokio::spawn(async move {
+        while let Some(signal) = changes.next().await {
+            if let Ok(args) = signal.args() {
+                labels.lock().unwrap_or_else(|e| e.into_inner()).remove(args.name().as_str());
+            }
+        }
+    })))
+}
+
+// Function to read all properties of the Player interface.
+async fn player_properties(conn: &Connection, player: &str) -> Result<HashMap<String, OwnedValue>> {
+    PropertiesProxy::builder(conn)
+        .destination(player.to_string())?
+        .path(MPRIS_OBJECT_PATH)?
+        .build()
+        .await?
+        .get_all(Some(InterfaceName::from_static_str_unchecked(MPRIS_PLAYER_INTERFACE)).into())
+        .await
+        .context("Failed to get MPRIS player properties")
+}
+
+// Electron 版 QQ 音乐以 chromium.instanceXXX 注册总线名，需再看根接口的 DesktopEntry/Identity，
+// 因此过滤用的标识为 "总线名 DesktopEntry Identity"。
+async fn player_label(conn: &Connection, player: &str) -> String {
+    let mut label = player.to_string();
+    let Ok(proxy) = PropertiesProxy::builder(conn)
+        .destination(player.to_string())
+        .and_then(|b| b.path(MPRIS_OBJECT_PATH))
+    else {
+        return label;
+    };
+    let Ok(proxy) = proxy.build().await else {
+        return label;
+    };
+    let Ok(props) = proxy
+        .get_all(Some(InterfaceName::from_static_str_unchecked(MPRIS_ROOT_INTERFACE)).into())
+        .await
+    else {
+        return label;
+    };
+    for value in ["DesktopEntry", "Identity"]
+        .iter()
+        .filter_map(|key| props.get(*key))
+        .flat_map(value_as_strings)
+    {
+        label.push(' ');
+        label.push_str(&value);
+    }
+    label
+}
+
+/// 把 Player 接口的属性映射为原始采样。
+///
+/// - `Metadata`：`xesam:title` / `xesam:artist` / `xesam:album` / `mpris:length`(μs) / `mpris:artUrl`
+/// - `Position`：μs，读取时刻即 `now_ms`
+/// - `PlaybackStatus`：`Playing` / `Paused` / `Stopped`
+/// - `Rate` / `Shuffle` / `LoopStatus`：可选，播放器不提供时为 None
+///
+/// 没有标题时返回 None（与 SMTC 的 "empty title" 一致）。
+pub fn sample_from_properties(props: &HashMap<String, OwnedValue>, now_ms: i64) -> Option<MediaSample> {
+    let metadata: HashMap<String, OwnedValue> = props
+        .get("Metadata")
+        .and_then(|v| v.try_clone().ok())
+        .and_then(|v| HashMap::try_from(v).ok())
+        .unwrap_or_default();
+
+    let title = metadata.get("xesam:title").map(value_as_strings).unwrap_or_default().join("");
+    if title.is_empty() {
+        return None;
+    }
+    let artist = metadata.get("xesam:artist").map(value_as_strings).unwrap_or_default().join("/");
+    let album = metadata.get("xesam:album").map(value_as_strings).unwrap_or_default().join("");
+    let art_url = metadata.get("mpris:artUrl").map(value_as_strings).unwrap_or_default().join("");
+
+    let length_us = metadata.get("mpris:length").and_then(value_as_i64).unwrap_or(0);
+    let position_us = props.get("Position").and_then(value_as_i64).unwrap_or(0);
+
+    let is_playing = props
+        .get("PlaybackStatus")
+        .and_then(|v| <&str>::try_from(v).ok())
+        .map(|status| status == "Playing")
+        .unwrap_or(false);
+
+    // 可选属性：Rate (d) / Shuffle (b) / LoopStatus (s: None / Track / Playlist)
+    let playback_rate = props.get("Rate").and_then(|v| f64::try_from(v).ok());
+    let shuffle = props.get("Shuffle").and_then(|v| bool::try_from(v).ok());
+    let repeat_mode = props
+        .get("LoopStatus")
+        .and_then(|v| <&str>::try_from(v).ok())
+        .and_then(|status| match status {
+            "None" => Some(RepeatMode::None),
+            "Track" => Some(RepeatMode::Track),
+            "Playlist" => Some(RepeatMode::List),
+            _ => None,
+        });
+
+    Some(MediaSample {
+        title,
+        artist,
+        album,
+        position_ms: (position_us.max(0) / 1000) as u64,
+        end_time_ms: (length_us.max(0) / 1000) as u64,
+        last_updated_ms: Some(now_ms),
+        is_playing,
+        album_pic_url: art_url,
+        playback_rate,
+        shuffle,
+        repeat_mode,
+    })
+}
+
+// Helper to read a string or string-array property (xesam:artist is `as`).
+fn value_as_strings(value: &OwnedValue) -> Vec<String> {
+    if let Ok(s) = <&str>::try_from(value) {
+        return vec![s.to_string()];
+    }
+    if let Ok(array) = <&Array>::try_from(value) {
+        return array
+            .iter()
+            .filter_map(|v| <&str>::try_from(v).ok())
+            .map(|s| s.to_string())
+            .collect();
+    }
+    Vec::new()
+}
+
+// Helper to read an integer property; the spec says `x` but some players send `t` or `i`.
+fn value_as_i64(value: &OwnedValue) -> Option<i64> {
+    i64::try_from(value)
+        .ok()
+        .or_else(|| u64::try_from(value).ok().map(|v| v as i64))
+        .or_else(|| i32::try_from(value).ok().map(i64::from))
+        .or_else(|| u32::try_from(value).ok().map(i64::from))
+}
+
+// Helper to read a local cover file into the cover store (keyed by content hash)
+fn read_local_art(path: &str) -> Option<String> {
+    let data = std::fs::read(percent_decode(path)).ok()?;
+    Some(cover_store::publish(None, data, None))
+}
+
+// Helper to decode %XX escapes in file:// URLs
+fn percent_decode(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            if let Some(Ok(b)) = s.get(i + 1..i + 3).map(|hex| u8::from_str_radix(hex, 16)) {
+                out.push(b);
+                i += 3;
+                continue;
+            }
+        }
+        out.push(bytes[i]);
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use zbus::zvariant::Value;
+
+    fn owned(value: Value<'_>) -> OwnedValue {
+        value.try_to_owned().unwrap()
+    }
+
+    fn player_properties(status: &str) -> HashMap<String, OwnedValue> {
+        let mut metadata: HashMap<&str, Value> = HashMap::new();
+        metadata.insert("xesam:title", Value::from("那时雨"));
+        metadata.insert("xesam:artist", Value::from(vec!["徐良", "阿悄"]));
+        metadata.insert("xesam:album", Value::from("不写完"));
+        metadata.insert("mpris:length", Value::from(200_000_000i64));
+        metadata.insert("mpris:artUrl", Value::from("https://y.gtimg.cn/cover.jpg"));
+
+        let mut props = HashMap::new();
+        props.insert("Metadata".to_string(), owned(Value::from(metadata)));
+        props.insert("Position".to_string(), owned(Value::from(12_345_000i64)));
+        props.insert("PlaybackStatus".to_string(), owned(Value::from(status)));
+        props
+    }
+
+    /// 默认允许列表按总线名识别 QQ 音乐；Electron 版靠标识中附加的 DesktopEntry 命中
+    #[test]
+    fn test_default_filter_matches_qqmusic_player() {
+        let filter = SessionFilter::default();
+        assert!(filter.allow_rank("org.mpris.MediaPlayer2.qqmusic").is_some());
+        assert!(filter.allow_rank("org.mpris.MediaPlayer2.QQMusic.instance42").is_some());
+        assert!(filter.allow_rank("org.mpris.MediaPlayer2.spotify").is_none());
+        assert!(filter.allow_rank("org.mpris.MediaPlayer2.chromium.instance1234").is_none());
+        assert!(filter.allow_rank("org.mpris.MediaPlayer2.chromium.instance1234 qqmusic QQ音乐").is_some());
+    }
+
+    /// Metadata / Position / PlaybackStatus 映射到采样字段（μs → ms，多歌手以 / 连接）
+    #[test]
+    fn test_sample_from_properties() {
+        let sample = sample_from_properties(&player_properties("Playing"), 1_000).unwrap();
+        assert_eq!(sample.title, "那时雨");
+        assert_eq!(sample.artist, "徐良/阿悄");
+        assert_eq!(sample.album, "不写完");
+        assert_eq!(sample.position_ms, 12_345);
+        assert_eq!(sample.end_time_ms, 200_000);
+        assert_eq!(sample.last_updated_ms, Some(1_000));
+        assert_eq!(sample.album_pic_url, "https://y.gtimg.cn/cover.jpg");
+        assert!(sample.is_playing);
+
+        assert_eq!(sample.playback_rate, None);
+        assert_eq!(sample.repeat_mode, None);
+
+        let paused = sample_from_properties(&player_properties("Paused"), 1_000).unwrap();
+        assert!(!paused.is_playing);
+
+        let mut props = player_properties("Playing");
+        props.insert("Rate".to_string(), owned(Value::from(1.25f64)));
+        props.insert("Shuffle".to_string(), owned(Value::from(true)));
+        props.insert("LoopStatus".to_string(), owned(Value::from("Playlist")));
+        let sample = sample_from_properties(&props, 1_000).unwrap();
+        assert_eq!(sample.playback_rate, Some(1.25));
+        assert_eq!(sample.shuffle, Some(true));
+        assert_eq!(sample.repeat_mode, Some(RepeatMode::List));
+    }
+
+    #[test]
+    fn test_sample_without_title_is_none() {
+        let mut props = player_properties("Playing");
+        props.insert("Metadata".to_string(), owned(Value::from(HashMap::<&str, Value>::new())));
+        assert!(sample_from_properties(&props, 1_000).is_none());
+    }
+
+    #[test]
+    fn test_percent_decode() {
+        assert_eq!(percent_decode("/tmp/My%20Cover%E5%9B%BE.jpg"), "/tmp/My Cover图.jpg");
+        assert_eq!(percent_decode("/tmp/100%"), "/tmp/100%");
+    }
+
+    /// 假播放器：在私有 dbus-daemon 上注册 org.mpris.MediaPlayer2.qqmusic，记录收到的控制调用
+    struct FakePlayer {
+        calls: std::sync::Arc<std::sync::Mutex<Vec<String>>>,
+    }
+
+    #[zbus::interface(name = "org.mpris.MediaPlayer2.Player")]
+    impl FakePlayer {
+        #[zbus(property)]
+        fn metadata(&self) -> HashMap<String, OwnedValue> {
+            let mut metadata = HashMap::new();
+            metadata.insert("xesam:title".to_string(), owned(Value::from("修炼爱情")));
+            metadata.insert("xesam:artist".to_string(), owned(Value::from(vec!["林俊杰"])));
+            metadata.insert("xesam:album".to_string(), owned(Value::from("因你 而在")));
+            metadata.insert("mpris:length".to_string(), owned(Value::from(287_000_000i64)));
+            metadata.insert(
+                "mpris:trackid".to_string(),
+                owned(Value::from(zbus::zvariant::ObjectPath::from_static_str_unchecked("/track/1"))),
+            );
+            metadata
+        }
+
+        fn play_pause(&self) {
+            self.calls.lock().unwrap().push("PlayPause".to_string());
+        }
+
+        fn set_position(&self, track_id: zbus::zvariant::ObjectPath<'_>, position: i64) {
+            self.calls.lock().unwrap().push(format!("SetPosition {} {}", track_id, position));
+        }
+
+        #[zbus(property)]
+        fn position(&self) -> i64 {
+            30_000_000
+        }
+
+        #[zbus(property)]
+        fn playback_status(&self) -> String {
+            "Paused".to_string()
+        }
+    }
+
+    /// 端到端：启动私有 dbus-daemon + 假播放器，MprisSource 应读出对应 SongInfo 并能发送控制命令。
+    /// 依赖系统的 dbus-daemon，默认不运行：`cargo test -- --ignored`
+    #[tokio::test]
+    #[ignore = "requires dbus-daemon; run with `cargo test -- --ignored`"]
+    async fn test_read_from_fake_player_on_private_bus() {
+        let mut daemon = std::process::Command::new("dbus-daemon")
+            .args(["--session", "--nofork", "--print-address"])
+            .stdout(std::process::Stdio::piped())
+            .spawn()
+            .expect("dbus-daemon should be installed");
+
+        let address = {
+            use std::io::BufRead;
+            let stdout = daemon.stdout.take().unwrap();
+            let mut line = String::new();
+            std::io::BufReader::new(stdout).read_line(&mut line).unwrap();
+            line.trim().to_string()
+        };
+
+        let calls = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+        let player = zbus::connection::Builder::address(address.as_str())
+            .unwrap()
+            .name("org.mpris.MediaPlayer2.qqmusic")
+            .unwrap()
+            .serve_at(MPRIS_OBJECT_PATH, FakePlayer { calls: calls.clone() })
+            .unwrap()
+            .build()
+            .await
+            .expect("fake player should register on the private bus");
+
+        let mut source = MprisSource::with_address(&address);
+        let info = source.read().await;
+        // 紧随其后的多会话读取复用同一次扫描；标识已缓存
+        let sessions = source.read_sessions().await;
+        let cached_label = source.labels.lock().unwrap().get("org.mpris.MediaPlayer2.qqmusic").cloned();
+        let play_pause = source.control(PlaybackCommand::PlayPause).await;
+        let seek = source.control(PlaybackCommand::Seek { position_ms: 60_000 }).await;
+
+        // 播放器退出（总线名释放）后标识缓存失效
+        drop(player);
+        let mut released = false;
+        for _ in 0..100 {
+            if source.labels.lock().unwrap().is_empty() {
+                released = true;
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+        let after_exit = source.read().await;
+        let _ = daemon.kill();
+        let _ = daemon.wait();
+
+        let info = info.expect("read should succeed").expect("player should be found");
+        assert_eq!(info.title, "修炼爱情");
+        assert_eq!(info.artist, "林俊杰");
+        assert_eq!(info.album, "因你 而在");
+        assert_eq!(info.current_time_ms, 30_000);
+        assert_eq!(info.total_time_ms, 287_000);
+        assert!(!info.is_playing);
+        assert_eq!(info.source_app, "org.mpris.MediaPlayer2.qqmusic");
+
+        let sessions = sessions.expect("read_sessions should succeed");
+        assert_eq!(sessions.len(), 1);
+        assert_eq!(sessions[0].title, "修炼爱情");
+        assert_eq!(cached_label.as_deref(), Some("org.mpris.MediaPlayer2.qqmusic"));
+        assert!(released, "label cache should be cleared on NameOwnerChanged");
+        assert_eq!(after_exit.expect("read should succeed"), None);
+
+        play_pause.expect("PlayPause should succeed");
+        seek.expect("SetPosition should succeed");
+        assert_eq!(
+            *calls.lock().unwrap(),
+            vec!["PlayPause".to_string(), "SetPosition /track/1 60000000".to_string()]
+        );
+    }
+}
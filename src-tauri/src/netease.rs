@@ -0,0 +1,264 @@
+use anyhow::{bail, Context, Result};
+use reqwest::{Client, Url};
+use serde_json::Value;
+
+use crate::lyric_provider::{LyricCandidate, LyricProvider, ProviderLyrics};
+use crate::lyrics_service::BoxFuture;
+use crate::song_match::{self, LyricsQuery, SongFields};
+use crate::song_info::{QrcLine, QrcWord};
+
+const SEARCH_URL: &str = "https://music.163.com/api/cloudsearch/pc";
+const LYRIC_URL: &str = "https://music.163.com/api/song/lyric/v1";
+/// 每次搜索取的候选数
+const SEARCH_LIMIT: &str = "10";
+
+/// 网易云音乐搜索结果中的一首歌
+#[derive(Debug, Clone, PartialEq)]
+pub struct NeteaseSong {
+    pub id: u64,
+    pub name: String,
+    pub artists: Vec<String>,
+    pub album: String,
+    pub pic_url: String,
+    pub duration_ms: u64,
+}
+
+/// 网易云音乐：cloudsearch 搜索 + lyric/v1（lrc、tlyric、romalrc、逐字 yrc）
+pub struct NeteaseProvider {
+    client: Client,
+    debug: bool,
+}
+
+impl NeteaseProvider {
+    pub fn new(debug: bool) -> Self {
+        Self {
+            client: Client::builder()
+                .user_agent("Mozilla/5.0 (Windows NT 10.0; Win64; x64) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/131.0.0.0 Safari/537.36")
+                .build()
+                .unwrap_or_default(),
+            debug,
+        }
+    }
+
+    async fn get_json(&self, base: &str, params: &[(&str, &str)]) -> Result<Value> {
+        let url = Url::parse_with_params(base, params)?;
+        let resp: Value = self.client.get(url)
+            .header("Referer", "https://music.163.com/")
+            .send()
+            .await
+            .context("NetEase request failed")?
+            .json()
+            .await
+            .context("Failed to parse NetEase JSON")?;
+        match resp["code"].as_i64() {
+            Some(200) => Ok(resp),
+            code => bail!("NetEase API returned code {:?}", code),
+        }
+    }
+
+    pub async fn search(&self, keyword: &str) -> Result<Vec<NeteaseSong>> {
+        let resp = self.get_json(SEARCH_URL, &[("s", keyword), ("type", "1"), ("limit", SEARCH_LIMIT), ("offset", "0")]).await?;
+        Ok(parse_search(&resp))
+    }
+
+    pub async fn get_lyric(&self, id: u64) -> Result<ProviderLyrics> {
+        let id = id.to_string();
+        let params = [("id", id.as_str()), ("lv", "-1"), ("tv", "-1"), ("rv", "-1"), ("yv", "-1")];
+        let resp = self.get_json(LYRIC_URL, &params).await?;
+        Ok(parse_lyric(&resp))
+    }
+}
+
+impl LyricProvider for NeteaseProvider {
+    fn id(&self) -> &'static str {
+        "netease"
+    }
+
+    fn fetch<'a>(&'a self, query: &'a LyricsQuery) -> BoxFuture<'a, Result<Option<ProviderLyrics>>> {
+        Box::pin(async move {
+            // "artist title" 搜不到时只用标题再搜一次（多歌手分隔符常导致无结果）
+            let mut song = None;
+            for keyword in [format!("{} {}", query.artist, query.title), query.title.clone()] {
+                let songs = self.search(&keyword).await?;
+                if let Some(found) = pick_song(&songs, query) {
+                    song = Some(found.clone());
+                    break;
+                }
+            }
+            let Some(song) = song else {
+                return Ok(None);
+            };
+            if self.debug { eprintln!("[netease] Matched '{} - {}' (id={})", song.artists.join("/"), song.name, song.id); }
+
+            let mut lyrics = self.get_lyric(song.id).await?;
+            lyrics.pic_url = song.pic_url;
+            Ok(lyrics.has_lyrics().then_some(lyrics))
+        })
+    }
+
+    fn search<'a>(&'a self, query: &'a LyricsQuery) -> BoxFuture<'a, Result<Vec<LyricCandidate>>> {
+        Box::pin(async move {
+            let songs = self.search(&format!("{} {}", query.artist, query.title)).await?;
+            Ok(songs
+                .iter()
+                .map(|song| LyricCandidate::song(self.id(), song.id.to_string(), &song.name, &song.artists.join("/"), &song.album, song.duration_ms))
+                .collect())
+        })
+    }
+
+    fn fetch_by_id<'a>(&'a self, id: &'a str) -> BoxFuture<'a, Result<Option<ProviderLyrics>>> {
+        Box::pin(async move {
+            let id = id.trim().parse().with_context(|| format!("Invalid NetEase song id '{}'", id))?;
+            let lyrics = self.get_lyric(id).await?;
+            Ok(lyrics.has_lyrics().then_some(lyrics))
+        })
+    }
+}
+
+/// 解析 cloudsearch 响应
+pub fn parse_search(resp: &Value) -> Vec<NeteaseSong> {
+    let Some(songs) = resp["result"]["songs"].as_array() else {
+        return Vec::new();
+    };
+    songs
+        .iter()
+        .filter_map(|song| {
+            Some(NeteaseSong {
+                id: song["id"].as_u64()?,
+                name: song["name"].as_str()?.to_string(),
+                artists: song["ar"]
+                    .as_array()
+                    .map(|ar| ar.iter().filter_map(|a| a["name"].as_str().map(str::to_string)).collect())
+                    .unwrap_or_default(),
+                album: song["al"]["name"].as_str().unwrap_or_default().to_string(),
+                pic_url: song["al"]["picUrl"].as_str().unwrap_or_default().to_string(),
+                duration_ms: song["dt"].as_u64().unwrap_or(0),
+            })
+        })
+        .collect()
+}
+
+/// 从候选中选出同一首歌中匹配度最高的一首（见 `song_match::pick_best`）。
+/// 标题不一致的结果（如"(伴奏)"版本）不采用，交给下一个歌词源。
+pub fn pick_song<'a>(songs: &'a [NeteaseSong], query: &LyricsQuery) -> Option<&'a NeteaseSong> {
+    song_match::pick_best(songs, query, |song| SongFields {
+        title: &song.name,
+        artists: &song.artists,
+        album: &song.album,
+        duration_ms: song.duration_ms,
+    })
+}
+
+/// 解析 lyric/v1 响应；`nolyric` / `uncollected` 时各字段为空
+pub fn parse_lyric(resp: &Value) -> ProviderLyrics {
+    let text = |key: &str| resp[key]["lyric"].as_str().unwrap_or_default().to_string();
+    ProviderLyrics {
+        provider: "netease",
+        lyrics: text("lrc"),
+        trans: text("tlyric"),
+        roma: text("romalrc"),
+        qrc_data: parse_yrc(&text("yrc")),
+        ..ProviderLyrics::default()
+    }
+}
+
+/// 解析 YRC 逐字歌词：`[行开始,行时长](字开始,字时长,0)字(字开始,字时长,0)字…`。
+///
+/// 与 QRC 相同，字时间为相对歌曲开头的绝对时间，可直接转为 `QrcLine`；
+/// 作词/作曲等 JSON 行（`{"t":…}`）与没有文字的行被跳过。
+pub fn parse_yrc(text: &str) -> Vec<QrcLine> {
+    text.lines().filter_map(parse_yrc_line).collect()
+}
+
+fn parse_yrc_line(line: &str) -> Option<QrcLine> {
+    let (header, mut body) = line.trim().strip_prefix('[')?.split_once(']')?;
+    let (start, duration) = header.split_once(',')?;
+    let start_time_ms = start.trim().parse().ok()?;
+    let duration_ms = duration.trim().parse().ok()?;
+
+    let mut words = Vec::new();
+    while let Some(((word_start, word_duration), rest)) = take_timing(body) {
+        // 字的文本一直到下一个时间标记；文本本身可能含括号，如 "(again)"
+        let end = rest
+            .char_indices()
+            .find(|(i, c)| *c == '(' && take_timing(&rest[*i..]).is_some())
+            .map_or(rest.len(), |(i, _)| i);
+        words.push(QrcWord { content: rest[..end].to_string(), start_time_ms: word_start, duration_ms: word_duration });
+        body = &rest[end..];
+    }
+
+    let content: String = words.iter().map(|w| w.content.as_str()).collect();
+    if content.trim().is_empty() {
+        return None;
+    }
+    Some(QrcLine { content, start_time_ms, duration_ms, words })
+}
+
+/// 解析开头的 `(开始,时长,0)` 时间标记，返回时间与剩余文本
+fn take_timing(s: &str) -> Option<((u64, u64), &str)> {
+    let inner_len = s.strip_prefix('(')?.find(')')?;
+    let parts: Vec<&str> = s[1..1 + inner_len].split(',').collect();
+    if !(2..=3).contains(&parts.len()) || !parts.iter().all(|p| p.trim().parse::<u64>().is_ok()) {
+        return None;
+    }
+    let start = parts[0].trim().parse().ok()?;
+    let duration = parts[1].trim().parse().ok()?;
+    Some(((start, duration), &s[inner_len + 2..]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::song_query;
+
+    const SEARCH_FIXTURE: &str = include_str!("../tests/fixtures/netease_search.json");
+    const LYRIC_FIXTURE: &str = include_str!("../tests/fixtures/netease_lyric.json");
+
+
+    #[test]
+    fn test_parse_search_and_pick() {
+        let songs = parse_search(&serde_json::from_str(SEARCH_FIXTURE).unwrap());
+        assert_eq!(songs.len(), 3);
+        assert_eq!(songs[0].artists, ["徐良"]);
+        assert_eq!(songs[0].duration_ms, 253_440);
+
+        // 跳过伴奏版与其他歌手的翻唱
+        assert_eq!(pick_song(&songs, &song_query(253_000)).map(|s| s.id), Some(1901371646));
+        let cover_version = LyricsQuery { artist: "群星".to_string(), ..song_query(0) };
+        assert_eq!(pick_song(&songs, &cover_version).map(|s| s.id), Some(29732235));
+        let unknown = LyricsQuery { title: "不存在".to_string(), ..song_query(0) };
+        assert!(pick_song(&songs, &unknown).is_none());
+
+        assert!(parse_search(&serde_json::json!({ "result": { "songCount": 0 }, "code": 200 })).is_empty());
+    }
+
+    #[test]
+    fn test_parse_lyric() {
+        let lyrics = parse_lyric(&serde_json::from_str(LYRIC_FIXTURE).unwrap());
+        assert!(lyrics.lyrics.contains("[00:16.210]雨落在窗前"));
+        assert!(lyrics.trans.contains("rain on the window"));
+        assert!(lyrics.roma.contains("yu luo zai chuang qian"));
+        assert_eq!(lyrics.qrc_data.len(), 2);
+        assert!(lyrics.has_lyrics());
+
+        let none = parse_lyric(&serde_json::json!({ "nolyric": true, "code": 200 }));
+        assert!(!none.has_lyrics());
+    }
+
+    #[test]
+    fn test_parse_yrc() {
+        let lyrics = parse_lyric(&serde_json::from_str(LYRIC_FIXTURE).unwrap());
+        let line = &lyrics.qrc_data[0];
+        assert_eq!((line.start_time_ms, line.duration_ms), (16210, 3460));
+        assert_eq!(line.content, "雨落在窗前");
+        assert_eq!(line.words.len(), 4);
+        assert_eq!(line.words[3], QrcWord { content: "窗前".to_string(), start_time_ms: 17810, duration_ms: 1860 });
+
+        // 文本中的括号不是时间标记
+        let line = &lyrics.qrc_data[1];
+        assert_eq!(line.content, "Rain falls (again)");
+        assert_eq!(line.words.iter().map(|w| w.content.as_str()).collect::<Vec<_>>(), ["Rain ", "falls ", "(again)"]);
+
+        assert!(parse_yrc("[100,abc](100,50,0)x\n[oops").is_empty());
+    }
+}
@@ -0,0 +1,178 @@
+use serde::Serialize;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+
+use crate::song_info::{QrcLine, RepeatMode, SongInfo};
+
+/// WebSocket 协议版本。
+///
+/// - v1（默认，兼容旧客户端）：每次变化推送完整 `SongInfo`
+/// - v2（`/ws?protocol=2`）：静态内容变化时推送一次 `track`，其余只推送几十字节的 `tick`
+pub const PROTOCOL_VERSION: u32 = 2;
+
+/// v2 服务端消息
+#[derive(Debug, Serialize, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ServerMessage<'a> {
+    Hello { version: u32 },
+    Track(TrackPayload<'a>),
+    Tick(TickPayload),
+}
+
+/// 一首歌的静态内容：歌词加载完成、封面更新时会以新的 `track_id` 重发
+#[derive(Debug, Serialize, PartialEq)]
+pub struct TrackPayload<'a> {
+    /// 静态内容的摘要（十六进制），tick 携带同一 id 以便客户端对齐
+    pub track_id: String,
+    pub source_app: &'a str,
+    pub title: &'a str,
+    pub artist: &'a str,
+    pub album: &'a str,
+    pub lyrics: &'a str,
+    pub trans: &'a str,
+    pub qrc_data: &'a [QrcLine],
+    /// 封面地址：在线地址，或相对同步服务的 `/api/cover/{id}`（见 cover_store）
+    pub cover_url: &'a str,
+    /// 在线搜索命中歌曲的匹配度（0~1），非搜索来源为 null
+    pub lyrics_confidence: Option<f32>,
+}
+
+/// 高频进度消息
+#[derive(Debug, Serialize, PartialEq)]
+pub struct TickPayload {
+    pub track_id: String,
+    pub current_time_ms: u64,
+    pub total_time_ms: u64,
+    pub is_playing: bool,
+    pub server_ts: u64,
+    pub playback_rate: Option<f64>,
+    pub shuffle: Option<bool>,
+    pub repeat_mode: Option<RepeatMode>,
+    /// 本曲歌词偏移（毫秒，正值提前），可随时调整，因此放在 tick 而不触发 track 重发
+    pub lyrics_offset_ms: i64,
+    /// 按本曲偏移修正后的歌词时间，与 `current_time_ms` 同一采样时刻
+    pub lyric_time_ms: u64,
+}
+
+/// 静态内容摘要：进度、播放状态等高频字段不参与计算
+pub fn track_id(info: &SongInfo) -> String {
+    let mut hasher = DefaultHasher::new();
+    info.source_app.hash(&mut hasher);
+    info.title.hash(&mut hasher);
+    info.artist.hash(&mut hasher);
+    info.album.hash(&mut hasher);
+    info.lyrics.hash(&mut hasher);
+    info.trans.hash(&mut hasher);
+    info.qrc_data.hash(&mut hasher);
+    info.album_pic_url.hash(&mut hasher);
+    format!("{:016x}", hasher.finish())
+}
+
+/// 单个连接的 v2 编码状态：记住已发送的 track，之后只发 tick
+#[derive(Debug, Default)]
+pub struct TrackTickEncoder {
+    sent_track_id: Option<String>,
+}
+
+impl TrackTickEncoder {
+    /// 编码一次状态变化：静态内容变化时先发 `track`，然后总是发 `tick`
+    pub fn encode(&mut self, info: &SongInfo) -> Vec<String> {
+        let id = track_id(info);
+        let mut messages = Vec::with_capacity(2);
+        if self.sent_track_id.as_deref() != Some(id.as_str()) {
+            let track = ServerMessage::Track(TrackPayload {
+                track_id: id.clone(),
+                source_app: &info.source_app,
+                title: &info.title,
+                artist: &info.artist,
+                album: &info.album,
+                lyrics: &info.lyrics,
+                trans: &info.trans,
+                qrc_data: &info.qrc_data,
+                cover_url: &info.album_pic_url,
+                lyrics_confidence: info.lyrics_confidence,
+            });
+            messages.extend(serde_json::to_string(&track).ok());
+            self.sent_track_id = Some(id.clone());
+        }
+        let tick = ServerMessage::Tick(TickPayload {
+            track_id: id,
+            current_time_ms: info.current_time_ms,
+            total_time_ms: info.total_time_ms,
+            is_playing: info.is_playing,
+            server_ts: info.server_ts,
+            playback_rate: info.playback_rate,
+            shuffle: info.shuffle,
+            repeat_mode: info.repeat_mode,
+            lyrics_offset_ms: info.lyrics_offset_ms,
+            lyric_time_ms: info.lyric_time_ms,
+        });
+        messages.extend(serde_json::to_string(&tick).ok());
+        messages
+    }
+
+    /// 切换订阅后强制重发 track
+    pub fn reset(&mut self) {
+        self.sent_track_id = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn song(current_time_ms: u64) -> SongInfo {
+        SongInfo {
+            title: "那时雨".to_string(),
+            artist: "徐良".to_string(),
+            lyrics: "[00:01.00]那时雨".to_string(),
+            album_pic_url: "/api/cover/003kQTm81fCMvb".to_string(),
+            current_time_ms,
+            total_time_ms: 200_000,
+            is_playing: true,
+            server_ts: 1_000 + current_time_ms,
+            ..SongInfo::default()
+        }
+    }
+
+    /// 同一首歌只在第一次发送 track，之后只有 tick；歌词加载完成后重发 track
+    #[test]
+    fn test_track_sent_once_then_ticks() {
+        let mut encoder = TrackTickEncoder::default();
+        let first = encoder.encode(&song(0));
+        assert_eq!(first.len(), 2);
+        let track: serde_json::Value = serde_json::from_str(&first[0]).unwrap();
+        assert_eq!(track["type"], "track");
+        assert_eq!(track["cover_url"], "/api/cover/003kQTm81fCMvb");
+
+        let next = encoder.encode(&song(50));
+        assert_eq!(next.len(), 1);
+        let tick: serde_json::Value = serde_json::from_str(&next[0]).unwrap();
+        assert_eq!(tick["type"], "tick");
+        assert_eq!(tick["current_time_ms"], 50);
+        assert_eq!(tick["lyrics_offset_ms"], 0);
+        assert_eq!(tick["track_id"], track["track_id"]);
+        assert!(next[0].len() < 300, "{}", next[0]);
+
+        let with_trans = SongInfo { trans: "[00:01.00]When it rained".to_string(), ..song(100) };
+        assert_eq!(encoder.encode(&with_trans).len(), 2);
+
+        encoder.reset();
+        assert_eq!(encoder.encode(&with_trans).len(), 2);
+    }
+
+    /// 本曲偏移由服务端修正进歌词时间，不影响 track
+    #[test]
+    fn test_tick_carries_lyric_time() {
+        let mut encoder = TrackTickEncoder::default();
+        encoder.encode(&song(0));
+        let mut info = song(60_000);
+        info.set_lyrics_offset(-300);
+        let messages = encoder.encode(&info);
+        assert_eq!(messages.len(), 1);
+        let tick: serde_json::Value = serde_json::from_str(&messages[0]).unwrap();
+        assert_eq!(tick["current_time_ms"], 60_000);
+        assert_eq!(tick["lyrics_offset_ms"], -300);
+        assert_eq!(tick["lyric_time_ms"], 59_700);
+    }
+}
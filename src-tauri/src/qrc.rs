@@ -348,6 +348,14 @@ pub fn parse_qrc_text(qrc_text: &str) -> Vec<QrcLine> {
     lines
 }
 
+/// 按行生成 `[mm:ss.xx]` 格式的 LRC（逐字歌词来源缺少 LRC 时用于文本输出与翻译对齐）
+pub fn lines_to_lrc<'a>(lines: impl Iterator<Item = (u64, &'a str)>) -> String {
+    lines
+        .map(|(ms, text)| format!("[{:02}:{:02}.{:02}]{}", ms / 60_000, ms / 1000 % 60, ms % 1000 / 10, text))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
 // Extract plain LRC text from XML (<Lyric_n LyricContent="...">) if it's an XML
 pub fn extract_lrc_from_xml(xml_content: &str) -> Option<String> {
     let root = Element::parse(xml_content.as_bytes()).ok()?;
@@ -516,8 +524,12 @@ mod tests {
     #[test]
     fn test_compare_online_vs_local_qrc() {
         let test_path = r"D:\QQMusicCache\QQMusicLyricNew\林俊杰 - 修炼爱情 - 287 - 因你 而在_qm.qrc";
-        let song_title = "修炼爱情";
-        let song_artist = "林俊杰";
+        let query = crate::song_match::LyricsQuery {
+            title: "修炼爱情".to_string(),
+            artist: "林俊杰".to_string(),
+            album: "因你 而在".to_string(),
+            duration_ms: 287_000,
+        };
 
         // 1. 读取本地文件
         let path = std::path::Path::new(test_path);
@@ -534,7 +546,7 @@ mod tests {
         // 2. 从在线 API 获取 QRC 原始 hex 字符串
         let rt = tokio::runtime::Runtime::new().unwrap();
         let fetcher = crate::lyrics::LyricFetcher::with_debug(true);
-        let (lyrics, _trans, qrc_hex, _pic_url) = match rt.block_on(fetcher.fetch_lyrics(song_title, song_artist)) {
+        let (lyrics, _trans, qrc_hex, _pic_url) = match rt.block_on(fetcher.fetch_lyrics(&query)) {
             Ok(r) => r,
             Err(e) => { eprintln!("[test] 在线获取失败: {}", e); return; }
         };
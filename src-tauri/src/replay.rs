@@ -0,0 +1,447 @@
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::cell::Cell;
+use std::path::Path;
+use std::rc::Rc;
+use std::time::Instant;
+
+use crate::capture::{self, CaptureEntry};
+use crate::media_source::{song_info_from_sample, LocalBoxFuture, MediaSample, MediaSource};
+use crate::song_info::SongInfo;
+
+/// 回放脚本：按顺序播放的歌曲列表，用于确定性测试与无播放器演示。
+///
+/// ```toml
+/// repeat = true
+///
+/// [[tracks]]
+/// title = "那时雨"
+/// artist = "徐良"
+/// duration_ms = 200000
+/// play_ms = 30000            # 30 秒后切到下一首（默认播放到结尾）
+/// timeline_lag_ms = 1500     # 切歌后 1.5 秒内 timeline 仍报上一首（模拟 SMTC 滞后）
+/// events = [
+///     { at_ms = 5000, action = "pause" },
+///     { at_ms = 8000, action = "resume" },
+///     { at_ms = 10000, action = "seek", position_ms = 60000 },
+/// ]
+/// ```
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
+pub struct ReplayScript {
+    /// 播放完最后一首后从头循环
+    #[serde(default)]
+    pub repeat: bool,
+    pub tracks: Vec<ReplayTrack>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
+pub struct ReplayTrack {
+    pub title: String,
+    #[serde(default)]
+    pub artist: String,
+    #[serde(default)]
+    pub album: String,
+    #[serde(default)]
+    pub album_pic_url: String,
+    pub duration_ms: u64,
+    /// 在本曲停留的脚本时长（毫秒），到点切歌；缺省时播放到结尾
+    #[serde(default)]
+    pub play_ms: Option<u64>,
+    /// 切到本曲后 timeline 仍报上一首 Position/EndTime 的时长（毫秒）
+    #[serde(default)]
+    pub timeline_lag_ms: u64,
+    #[serde(default)]
+    pub events: Vec<ReplayEvent>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
+pub struct ReplayEvent {
+    /// 相对本曲开始的脚本时间（毫秒），同一曲内按时间升序排列
+    pub at_ms: u64,
+    #[serde(flatten)]
+    pub action: ReplayAction,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum ReplayAction {
+    Pause,
+    Resume,
+    Seek { position_ms: u64 },
+}
+
+/// 某一脚本时刻的曲内状态
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct TrackState {
+    position_ms: u64,
+    is_playing: bool,
+}
+
+impl ReplayTrack {
+    /// 按事件推演到曲内时刻 `elapsed_ms` 时的播放位置与状态
+    fn state_at(&self, elapsed_ms: u64) -> TrackState {
+        let mut position_ms = 0u64;
+        let mut is_playing = true;
+        let mut last_ms = 0u64;
+
+        for event in self.events.iter().filter(|e| e.at_ms <= elapsed_ms) {
+            if is_playing {
+                position_ms += event.at_ms.saturating_sub(last_ms);
+            }
+            last_ms = last_ms.max(event.at_ms);
+            match event.action {
+                ReplayAction::Pause => is_playing = false,
+                ReplayAction::Resume => is_playing = true,
+                ReplayAction::Seek { position_ms: to } => position_ms = to,
+            }
+        }
+        if is_playing {
+            position_ms += elapsed_ms.saturating_sub(last_ms);
+        }
+
+        TrackState {
+            position_ms: position_ms.min(self.duration_ms),
+            is_playing: is_playing && position_ms < self.duration_ms,
+        }
+    }
+
+    /// 本曲占用的脚本时长：显式 `play_ms`，否则为播放到结尾所需的时间。
+    /// 以暂停结束且不再恢复时返回 None（停留在本曲）。
+    fn span_ms(&self) -> Option<u64> {
+        if let Some(play_ms) = self.play_ms {
+            return Some(play_ms);
+        }
+        // 在每段事件区间内检查是否能播到结尾
+        let mut starts: Vec<u64> = std::iter::once(0).chain(self.events.iter().map(|e| e.at_ms)).collect();
+        starts.sort_unstable();
+        starts.dedup();
+        for (i, &from) in starts.iter().enumerate() {
+            let state = self.state_at(from);
+            if state.position_ms >= self.duration_ms {
+                return Some(from);
+            }
+            if !state.is_playing {
+                continue;
+            }
+            let end = from + (self.duration_ms - state.position_ms);
+            match starts.get(i + 1) {
+                Some(&next) if end > next => continue,
+                _ => return Some(end),
+            }
+        }
+        None
+    }
+}
+
+impl ReplayScript {
+    /// 从文件加载脚本：`.toml` 按 TOML 解析，其余按 JSON 解析
+    pub fn load_from_file(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| anyhow!("无法读取回放文件 {}: {}", path.display(), e))?;
+        let is_toml = path.extension().and_then(|e| e.to_str()) == Some("toml");
+        let script: Self = if is_toml {
+            toml::from_str(&content).map_err(|e| anyhow!("解析回放文件失败: {}", e))?
+        } else {
+            serde_json::from_str(&content).map_err(|e| anyhow!("解析回放文件失败: {}", e))?
+        };
+        if script.tracks.is_empty() {
+            anyhow::bail!("回放文件 {} 中没有歌曲", path.display());
+        }
+        Ok(script)
+    }
+
+    /// 脚本时刻 `script_ms` 的原始采样；脚本播放完毕（且不循环）时返回 None
+    pub fn sample_at(&self, script_ms: u64) -> Option<MediaSample> {
+        let spans: Vec<Option<u64>> = self.tracks.iter().map(|t| t.span_ms()).collect();
+        let total_ms = spans.iter().try_fold(0u64, |acc, s| s.map(|s| acc + s));
+
+        let mut t = match total_ms {
+            Some(total) if self.repeat && total > 0 => script_ms % total,
+            _ => script_ms,
+        };
+
+        for (idx, track) in self.tracks.iter().enumerate() {
+            let span = spans[idx];
+            let in_track = match span {
+                Some(span) => t < span,
+                None => true,
+            };
+            if !in_track {
+                t -= span.unwrap_or(0);
+                continue;
+            }
+
+            // 切歌后一段时间 timeline 仍报上一首（标题已更新），复现 SMTC 的异步更新间隙
+            let previous = if idx > 0 {
+                Some(idx - 1)
+            } else if self.repeat && total_ms.is_some() {
+                Some(self.tracks.len() - 1)
+            } else {
+                None
+            };
+            let (position_ms, end_time_ms, is_playing) = match previous {
+                Some(prev_idx) if t < track.timeline_lag_ms => {
+                    let prev = &self.tracks[prev_idx];
+                    let prev_state = prev.state_at(spans[prev_idx].unwrap_or(0));
+                    (prev_state.position_ms, prev.duration_ms, true)
+                }
+                _ => {
+                    let state = track.state_at(t);
+                    (state.position_ms, track.duration_ms, state.is_playing)
+                }
+            };
+
+            return Some(MediaSample {
+                title: track.title.clone(),
+                artist: track.artist.clone(),
+                album: track.album.clone(),
+                position_ms,
+                end_time_ms,
+                last_updated_ms: None,
+                is_playing,
+                album_pic_url: track.album_pic_url.clone(),
+                ..MediaSample::default()
+            });
+        }
+
+        None
+    }
+}
+
+/// 回放时钟：实时（可加速）或测试用的手动时钟
+#[derive(Debug, Clone)]
+pub enum ReplayClock {
+    /// 脚本时间 = 真实流逝时间 × speed
+    RealTime { started: Instant, speed: f64 },
+    /// 由测试代码直接设置脚本时间（毫秒）
+    Manual(Rc<Cell<u64>>),
+}
+
+impl ReplayClock {
+    pub fn real_time(speed: f64) -> Self {
+        Self::RealTime {
+            started: Instant::now(),
+            speed: if speed > 0.0 { speed } else { 1.0 },
+        }
+    }
+
+    pub fn now_ms(&self) -> u64 {
+        match self {
+            Self::RealTime { started, speed } => (started.elapsed().as_millis() as f64 * speed) as u64,
+            Self::Manual(cell) => cell.get(),
+        }
+    }
+}
+
+/// 回放时间线：手写脚本，或 `--record` 录制的原始采样
+#[derive(Debug, Clone)]
+pub enum ReplayTimeline {
+    Script(ReplayScript),
+    Capture(Vec<CaptureEntry>),
+}
+
+impl From<ReplayScript> for ReplayTimeline {
+    fn from(script: ReplayScript) -> Self {
+        Self::Script(script)
+    }
+}
+
+impl ReplayTimeline {
+    /// 按扩展名加载：`.ndjson` / `.jsonl` 为录制文件，其余为回放脚本
+    pub fn load_from_file(path: &Path) -> Result<Self> {
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("ndjson") | Some("jsonl") => Ok(Self::Capture(capture::load_capture(path)?)),
+            _ => Ok(Self::Script(ReplayScript::load_from_file(path)?)),
+        }
+    }
+
+    /// 回放时刻 `replay_ms` 的 SongInfo；时间线结束时返回 None
+    fn song_info_at(&self, replay_ms: u64, now_ms: i64) -> Option<SongInfo> {
+        match self {
+            Self::Script(script) => script
+                .sample_at(replay_ms)
+                .map(|sample| song_info_from_sample(sample, now_ms)),
+            Self::Capture(entries) => {
+                // 录制的时间戳处于录制时的时间域：在该时间域内做漂移修正，
+                // 才能与现场读到的进度一致
+                let first_ts = entries.first()?.ts_ms;
+                let capture_now = first_ts + replay_ms as i64;
+                let last_ts = entries.last()?.ts_ms;
+                if capture_now > last_ts {
+                    return None;
+                }
+                let idx = entries.partition_point(|e| e.ts_ms <= capture_now);
+                let entry = &entries[idx.saturating_sub(1)];
+                let mut info = song_info_from_sample(entry.to_sample(), capture_now);
+                info.server_ts = now_ms.max(0) as u64;
+                info.source_app = entry.source.clone();
+                Some(info)
+            }
+        }
+    }
+}
+
+/// 回放媒体源：按脚本或录制的时间线输出 SongInfo，替代 SMTC
+pub struct ReplaySource {
+    timeline: ReplayTimeline,
+    clock: ReplayClock,
+}
+
+impl ReplaySource {
+    pub fn new(timeline: impl Into<ReplayTimeline>, clock: ReplayClock) -> Self {
+        Self { timeline: timeline.into(), clock }
+    }
+
+    pub fn from_file(path: &Path, speed: f64) -> Result<Self> {
+        Ok(Self::new(ReplayTimeline::load_from_file(path)?, ReplayClock::real_time(speed)))
+    }
+}
+
+impl MediaSource for ReplaySource {
+    fn name(&self) -> &'static str {
+        "Replay"
+    }
+
+    fn read(&mut self) -> LocalBoxFuture<'_, Result<Option<SongInfo>>> {
+        let now_ms = chrono::Utc::now().timestamp_millis();
+        let info = self.timeline.song_info_at(self.clock.now_ms(), now_ms);
+        Box::pin(async move { Ok(info) })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn track(title: &str, duration_ms: u64) -> ReplayTrack {
+        ReplayTrack {
+            title: title.to_string(),
+            artist: "徐良".to_string(),
+            album: String::new(),
+            album_pic_url: String::new(),
+            duration_ms,
+            play_ms: None,
+            timeline_lag_ms: 0,
+            events: Vec::new(),
+        }
+    }
+
+    fn event(at_ms: u64, action: ReplayAction) -> ReplayEvent {
+        ReplayEvent { at_ms, action }
+    }
+
+    /// 暂停期间位置不前进，恢复后继续；seek 直接跳到目标位置
+    #[test]
+    fn test_pause_resume_and_seek() {
+        let mut t = track("那时雨", 200_000);
+        t.events = vec![
+            event(5_000, ReplayAction::Pause),
+            event(8_000, ReplayAction::Resume),
+            event(10_000, ReplayAction::Seek { position_ms: 60_000 }),
+        ];
+        assert_eq!(t.state_at(4_000), TrackState { position_ms: 4_000, is_playing: true });
+        assert_eq!(t.state_at(7_000), TrackState { position_ms: 5_000, is_playing: false });
+        assert_eq!(t.state_at(9_000), TrackState { position_ms: 6_000, is_playing: true });
+        assert_eq!(t.state_at(12_000), TrackState { position_ms: 62_000, is_playing: true });
+        // 3 秒暂停 + seek 跳过 53 秒：结尾时刻 = 10s + (200s - 60s)
+        assert_eq!(t.span_ms(), Some(150_000));
+
+        // 暂停后不再恢复：停留在本曲
+        let mut paused = track("那时雨", 200_000);
+        paused.events = vec![event(5_000, ReplayAction::Pause)];
+        assert_eq!(paused.span_ms(), None);
+    }
+
+    /// 播放到结尾或 play_ms 到点后切到下一首；脚本结束后无播放
+    #[test]
+    fn test_track_changes() {
+        let mut first = track("A", 10_000);
+        first.play_ms = Some(4_000);
+        let script = ReplayScript { repeat: false, tracks: vec![first, track("B", 5_000)] };
+
+        assert_eq!(script.sample_at(3_999).unwrap().title, "A");
+        let b = script.sample_at(4_500).unwrap();
+        assert_eq!(b.title, "B");
+        assert_eq!(b.position_ms, 500);
+        assert!(script.sample_at(9_000).is_none());
+
+        let looped = ReplayScript { repeat: true, ..script };
+        assert_eq!(looped.sample_at(9_500).unwrap().title, "A");
+    }
+
+    /// timeline_lag_ms 内标题已是新歌，但 Position/EndTime 仍为上一首
+    #[test]
+    fn test_timeline_lag_reports_previous_track() {
+        let mut second = track("B", 300_000);
+        second.timeline_lag_ms = 1_500;
+        let script = ReplayScript { repeat: false, tracks: vec![track("A", 10_000), second] };
+
+        let lagging = script.sample_at(11_000).unwrap();
+        assert_eq!(lagging.title, "B");
+        assert_eq!(lagging.end_time_ms, 10_000);
+        assert_eq!(lagging.position_ms, 10_000);
+
+        let settled = script.sample_at(12_000).unwrap();
+        assert_eq!(settled.end_time_ms, 300_000);
+        assert_eq!(settled.position_ms, 2_000);
+    }
+
+    #[test]
+    fn test_parse_toml_script() {
+        let script: ReplayScript = toml::from_str(r#"
+            repeat = true
+
+            [[tracks]]
+            title = "那时雨"
+            artist = "徐良"
+            duration_ms = 200000
+            events = [
+                { at_ms = 5000, action = "pause" },
+                { at_ms = 10000, action = "seek", position_ms = 60000 },
+            ]
+        "#).unwrap();
+        assert!(script.repeat);
+        assert_eq!(script.tracks[0].events[1].action, ReplayAction::Seek { position_ms: 60_000 });
+    }
+
+    /// 加速时钟：手动时钟驱动 ReplaySource 输出对应 SongInfo
+    #[tokio::test]
+    async fn test_replay_source_with_manual_clock() {
+        let clock = Rc::new(Cell::new(0));
+        let script = ReplayScript { repeat: false, tracks: vec![track("那时雨", 200_000)] };
+        let mut source = ReplaySource::new(script, ReplayClock::Manual(clock.clone()));
+
+        clock.set(42_000);
+        let info = source.read().await.unwrap().unwrap();
+        assert_eq!(info.title, "那时雨");
+        assert_eq!(info.current_time_ms, 42_000);
+        assert_eq!(info.total_time_ms, 200_000);
+        assert!(info.is_playing);
+    }
+
+    /// 录制回放：在录制时间域内做漂移修正，采样之间按 LastUpdatedTime 前推
+    #[tokio::test]
+    async fn test_replay_capture_entries() {
+        let content = r#"{"ts_ms":1000000,"title":"那时雨","position_ms":10000,"last_updated_ms":999800,"end_time_ms":200000,"playback_status":"Playing"}
+{"ts_ms":1002000,"title":"不写完","position_ms":199000,"last_updated_ms":1001900,"end_time_ms":200000,"playback_status":"Changing"}"#;
+        let entries = capture::parse_capture(content).unwrap();
+        let clock = Rc::new(Cell::new(0));
+        let mut source = ReplaySource::new(ReplayTimeline::Capture(entries), ReplayClock::Manual(clock.clone()));
+
+        clock.set(500);
+        let info = source.read().await.unwrap().unwrap();
+        assert_eq!(info.title, "那时雨");
+        assert_eq!(info.current_time_ms, 10_700);
+
+        // 切歌瞬间 SMTC 仍报旧歌 timeline（Changing 视为未播放，不前推）
+        clock.set(2_000);
+        let info = source.read().await.unwrap().unwrap();
+        assert_eq!(info.title, "不写完");
+        assert_eq!(info.current_time_ms, 199_000);
+        assert!(!info.is_playing);
+
+        // 录制结束后视为无播放
+        clock.set(2_001);
+        assert!(source.read().await.unwrap().is_none());
+    }
+}
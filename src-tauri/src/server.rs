@@ -1,30 +1,78 @@
 use axum::{
     extract::{
         ws::{Message, WebSocket, WebSocketUpgrade},
-        State,
+        Path, Query, Request, State,
     },
-    routing::get,
+    http::{header, HeaderMap, HeaderValue, Method, StatusCode},
+    middleware::{self, Next},
+    response::IntoResponse,
+    routing::{get, post},
     Json, Router,
 };
+use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 use tokio::sync::watch;
-use tower_http::cors::CorsLayer;
+use tower_http::cors::{AllowOrigin, CorsLayer};
 
+use crate::control::{self, ControlError, PlaybackCommand, SeekResult, SeekTarget};
+use crate::cover_image::{CoverFormat, Palette};
+use crate::cover_store::{self, Cover};
+use crate::lyric_provider::LyricCandidate;
+use crate::lyrics_offsets::OffsetChange;
+use crate::lyrics_pins::LyricsPin;
+use crate::protocol::{ServerMessage, TrackTickEncoder, PROTOCOL_VERSION};
+use crate::sessions::SessionsSnapshot;
 use crate::song_info::SongInfo;
 
+/// 请求来源是否为本机页面（`http://127.0.0.1:1420`、`http://localhost:3000`、Tauri WebView 等）
+fn is_local_origin(origin: &HeaderValue) -> bool {
+    let Ok(origin) = origin.to_str() else {
+        return false;
+    };
+    let Some((scheme, rest)) = origin.split_once("://") else {
+        return false;
+    };
+    // 去掉端口；IPv6 形如 `[::1]:3000`
+    let host = match rest.strip_prefix('[') {
+        Some(v6) => v6.split(']').next().unwrap_or_default(),
+        None => rest.split(':').next().unwrap_or_default(),
+    };
+    match scheme {
+        "http" | "https" => matches!(host, "127.0.0.1" | "localhost" | "::1" | "tauri.localhost"),
+        "tauri" => host == "localhost",
+        _ => false,
+    }
+}
+
+/// 请求能否执行写操作：浏览器发出的请求须来自本机页面；没有 Origin 的请求来自 curl、脚本等非浏览器客户端，照常放行
+fn origin_allowed(headers: &HeaderMap) -> bool {
+    match headers.get(header::ORIGIN) {
+        Some(origin) => is_local_origin(origin),
+        None => true,
+    }
+}
+
+/// 写接口的来源检查。CORS 只能阻止网页读取响应，无请求体的 POST 属于简单请求，
+/// 浏览器不发预检就会直接执行，因此必须由服务端拒绝其他网页的请求
+async fn require_local_origin(request: Request, next: Next) -> axum::response::Response {
+    if !origin_allowed(request.headers()) {
+        return StatusCode::FORBIDDEN.into_response();
+    }
+    next.run(request).await
+}
+
 /// 服务端状态持有 watch::Receiver
 struct AppState {
     receiver: watch::Receiver<SongInfo>,
+    sessions: watch::Receiver<SessionsSnapshot>,
 }
 
-pub async fn start_server(port: u16, receiver: watch::Receiver<SongInfo>) {
-    let state = Arc::new(AppState { receiver });
-
-    let app = Router::new()
-        .route("/api/current", get(get_current))
-        .route("/ws", get(ws_handler))
-        .layer(CorsLayer::permissive())
-        .with_state(state);
+pub async fn start_server(
+    port: u16,
+    receiver: watch::Receiver<SongInfo>,
+    sessions: watch::Receiver<SessionsSnapshot>,
+) {
+    let app = router(Arc::new(AppState { receiver, sessions }));
 
     let addr = format!("127.0.0.1:{}", port);
     if let Ok(listener) = tokio::net::TcpListener::bind(&addr).await {
@@ -34,52 +82,538 @@ pub async fn start_server(port: u16, receiver: watch::Receiver<SongInfo>) {
     }
 }
 
+fn router(state: Arc<AppState>) -> Router {
+    // 写接口（控制、固定歌词、歌词偏移）不需要鉴权，只接受本机页面与非浏览器客户端，
+    // 避免任意网页借浏览器向本机服务发请求；只读接口保持任意来源可读
+    let write_routes = Router::new()
+        .route("/api/control", post(post_control))
+        .route("/api/control/play_pause", post(post_play_pause))
+        .route("/api/control/next", post(post_next))
+        .route("/api/control/previous", post(post_previous))
+        .route("/api/control/seek", post(post_seek))
+        .route("/api/control/seek_lyric", post(post_seek_lyric))
+        .route("/api/lyrics/candidates", get(get_lyric_candidates))
+        .route("/api/lyrics/pin", post(post_lyrics_pin).delete(delete_lyrics_pin))
+        .route("/api/lyrics/offset", post(post_lyrics_offset).delete(delete_lyrics_offset))
+        .route_layer(middleware::from_fn(require_local_origin))
+        .layer(
+            CorsLayer::new()
+                .allow_origin(AllowOrigin::predicate(|origin, _| is_local_origin(origin)))
+                .allow_methods([Method::POST, Method::DELETE])
+                .allow_headers([header::CONTENT_TYPE]),
+        );
+    Router::new()
+        .route("/api/current", get(get_current))
+        .route("/api/sessions", get(get_sessions))
+        .route("/api/sessions/:id", get(get_session))
+        .route("/api/cover/:id", get(get_cover))
+        .route("/api/cover/:id/palette", get(get_cover_palette))
+        .route("/ws", get(ws_handler))
+        .layer(CorsLayer::permissive())
+        .merge(write_routes)
+        .with_state(state)
+}
+
 async fn get_current(State(state): State<Arc<AppState>>) -> Json<SongInfo> {
     let current = state.receiver.borrow().clone();
     Json(current)
 }
 
+/// 所有会话及活动会话 id
+async fn get_sessions(State(state): State<Arc<AppState>>) -> Json<SessionsSnapshot> {
+    Json(state.sessions.borrow().clone())
+}
+
+async fn get_session(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> Result<Json<SongInfo>, StatusCode> {
+    state
+        .sessions
+        .borrow()
+        .get(Some(&id))
+        .cloned()
+        .map(Json)
+        .ok_or(StatusCode::NOT_FOUND)
+}
+
+/// 封面参数：`?size=256` 等比缩放到不超过 size×size（16~1024，不放大），
+/// `?format=jpeg|png|webp|auto`（auto 按 Accept 协商）；缩放但未指定格式时沿用原图格式
+#[derive(Debug, Deserialize)]
+struct CoverParams {
+    size: Option<u32>,
+    format: Option<String>,
+}
+
+/// 封面图片：id 为 album_mid 或缩略图内容摘要（见 cover_store），支持 ETag 条件请求
+async fn get_cover(
+    Path(id): Path<String>,
+    Query(params): Query<CoverParams>,
+    headers: HeaderMap,
+) -> axum::response::Response {
+    let accept = headers.get(header::ACCEPT).and_then(|v| v.to_str().ok()).unwrap_or("");
+    let negotiated = params.format.as_deref().is_some_and(|f| f.eq_ignore_ascii_case("auto"));
+    let format = match params.format.as_deref() {
+        None => None,
+        Some(_) if negotiated => Some(CoverFormat::negotiate(accept)),
+        Some(name) => match CoverFormat::parse(name) {
+            Some(format) => Some(format),
+            None => return (StatusCode::BAD_REQUEST, format!("不支持的格式: {}", name)).into_response(),
+        },
+    };
+
+    let cover = if params.size.is_none() && format.is_none() {
+        cover_store::get(&id).map(Ok)
+    } else {
+        let size = params.size;
+        let task = tokio::task::spawn_blocking(move || {
+            let source_format = cover_store::get(&id).and_then(|c| CoverFormat::from_content_type(c.content_type));
+            let format = format.or(source_format).unwrap_or(CoverFormat::Jpeg);
+            cover_store::get_variant(&id, size, format)
+        });
+        task.await.unwrap_or(None)
+    };
+    match cover {
+        Some(Ok(cover)) => cover_response(cover, &headers, negotiated),
+        Some(Err(e)) => (StatusCode::INTERNAL_SERVER_ERROR, format!("封面处理失败: {}", e)).into_response(),
+        None => StatusCode::NOT_FOUND.into_response(),
+    }
+}
+
+fn cover_response(cover: Cover, headers: &HeaderMap, negotiated: bool) -> axum::response::Response {
+    let etag = format!("\"{}\"", cover.etag);
+    let mut response_headers = HeaderMap::new();
+    if let Ok(value) = etag.parse() {
+        response_headers.insert(header::ETAG, value);
+    }
+    response_headers.insert(header::CACHE_CONTROL, header::HeaderValue::from_static("public, max-age=86400"));
+    if negotiated {
+        response_headers.insert(header::VARY, header::HeaderValue::from_static("Accept"));
+    }
+    let not_modified = headers
+        .get(header::IF_NONE_MATCH)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.split(',').any(|tag| tag.trim() == etag || tag.trim() == "*"));
+    if not_modified {
+        return (StatusCode::NOT_MODIFIED, response_headers).into_response();
+    }
+    response_headers.insert(header::CONTENT_TYPE, header::HeaderValue::from_static(cover.content_type));
+    (response_headers, cover.bytes.as_ref().clone()).into_response()
+}
+
+/// 封面主题色：`{"dominant": "#rrggbb", "accent": "#rrggbb", "colors": [...]}`
+async fn get_cover_palette(Path(id): Path<String>) -> Result<Json<Palette>, (StatusCode, String)> {
+    let task = tokio::task::spawn_blocking(move || cover_store::palette(&id));
+    match task.await.unwrap_or(None) {
+        Some(Ok(palette)) => Ok(Json(palette)),
+        Some(Err(e)) => Err((StatusCode::INTERNAL_SERVER_ERROR, format!("封面处理失败: {}", e))),
+        None => Err((StatusCode::NOT_FOUND, "封面不存在".to_string())),
+    }
+}
+
+/// 播放控制的执行结果（HTTP 响应体 / WebSocket 回执）
+#[derive(Debug, Serialize)]
+struct ControlResponse {
+    ok: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+impl ControlResponse {
+    fn from_result<T>(result: &Result<T, ControlError>) -> Self {
+        match result {
+            Ok(_) => Self { ok: true, error: None },
+            Err(e) => Self { ok: false, error: Some(e.to_string()) },
+        }
+    }
+}
+
+/// 控制结果对应的 HTTP 状态码：执行失败 409，请求无效 400，
+/// 主循环未就绪 503，等待超时 504（命令可能仍会执行）
+fn control_status<T>(result: &Result<T, ControlError>) -> StatusCode {
+    match result {
+        Ok(_) => StatusCode::OK,
+        Err(ControlError::Failed(_)) => StatusCode::CONFLICT,
+        Err(ControlError::Invalid(_)) => StatusCode::BAD_REQUEST,
+        Err(ControlError::Unavailable(_)) => StatusCode::SERVICE_UNAVAILABLE,
+        Err(ControlError::Timeout) => StatusCode::GATEWAY_TIMEOUT,
+    }
+}
+
+async fn run_control(command: PlaybackCommand) -> (StatusCode, Json<ControlResponse>) {
+    let result = control::send(command).await;
+    (control_status(&result), Json(ControlResponse::from_result(&result)))
+}
+
+/// 通用控制入口：`{"command": "play_pause" | "next" | "previous" | "seek", "position_ms"?: u64}`
+async fn post_control(Json(command): Json<PlaybackCommand>) -> (StatusCode, Json<ControlResponse>) {
+    run_control(command).await
+}
+
+async fn post_play_pause() -> (StatusCode, Json<ControlResponse>) {
+    run_control(PlaybackCommand::PlayPause).await
+}
+
+async fn post_next() -> (StatusCode, Json<ControlResponse>) {
+    run_control(PlaybackCommand::Next).await
+}
+
+async fn post_previous() -> (StatusCode, Json<ControlResponse>) {
+    run_control(PlaybackCommand::Previous).await
+}
+
+#[derive(Debug, Deserialize)]
+struct SeekRequest {
+    position_ms: u64,
+}
+
+async fn post_seek(Json(request): Json<SeekRequest>) -> (StatusCode, Json<ControlResponse>) {
+    run_control(PlaybackCommand::Seek { position_ms: request.position_ms }).await
+}
+
+/// 歌词跳转响应：`ok` 表示命令已下发，`honored` 表示跳转后的时间轴采样确认生效
+#[derive(Debug, Serialize)]
+struct SeekLyricResponse {
+    #[serde(flatten)]
+    response: ControlResponse,
+    #[serde(flatten, skip_serializing_if = "Option::is_none")]
+    result: Option<SeekResult>,
+}
+
+/// 跳转到歌词行或歌词时间戳：`{"line": n}` 或 `{"time_ms": n}`
+async fn post_seek_lyric(Json(target): Json<SeekTarget>) -> (StatusCode, Json<SeekLyricResponse>) {
+    let result = control::seek_to(target).await;
+    let status = control_status(&result);
+    let response = SeekLyricResponse {
+        response: ControlResponse::from_result(&result),
+        result: result.ok(),
+    };
+    (status, Json(response))
+}
+
+/// 当前歌曲的歌词候选响应；`candidates` 按匹配度从高到低排列
+#[derive(Debug, Serialize)]
+struct LyricCandidatesResponse {
+    #[serde(flatten)]
+    response: ControlResponse,
+    candidates: Vec<LyricCandidate>,
+}
+
+async fn get_lyric_candidates() -> (StatusCode, Json<LyricCandidatesResponse>) {
+    let result = control::lyric_candidates().await;
+    let status = control_status(&result);
+    let response = ControlResponse::from_result(&result);
+    let candidates = result.unwrap_or_default();
+    (status, Json(LyricCandidatesResponse { response, candidates }))
+}
+
+/// 为当前歌曲固定歌词：请求体为候选中的 `pin`，
+/// 如 `{"kind": "song", "provider": "qqmusic", "id": "..."}` 或 `{"kind": "file", "path": "..."}`
+async fn post_lyrics_pin(Json(pin): Json<LyricsPin>) -> (StatusCode, Json<ControlResponse>) {
+    run_pin(Some(pin)).await
+}
+
+/// 取消当前歌曲的固定，恢复自动匹配
+async fn delete_lyrics_pin() -> (StatusCode, Json<ControlResponse>) {
+    run_pin(None).await
+}
+
+async fn run_pin(pin: Option<LyricsPin>) -> (StatusCode, Json<ControlResponse>) {
+    let result = control::pin_lyrics(pin).await;
+    (control_status(&result), Json(ControlResponse::from_result(&result)))
+}
+
+/// 单曲歌词偏移的调整结果：`offset_ms` 为保存后的偏移
+#[derive(Debug, Serialize)]
+struct LyricsOffsetResponse {
+    #[serde(flatten)]
+    response: ControlResponse,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    offset_ms: Option<i64>,
+}
+
+/// 调整当前歌曲的歌词偏移：`{"offset_ms": -300}` 或 `{"delta_ms": 50}`
+async fn post_lyrics_offset(Json(change): Json<OffsetChange>) -> (StatusCode, Json<LyricsOffsetResponse>) {
+    run_lyrics_offset(change).await
+}
+
+/// 清除当前歌曲的歌词偏移
+async fn delete_lyrics_offset() -> (StatusCode, Json<LyricsOffsetResponse>) {
+    run_lyrics_offset(OffsetChange::Set { offset_ms: 0 }).await
+}
+
+async fn run_lyrics_offset(change: OffsetChange) -> (StatusCode, Json<LyricsOffsetResponse>) {
+    let result = control::lyrics_offset(change).await;
+    let status = control_status(&result);
+    let response = LyricsOffsetResponse {
+        response: ControlResponse::from_result(&result),
+        offset_ms: result.ok(),
+    };
+    (status, Json(response))
+}
+
+/// WebSocket 控制回执：带 `type` 字段，与 SongInfo 推送区分
+#[derive(Debug, Serialize)]
+struct WsControlResult {
+    r#type: &'static str,
+    #[serde(flatten)]
+    response: ControlResponse,
+}
+
+/// WebSocket 连接参数：`/ws?session=<id>` 从连接起即订阅指定会话，
+/// `protocol=2` 使用 track/tick 协议（缺省为 v1，每次推送完整 SongInfo）
+#[derive(Debug, Deserialize)]
+struct WsParams {
+    session: Option<String>,
+    protocol: Option<u32>,
+}
+
+/// 客户端消息：播放控制命令，或 `{"subscribe": "<id>" | null}` 切换订阅（null 表示跟随活动会话）
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum WsClientMessage {
+    Control(PlaybackCommand),
+    Subscribe { subscribe: Option<String> },
+}
+
+/// 订阅确认
+#[derive(Debug, Serialize)]
+struct WsSubscribed<'a> {
+    r#type: &'static str,
+    session: Option<&'a str>,
+}
+
 async fn ws_handler(
     ws: WebSocketUpgrade,
+    headers: HeaderMap,
+    Query(params): Query<WsParams>,
     State(state): State<Arc<AppState>>,
 ) -> axum::response::Response {
-    ws.on_upgrade(|socket| handle_socket(socket, state))
+    // WebSocket 不受 CORS 约束，且连接后可发送控制、跳转、固定歌词与偏移命令
+    if !origin_allowed(&headers) {
+        return StatusCode::FORBIDDEN.into_response();
+    }
+    // 高于服务端支持的版本按最高版本处理
+    let encoder = params.protocol.filter(|v| *v >= 2).map(|_| TrackTickEncoder::default());
+    ws.on_upgrade(|socket| handle_socket(socket, state, params.session, encoder))
+}
+
+async fn send_json<T: Serialize>(socket: &mut WebSocket, value: &T) -> bool {
+    match serde_json::to_string(value) {
+        // Axum 0.7 需要使用 Message::Text(String)
+        Ok(json) => socket.send(Message::Text(json)).await.is_ok(),
+        Err(_) => true,
+    }
+}
+
+/// 按连接协议推送 SongInfo：v1 发送完整数据，v2 发送 track（如有变化）与 tick
+async fn send_info(socket: &mut WebSocket, encoder: &mut Option<TrackTickEncoder>, info: &SongInfo) -> bool {
+    let Some(encoder) = encoder else {
+        return send_json(socket, info).await;
+    };
+    for message in encoder.encode(info) {
+        if socket.send(Message::Text(message)).await.is_err() {
+            return false;
+        }
+    }
+    true
+}
+
+/// 订阅指定会话时推送该会话的 SongInfo；只在该会话满足推送条件时发送
+async fn send_session(
+    socket: &mut WebSocket,
+    encoder: &mut Option<TrackTickEncoder>,
+    snapshot: &SessionsSnapshot,
+    session: &str,
+    last_sent: &mut Option<SongInfo>,
+) -> bool {
+    let Some(info) = snapshot.get(Some(session)) else {
+        return true;
+    };
+    if !crate::should_push(last_sent.as_ref(), info) {
+        return true;
+    }
+    *last_sent = Some(info.clone());
+    send_info(socket, encoder, info).await
 }
 
-async fn handle_socket(mut socket: WebSocket, state: Arc<AppState>) {
+async fn handle_socket(
+    mut socket: WebSocket,
+    state: Arc<AppState>,
+    mut session: Option<String>,
+    mut encoder: Option<TrackTickEncoder>,
+) {
     let mut rx = state.receiver.clone();
+    let mut sessions_rx = state.sessions.clone();
+    // 订阅指定会话时最近一次发送的数据
+    let mut last_sent: Option<SongInfo> = None;
+    // 控制命令在独立任务中等待主循环回复（最长 CONTROL_TIMEOUT），回执经此通道送回，不阻塞推送
+    let (results_tx, mut results_rx) = tokio::sync::mpsc::unbounded_channel::<WsControlResult>();
+
+    // v2 连接先告知协议版本
+    if encoder.is_some() && !send_json(&mut socket, &ServerMessage::Hello { version: PROTOCOL_VERSION }).await {
+        return;
+    }
 
     // 首次连接时，发送一次当前数据
-    let initial = rx.borrow().clone();
-    // axum::extract::ws::Message::Text / axum::extract::ws::Message::Text(...)
-    if let Ok(json) = serde_json::to_string(&initial) {
-        if socket.send(Message::Text(json)).await.is_err() {
-            return;
+    let sent = match &session {
+        Some(id) => {
+            let snapshot = sessions_rx.borrow().clone();
+            send_session(&mut socket, &mut encoder, &snapshot, id, &mut last_sent).await
         }
+        None => {
+            let initial = rx.borrow().clone();
+            send_info(&mut socket, &mut encoder, &initial).await
+        }
+    };
+    if !sent {
+        return;
     }
 
     loop {
         // 等待数据更新或 socket 关闭
         tokio::select! {
             result = rx.changed() => {
-                if result.is_ok() {
+                if result.is_err() {
+                    break; // sender 已经断开
+                }
+                if session.is_none() {
                     let current = rx.borrow().clone();
-                    if let Ok(json) = serde_json::to_string(&current) {
-                        // Axum 0.7 需要使用 Message::Text(String)
-                        if socket.send(Message::Text(json)).await.is_err() {
-                            break;
-                        }
+                    if !send_info(&mut socket, &mut encoder, &current).await {
+                        break;
                     }
-                } else {
-                    break; // sender 已经断开
                 }
             }
-            msg = socket.recv() => {
-                // 如果客户端发送消息或断开连接
-                if let Some(Ok(Message::Close(_))) | None = msg {
+            result = sessions_rx.changed() => {
+                if result.is_err() {
+                    break;
+                }
+                if let Some(id) = &session {
+                    let snapshot = sessions_rx.borrow().clone();
+                    if !send_session(&mut socket, &mut encoder, &snapshot, id, &mut last_sent).await {
+                        break;
+                    }
+                }
+            }
+            Some(reply) = results_rx.recv() => {
+                if !send_json(&mut socket, &reply).await {
                     break;
                 }
             }
+            msg = socket.recv() => {
+                match msg {
+                    Some(Ok(Message::Text(text))) => {
+                        let sent = match serde_json::from_str::<WsClientMessage>(&text) {
+                            // 客户端发送的控制命令，如 {"command":"next"}
+                            Ok(WsClientMessage::Control(command)) => {
+                                let results_tx = results_tx.clone();
+                                tokio::spawn(async move {
+                                    let result = control::send(command).await;
+                                    let _ = results_tx.send(WsControlResult {
+                                        r#type: "control_result",
+                                        response: ControlResponse::from_result(&result),
+                                    });
+                                });
+                                true
+                            }
+                            // 切换订阅后立即推送一次所订阅会话的当前数据
+                            Ok(WsClientMessage::Subscribe { subscribe }) => {
+                                session = subscribe;
+                                last_sent = None;
+                                if let Some(encoder) = encoder.as_mut() {
+                                    encoder.reset();
+                                }
+                                let ack = WsSubscribed { r#type: "subscribed", session: session.as_deref() };
+                                send_json(&mut socket, &ack).await && match &session {
+                                    Some(id) => {
+                                        let snapshot = sessions_rx.borrow().clone();
+                                        send_session(&mut socket, &mut encoder, &snapshot, id, &mut last_sent).await
+                                    }
+                                    None => {
+                                        let current = rx.borrow().clone();
+                                        send_info(&mut socket, &mut encoder, &current).await
+                                    }
+                                }
+                            }
+                            Err(e) => {
+                                let reply = WsControlResult {
+                                    r#type: "control_result",
+                                    response: ControlResponse::from_result::<()>(&Err(ControlError::Invalid(format!("无法解析消息: {}", e)))),
+                                };
+                                send_json(&mut socket, &reply).await
+                            }
+                        };
+                        if !sent {
+                            break;
+                        }
+                    }
+                    // 如果客户端断开连接
+                    Some(Ok(Message::Close(_))) | None => break,
+                    _ => {}
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_local_origin() {
+        for origin in ["http://127.0.0.1:1420", "http://localhost", "http://[::1]:3000", "http://tauri.localhost", "tauri://localhost"] {
+            assert!(is_local_origin(&HeaderValue::from_static(origin)), "{}", origin);
         }
+        for origin in ["https://example.com", "http://127.0.0.1.example.com", "http://localhost.evil", "null", "file://"] {
+            assert!(!is_local_origin(&HeaderValue::from_static(origin)), "{}", origin);
+        }
+    }
+
+    /// 其他网页发来的写请求与 WebSocket 握手在执行前被拒绝；本机页面与非浏览器客户端照常处理
+    #[tokio::test]
+    async fn test_foreign_origin_rejected() {
+        let (_tx, receiver) = watch::channel(SongInfo::default());
+        let (_sessions_tx, sessions) = watch::channel(SessionsSnapshot::default());
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let app = router(Arc::new(AppState { receiver, sessions }));
+        tokio::spawn(async move { axum::serve(listener, app).await });
+
+        let client = reqwest::Client::new();
+        let post = |path: &str, origin: Option<&'static str>| {
+            let request = client.post(format!("http://{}{}", addr, path));
+            match origin {
+                Some(origin) => request.header(header::ORIGIN, origin),
+                None => request,
+            }
+        };
+        for path in ["/api/control/play_pause", "/api/control/next", "/api/control/previous"] {
+            let response = post(path, Some("https://evil.example")).send().await.unwrap();
+            assert_eq!(response.status(), StatusCode::FORBIDDEN, "{}", path);
+        }
+        let response = post("/api/lyrics/offset", Some("https://evil.example"))
+            .json(&serde_json::json!({ "delta_ms": 50 }))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        for origin in [Some("http://127.0.0.1:1420"), None] {
+            let response = post("/api/control/next", origin).send().await.unwrap();
+            assert_ne!(response.status(), StatusCode::FORBIDDEN, "{:?}", origin);
+        }
+
+        let handshake = |origin: &'static str| {
+            client
+                .get(format!("http://{}/ws", addr))
+                .header(header::CONNECTION, "Upgrade")
+                .header(header::UPGRADE, "websocket")
+                .header(header::SEC_WEBSOCKET_VERSION, "13")
+                .header(header::SEC_WEBSOCKET_KEY, "dGhlIHNhbXBsZSBub25jZQ==")
+                .header(header::ORIGIN, origin)
+                .send()
+        };
+        assert_eq!(handshake("https://evil.example").await.unwrap().status(), StatusCode::FORBIDDEN);
+        assert_eq!(handshake("http://localhost:3000").await.unwrap().status(), StatusCode::SWITCHING_PROTOCOLS);
     }
 }
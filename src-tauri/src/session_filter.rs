@@ -0,0 +1,188 @@
+use serde::{Deserialize, Serialize};
+
+/// 会话回退策略
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SessionPolicy {
+    /// 只接受允许列表中的会话，没有时视为无播放
+    QqmusicOnly,
+    /// 优先允许列表中的会话，没有时回退到系统当前会话
+    #[default]
+    PreferQqmusic,
+    /// 跟随系统当前会话
+    Any,
+}
+
+/// 媒体会话过滤配置（`config.toml` 的 `[sessions]` 段）。
+///
+/// `allow` / `deny` 中的每一项按不区分大小写的子串匹配会话标识：
+/// SMTC 为 SourceAppUserModelId，MPRIS 为总线名及根接口的 DesktopEntry/Identity。
+/// `allow` 的顺序即优先级；命中 `deny` 的会话在任何策略下都不会被选中。
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct SessionFilter {
+    #[serde(default)]
+    pub policy: SessionPolicy,
+    #[serde(default = "default_allow")]
+    pub allow: Vec<String>,
+    #[serde(default)]
+    pub deny: Vec<String>,
+}
+
+fn default_allow() -> Vec<String> {
+    vec!["qqmusic".to_string(), "xuanwo".to_string(), "qq音乐".to_string()]
+}
+
+impl Default for SessionFilter {
+    fn default() -> Self {
+        Self {
+            policy: SessionPolicy::default(),
+            allow: default_allow(),
+            deny: Vec::new(),
+        }
+    }
+}
+
+fn matches(label: &str, pattern: &str) -> bool {
+    !pattern.is_empty() && label.to_lowercase().contains(&pattern.to_lowercase())
+}
+
+impl SessionFilter {
+    pub fn is_denied(&self, label: &str) -> bool {
+        self.deny.iter().any(|p| matches(label, p))
+    }
+
+    /// 在允许列表中的优先级（越小越优先），未命中或被拒绝时为 None
+    pub fn allow_rank(&self, label: &str) -> Option<usize> {
+        if self.is_denied(label) {
+            return None;
+        }
+        self.allow.iter().position(|p| matches(label, p))
+    }
+
+    /// 按策略给出依次尝试读取的会话下标。
+    ///
+    /// `labels` 为各会话的标识，`current` 为系统当前会话的下标（MPRIS 没有此概念，传 None）。
+    /// 存在允许列表中的会话时只返回它们（按优先级），读取失败也不降级到其他音源。
+    pub fn candidates(&self, labels: &[String], current: Option<usize>) -> Vec<usize> {
+        let mut allowed: Vec<(usize, usize)> = labels
+            .iter()
+            .enumerate()
+            .filter_map(|(idx, label)| self.allow_rank(label).map(|rank| (rank, idx)))
+            .collect();
+        allowed.sort();
+
+        if self.policy != SessionPolicy::Any && !allowed.is_empty() {
+            return allowed.into_iter().map(|(_, idx)| idx).collect();
+        }
+        if self.policy == SessionPolicy::QqmusicOnly {
+            return Vec::new();
+        }
+
+        let usable = |idx: &usize| !self.is_denied(&labels[*idx]);
+        let current = current.filter(|idx| *idx < labels.len()).filter(usable);
+        match (self.policy, current) {
+            // 与旧行为一致：有系统当前会话时只回退到它
+            (SessionPolicy::PreferQqmusic, Some(idx)) => vec![idx],
+            _ => current
+                .into_iter()
+                .chain(allowed.into_iter().map(|(_, idx)| idx))
+                .chain((0..labels.len()).filter(usable))
+                .fold(Vec::new(), |mut order, idx| {
+                    if !order.contains(&idx) {
+                        order.push(idx);
+                    }
+                    order
+                }),
+        }
+    }
+
+    /// 把上次选中的会话提到同一优先级的最前：只用于在并列的会话之间保持稳定，
+    /// 不会压过优先级更高的会话（例如允许列表中排名靠前的播放器后启动时立即切换过去）
+    pub fn prefer_previous(&self, labels: &[String], order: &mut Vec<usize>, previous: Option<usize>) {
+        let Some(pos) = previous.and_then(|prev| order.iter().position(|&idx| idx == prev)) else {
+            return;
+        };
+        let rank = |idx: usize| self.allow_rank(&labels[idx]);
+        if let Some(first) = order.iter().position(|&idx| rank(idx) == rank(order[pos])) {
+            let idx = order.remove(pos);
+            order.insert(first, idx);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn labels(ids: &[&str]) -> Vec<String> {
+        ids.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_allow_list_priority_and_deny() {
+        let filter = SessionFilter {
+            allow: vec!["spotify".to_string(), "QQMusic".to_string()],
+            deny: vec!["msedge".to_string()],
+            ..SessionFilter::default()
+        };
+        let ids = labels(&["QQMusic.exe", "Spotify.exe", "MSEdge"]);
+        assert_eq!(filter.candidates(&ids, Some(2)), vec![1, 0]);
+        assert!(filter.is_denied("msedge"));
+        assert_eq!(filter.allow_rank("MSEdge"), None);
+    }
+
+    /// 没有允许列表中的会话时，按策略决定是否回退（被拒绝的当前会话不会被选中）
+    #[test]
+    fn test_policy_fallback() {
+        let ids = labels(&["Spotify.exe", "Chrome"]);
+        let mut filter = SessionFilter::default();
+        assert_eq!(filter.candidates(&ids, Some(1)), vec![1]);
+        assert_eq!(filter.candidates(&ids, None), vec![0, 1]);
+
+        filter.policy = SessionPolicy::QqmusicOnly;
+        assert!(filter.candidates(&ids, Some(1)).is_empty());
+
+        filter.policy = SessionPolicy::PreferQqmusic;
+        filter.deny = vec!["chrome".to_string()];
+        assert_eq!(filter.candidates(&ids, Some(1)), vec![0]);
+
+        // any：当前会话优先，即使存在允许列表中的会话
+        let ids = labels(&["QQMusic.exe", "Spotify.exe"]);
+        filter.policy = SessionPolicy::Any;
+        assert_eq!(filter.candidates(&ids, Some(1)), vec![1, 0]);
+    }
+
+    /// 上次选中的会话只在并列时优先
+    #[test]
+    fn test_prefer_previous() {
+        let filter = SessionFilter::default();
+        let ids = labels(&["vlc", "firefox", "qqmusic"]);
+        let mut order = filter.candidates(&ids, None);
+        assert_eq!(order, vec![2]);
+        filter.prefer_previous(&ids, &mut order, Some(1));
+        assert_eq!(order, vec![2]);
+
+        // 都不在允许列表中：保持上次的选择
+        let ids = labels(&["vlc", "firefox"]);
+        let mut order = filter.candidates(&ids, None);
+        filter.prefer_previous(&ids, &mut order, Some(1));
+        assert_eq!(order, vec![1, 0]);
+
+        // 允许列表中排名更高的播放器出现后立即切换
+        let filter = SessionFilter { allow: vec!["spotify".to_string(), "qqmusic".to_string()], ..SessionFilter::default() };
+        let ids = labels(&["qqmusic", "spotify", "qqmusic-2"]);
+        let mut order = filter.candidates(&ids, None);
+        filter.prefer_previous(&ids, &mut order, Some(0));
+        assert_eq!(order, vec![1, 0, 2]);
+        filter.prefer_previous(&ids, &mut order, Some(2));
+        assert_eq!(order, vec![1, 2, 0]);
+    }
+
+    #[test]
+    fn test_parse_section() {
+        let filter: SessionFilter = toml::from_str("policy = \"qqmusic_only\"\ndeny = [\"chrome\"]").unwrap();
+        assert_eq!(filter.policy, SessionPolicy::QqmusicOnly);
+        assert_eq!(filter.allow, default_allow());
+        assert_eq!(filter.deny, vec!["chrome".to_string()]);
+    }
+}
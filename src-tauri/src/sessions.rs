@@ -0,0 +1,186 @@
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::song_info::SongInfo;
+
+/// 没有会话标识的源（回放等单会话源）使用的 id
+const DEFAULT_SESSION_ID: &str = "default";
+
+/// 多会话快照：会话 id → SongInfo，以及过滤策略选出的活动会话。
+///
+/// 会话 id 为 `source_app`（SMTC: AppUserModelId，MPRIS: 总线名），
+/// 同一应用有多个会话时追加 `#2`、`#3`。编号沿用上一次快照（见 `assign_ids`），
+/// 同一应用的其他会话出现或关闭时，已订阅的会话 id 不变。
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct SessionsSnapshot {
+    pub active: Option<String>,
+    pub sessions: BTreeMap<String, SongInfo>,
+}
+
+impl SessionsSnapshot {
+    /// 由媒体源的多会话读取结果与活动会话组装快照，`previous` 为上一次的快照。
+    ///
+    /// 活动会话（已附带歌词）替换同一应用下标题相同的会话；
+    /// 源不支持多会话（`sessions` 为空）或未列出活动会话时单独加入。
+    pub fn build(sessions: Vec<SongInfo>, active: Option<SongInfo>, previous: &SessionsSnapshot) -> Self {
+        let mut active = active;
+        let mut entries: Vec<(SongInfo, bool)> = Vec::with_capacity(sessions.len() + 1);
+        for info in sessions {
+            let is_active = active
+                .as_ref()
+                .is_some_and(|a| a.source_app == info.source_app && a.title == info.title);
+            if is_active {
+                entries.push((active.take().unwrap_or(info), true));
+            } else {
+                entries.push((info, false));
+            }
+        }
+        if let Some(info) = active {
+            entries.push((info, true));
+        }
+
+        let ids = assign_ids(entries.iter().map(|(info, _)| info), previous);
+        let mut snapshot = Self::default();
+        for (id, (info, is_active)) in ids.into_iter().zip(entries) {
+            if is_active {
+                snapshot.active = Some(id.clone());
+            }
+            snapshot.sessions.insert(id, info);
+        }
+        snapshot
+    }
+
+    /// 订阅的会话：`None` 表示跟随活动会话
+    pub fn get(&self, id: Option<&str>) -> Option<&SongInfo> {
+        let id = id.or(self.active.as_deref())?;
+        self.sessions.get(id)
+    }
+}
+
+fn base_id(source_app: &str) -> &str {
+    if source_app.is_empty() { DEFAULT_SESSION_ID } else { source_app }
+}
+
+/// 为会话分配 id，尽量沿用上一次快照：
+/// 1. 同一应用下标题、歌手都相同的会话沿用原 id；
+/// 2. 其余会话（切歌、换视频）按顺序沿用同一应用中剩下的 id；
+/// 3. 新会话取同一应用中未占用的最小编号（`app`、`app#2`…）。
+fn assign_ids<'a>(infos: impl Iterator<Item = &'a SongInfo>, previous: &SessionsSnapshot) -> Vec<String> {
+    let infos: Vec<&SongInfo> = infos.collect();
+    let mut ids: Vec<Option<String>> = vec![None; infos.len()];
+    let mut used: BTreeSet<String> = BTreeSet::new();
+
+    let same_app = |prev: &SongInfo, info: &SongInfo| base_id(&prev.source_app) == base_id(&info.source_app);
+    for (slot, info) in ids.iter_mut().zip(&infos) {
+        let found = previous.sessions.iter().find(|(id, prev)| {
+            !used.contains(*id) && same_app(prev, info) && prev.title == info.title && prev.artist == info.artist
+        });
+        if let Some((id, _)) = found {
+            used.insert(id.clone());
+            *slot = Some(id.clone());
+        }
+    }
+    for (slot, info) in ids.iter_mut().zip(&infos).filter(|(slot, _)| slot.is_none()) {
+        let found = previous.sessions.iter().find(|(id, prev)| !used.contains(*id) && same_app(prev, info));
+        if let Some((id, _)) = found {
+            used.insert(id.clone());
+            *slot = Some(id.clone());
+        }
+    }
+    ids.into_iter()
+        .zip(&infos)
+        .map(|(slot, info)| {
+            slot.unwrap_or_else(|| {
+                let base = base_id(&info.source_app);
+                let id = (1..)
+                    .map(|n| if n == 1 { base.to_string() } else { format!("{}#{}", base, n) })
+                    .find(|id| !used.contains(id))
+                    .unwrap_or_default();
+                used.insert(id.clone());
+                id
+            })
+        })
+        .collect()
+}
+
+/// 是否需要广播新的快照：会话增减、活动会话切换，或任一会话满足单会话的推送条件
+pub fn should_push_snapshot(last: &SessionsSnapshot, current: &SessionsSnapshot) -> bool {
+    last.active != current.active
+        || last.sessions.len() != current.sessions.len()
+        || current
+            .sessions
+            .iter()
+            .any(|(id, info)| crate::should_push(last.sessions.get(id), info))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn info(source_app: &str, title: &str) -> SongInfo {
+        SongInfo {
+            source_app: source_app.to_string(),
+            title: title.to_string(),
+            ..SongInfo::default()
+        }
+    }
+
+    /// 活动会话带歌词替换原条目；同应用的新会话按出现顺序编号
+    #[test]
+    fn test_build_snapshot() {
+        let active = SongInfo { lyrics: "[00:01.00]那时雨".to_string(), ..info("QQMusic.exe", "那时雨") };
+        let snapshot = SessionsSnapshot::build(
+            vec![info("Chrome", "视频 A"), info("QQMusic.exe", "那时雨"), info("Chrome", "视频 B")],
+            Some(active.clone()),
+            &SessionsSnapshot::default(),
+        );
+        assert_eq!(snapshot.active.as_deref(), Some("QQMusic.exe"));
+        assert_eq!(snapshot.get(None), Some(&active));
+        assert_eq!(snapshot.get(Some("Chrome#2")).map(|i| i.title.as_str()), Some("视频 B"));
+        assert_eq!(snapshot.sessions.len(), 3);
+    }
+
+    /// 单会话源：活动会话单独加入，无 source_app 时使用 default
+    #[test]
+    fn test_build_single_session_source() {
+        let snapshot = SessionsSnapshot::build(Vec::new(), Some(info("", "那时雨")), &SessionsSnapshot::default());
+        assert_eq!(snapshot.active.as_deref(), Some("default"));
+        assert!(SessionsSnapshot::build(Vec::new(), None, &SessionsSnapshot::default()).get(None).is_none());
+    }
+
+    /// 同应用的其他会话关闭、出现或切换内容时，已有会话的 id 不变
+    #[test]
+    fn test_session_ids_are_stable() {
+        let none = SessionsSnapshot::default();
+        let first = SessionsSnapshot::build(vec![info("Chrome", "视频 A"), info("Chrome", "视频 B")], None, &none);
+        assert_eq!(first.get(Some("Chrome#2")).map(|i| i.title.as_str()), Some("视频 B"));
+
+        // 关闭第一个标签页：视频 B 仍是 Chrome#2
+        let closed = SessionsSnapshot::build(vec![info("Chrome", "视频 B")], None, &first);
+        assert_eq!(closed.sessions.keys().collect::<Vec<_>>(), ["Chrome#2"]);
+
+        // 新标签页取最小的空闲编号；换视频的会话沿用原 id
+        let reopened = SessionsSnapshot::build(vec![info("Chrome", "视频 C"), info("Chrome", "视频 B")], None, &closed);
+        assert_eq!(reopened.get(Some("Chrome")).map(|i| i.title.as_str()), Some("视频 C"));
+        assert_eq!(reopened.get(Some("Chrome#2")).map(|i| i.title.as_str()), Some("视频 B"));
+        let next = SessionsSnapshot::build(vec![info("Chrome", "视频 C"), info("Chrome", "视频 D")], None, &reopened);
+        assert_eq!(next.get(Some("Chrome#2")).map(|i| i.title.as_str()), Some("视频 D"));
+    }
+
+    #[test]
+    fn test_should_push_snapshot() {
+        let none = SessionsSnapshot::default();
+        let first = SessionsSnapshot::build(vec![info("Spotify.exe", "A")], Some(info("QQMusic.exe", "B")), &none);
+        assert!(!should_push_snapshot(&first, &first.clone()));
+
+        let paused = SessionsSnapshot::build(
+            vec![SongInfo { is_playing: true, ..info("Spotify.exe", "A") }],
+            Some(info("QQMusic.exe", "B")),
+            &first,
+        );
+        assert!(should_push_snapshot(&first, &paused));
+
+        let switched = SessionsSnapshot::build(vec![info("Spotify.exe", "A")], Some(info("Spotify.exe", "A")), &first);
+        assert!(should_push_snapshot(&first, &switched));
+    }
+}
@@ -1,36 +1,265 @@
 use windows::Media::Control::GlobalSystemMediaTransportControlsSessionManager;
 use windows::Media::Control::GlobalSystemMediaTransportControlsSession;
+use windows::Media::Control::GlobalSystemMediaTransportControlsSessionMediaProperties;
 use windows::Media::Control::GlobalSystemMediaTransportControlsSessionPlaybackStatus;
+use windows::Media::MediaPlaybackAutoRepeatMode;
 use windows::Storage::Streams::{DataReader, IRandomAccessStreamReference};
-use base64::{Engine as _, engine::general_purpose::STANDARD};
-use crate::song_info::SongInfo;
+use crate::capture::{self, CaptureEntry};
+use windows::Foundation::TypedEventHandler;
+use crate::control::PlaybackCommand;
+use crate::cover_store;
+use crate::media_source::{advance_song_info, song_info_from_sample, LocalBoxFuture, MediaSample, MediaSource};
+use crate::session_filter::SessionFilter;
+use crate::song_info::{RepeatMode, SongInfo};
 use anyhow::{Result, Context};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::OnceLock;
-use tokio::sync::Mutex;
+use std::time::{Duration, Instant};
+use tokio::sync::{Mutex, Notify};
 
 struct SmtcReaderInner {
     manager: GlobalSystemMediaTransportControlsSessionManager,
     current_session: Option<GlobalSystemMediaTransportControlsSession>,
+    /// "AppUserModelId|title|artist"，相同则复用已加载的封面
     last_song_key: Option<String>,
-    last_thumbnail_base64: String,
+    /// 当前歌曲封面在封面存储中的地址
+    last_thumbnail_url: String,
+    /// 当前会话是过滤顺序中的首选；只有首选会话可以走快速路径，
+    /// 否则更优先的会话恢复可读时要靠完整扫描才能切换回去
+    current_is_first_choice: bool,
+    /// 订阅了变化事件的会话（所有会话，多会话监控需要感知非活动会话的变化）
+    watched_sessions: Vec<WatchedSession>,
+}
+
+/// 会话级事件订阅（MediaPropertiesChanged / TimelinePropertiesChanged / PlaybackInfoChanged）
+struct WatchedSession {
+    session: GlobalSystemMediaTransportControlsSession,
+    media_token: i64,
+    timeline_token: i64,
+    playback_token: i64,
+}
+
+/// SMTC 变化通知：事件回调运行在 WinRT 线程池上，只置脏标记并唤醒主循环，
+/// 真正的读取仍在 LocalSet 上进行。
+struct SmtcEvents {
+    dirty: AtomicBool,
+    /// 会话增减或系统当前会话切换，下一次读取必须完整扫描
+    sessions_changed: AtomicBool,
+    notify: Notify,
 }
 
 static SMTC_READER: OnceLock<Mutex<SmtcReaderInner>> = OnceLock::new();
+static SMTC_EVENTS: OnceLock<SmtcEvents> = OnceLock::new();
+
+// Offset between Windows FILETIME epoch and Unix epoch in milliseconds
+const WINDOWS_EPOCH_OFFSET_MS: i64 = 11_644_473_600_000;
+
+// Full re-read interval even without events, guarding against missed notifications
+const RESYNC_INTERVAL: Duration = Duration::from_secs(5);
+
+fn smtc_events() -> &'static SmtcEvents {
+    SMTC_EVENTS.get_or_init(|| SmtcEvents {
+        dirty: AtomicBool::new(true),
+        sessions_changed: AtomicBool::new(true),
+        notify: Notify::new(),
+    })
+}
+
+fn mark_dirty() {
+    let events = smtc_events();
+    events.dirty.store(true, Ordering::SeqCst);
+    events.notify.notify_one();
+}
+
+fn mark_sessions_changed() {
+    smtc_events().sessions_changed.store(true, Ordering::SeqCst);
+    mark_dirty();
+}
+
+/// Windows SMTC media source (sessions picked by the `[sessions]` allow/deny lists and policy).
+///
+/// 订阅 SMTC 变化事件：事件之间不访问 SMTC，直接从上一次读取结果外推进度；
+/// 收到事件或到达重同步间隔时才重新完整读取。
+pub struct SmtcSource {
+    /// 上一次完整读取的结果（`Some(None)` 表示当时没有播放会话）
+    cached: Option<Option<SongInfo>>,
+    /// 上一次完整读取时所有会话的结果（多会话监控）
+    cached_sessions: Vec<SongInfo>,
+    last_sync: Option<Instant>,
+    filter: SessionFilter,
+    /// 主循环是否读取多会话快照（启用同步服务时）；否则每次只读取活动会话
+    sessions_wanted: bool,
+}
+
+impl SmtcSource {
+    pub fn new(filter: SessionFilter) -> Self {
+        Self { cached: None, cached_sessions: Vec::new(), last_sync: None, filter, sessions_wanted: false }
+    }
+}
+
+/// 一次完整读取的结果：过滤后选中的活动会话 + 所有会话（未请求多会话时为空）
+pub struct SmtcSnapshot {
+    pub active: Option<SongInfo>,
+    pub sessions: Vec<SongInfo>,
+}
+
+/// 单个会话的一次读取；媒体属性留作切歌时加载封面，录制条目只在选为活动会话时写入
+#[derive(Clone)]
+struct SessionRead {
+    info: SongInfo,
+    media_properties: GlobalSystemMediaTransportControlsSessionMediaProperties,
+    capture: Option<CaptureEntry>,
+}
+
+impl SessionRead {
+    fn record(&self) {
+        if let Some(entry) = &self.capture {
+            capture::record(entry);
+        }
+    }
+}
+
+impl MediaSource for SmtcSource {
+    fn name(&self) -> &'static str {
+        "SMTC"
+    }
+
+    fn read(&mut self) -> LocalBoxFuture<'_, Result<Option<SongInfo>>> {
+        Box::pin(async move {
+            // 先取走脏标记：读取期间到达的事件会重新置位，下一轮再读
+            let dirty = smtc_events().dirty.swap(false, Ordering::SeqCst);
+            let expired = match self.last_sync {
+                Some(t) => t.elapsed() >= RESYNC_INTERVAL,
+                None => true,
+            };
+            if !dirty && !expired {
+                if let Some(cached) = &self.cached {
+                    let now_ms = chrono::Utc::now().timestamp_millis();
+                    return Ok(cached.as_ref().map(|info| advance_song_info(info, now_ms)));
+                }
+            }
+
+            match get_current_media_info(&self.filter, self.sessions_wanted).await {
+                Ok(snapshot) => {
+                    self.cached = Some(snapshot.active.clone());
+                    self.cached_sessions = snapshot.sessions;
+                    self.last_sync = Some(Instant::now());
+                    Ok(snapshot.active)
+                }
+                Err(e) => {
+                    self.cached = None;
+                    self.cached_sessions.clear();
+                    Err(e)
+                }
+            }
+        })
+    }
+
+    fn read_sessions(&mut self) -> LocalBoxFuture<'_, Result<Vec<SongInfo>>> {
+        Box::pin(async move {
+            // 与 read 共用一次完整读取，这里只外推进度；首次调用时让下一轮读取所有会话
+            if !self.sessions_wanted {
+                self.sessions_wanted = true;
+                mark_sessions_changed();
+            }
+            let now_ms = chrono::Utc::now().timestamp_millis();
+            Ok(self.cached_sessions.iter().map(|info| advance_song_info(info, now_ms)).collect())
+        })
+    }
+
+    fn control(&mut self, command: PlaybackCommand) -> LocalBoxFuture<'_, Result<()>> {
+        Box::pin(control_current_session(command))
+    }
+
+    fn is_event_driven(&self) -> bool {
+        true
+    }
+
+    fn wait_for_change(&mut self, timeout: Duration) -> LocalBoxFuture<'_, bool> {
+        Box::pin(async move {
+            let events = smtc_events();
+            if events.dirty.load(Ordering::SeqCst) {
+                return true;
+            }
+            tokio::time::timeout(timeout, events.notify.notified()).await.is_ok()
+        })
+    }
+}
+
+// Function to send a transport control command to the session we currently read from.
+async fn control_current_session(command: PlaybackCommand) -> Result<()> {
+    let session = match SMTC_READER.get() {
+        Some(reader_mutex) => reader_mutex.lock().await.current_session.clone(),
+        None => None,
+    };
+    let session = session.context("没有可控制的 SMTC 会话")?;
+
+    let accepted = match command {
+        PlaybackCommand::PlayPause => session.TryTogglePlayPauseAsync()?.await?,
+        PlaybackCommand::Next => session.TrySkipNextAsync()?.await?,
+        PlaybackCommand::Previous => session.TrySkipPreviousAsync()?.await?,
+        // SMTC position is in 100-ns ticks
+        PlaybackCommand::Seek { position_ms } => {
+            session.TryChangePlaybackPositionAsync(position_ms as i64 * 10_000)?.await?
+        }
+    };
+    if !accepted {
+        anyhow::bail!("播放器拒绝了控制命令: {:?}", command);
+    }
+
+    // 不等事件回调，下一轮立即重新读取
+    mark_dirty();
+    Ok(())
+}
 
-// Function to check if the session is from QQ Music.
-fn is_qqmusic_session(session: &GlobalSystemMediaTransportControlsSession) -> bool {
-    if let Ok(id) = session.SourceAppUserModelId() {
-        let app_id = id.to_string().to_lowercase();
-        app_id.contains("qqmusic")
-            || app_id.contains("xuanwo")
-            || app_id == "qqmusic"
-    } else {
-        false
+// Subscribe to change events of every listed session, dropping subscriptions of sessions that went away.
+fn watch_sessions(reader: &mut SmtcReaderInner, sessions: &[GlobalSystemMediaTransportControlsSession]) {
+    let (kept, gone): (Vec<_>, Vec<_>) = std::mem::take(&mut reader.watched_sessions)
+        .into_iter()
+        .partition(|w| sessions.contains(&w.session));
+    for old in gone {
+        let _ = old.session.RemoveMediaPropertiesChanged(old.media_token);
+        let _ = old.session.RemoveTimelinePropertiesChanged(old.timeline_token);
+        let _ = old.session.RemovePlaybackInfoChanged(old.playback_token);
+    }
+    reader.watched_sessions = kept;
+
+    for session in sessions {
+        if reader.watched_sessions.iter().any(|w| w.session == *session) {
+            continue;
+        }
+        let subscribe = || -> windows::core::Result<WatchedSession> {
+            Ok(WatchedSession {
+                session: session.clone(),
+                media_token: session.MediaPropertiesChanged(&TypedEventHandler::new(|_, _| {
+                    mark_dirty();
+                    Ok(())
+                }))?,
+                timeline_token: session.TimelinePropertiesChanged(&TypedEventHandler::new(|_, _| {
+                    mark_dirty();
+                    Ok(())
+                }))?,
+                playback_token: session.PlaybackInfoChanged(&TypedEventHandler::new(|_, _| {
+                    mark_dirty();
+                    Ok(())
+                }))?,
+            })
+        };
+        match subscribe() {
+            Ok(watched) => reader.watched_sessions.push(watched),
+            Err(e) => eprintln!("[SMTC] Failed to subscribe session events: {:?}", e),
+        }
     }
 }
 
-// Helper to read thumbnail from IRandomAccessStreamReference and convert to base64 data URL
-async fn read_thumbnail_base64(thumbnail_ref: IRandomAccessStreamReference) -> Result<String> {
+// Function to get the AppUserModelId the session filter matches against.
+fn session_app_id(session: &GlobalSystemMediaTransportControlsSession) -> String {
+    session.SourceAppUserModelId().map(|id| id.to_string()).unwrap_or_default()
+}
+
+// Helper to read thumbnail from IRandomAccessStreamReference into the cover store.
+// The thumbnail has no stable id, so it is keyed by content hash.
+async fn read_thumbnail(thumbnail_ref: IRandomAccessStreamReference) -> Result<String> {
     let stream = thumbnail_ref.OpenReadAsync()?.await?;
     let content_type = stream.ContentType()?.to_string();
     let size = stream.Size()? as u32;
@@ -41,14 +270,16 @@ async fn read_thumbnail_base64(thumbnail_ref: IRandomAccessStreamReference) -> R
     reader.LoadAsync(size)?.await?;
     let mut buffer = vec![0u8; size as usize];
     reader.ReadBytes(&mut buffer)?;
-    let base64_str = STANDARD.encode(&buffer);
-    Ok(format!("data:{};base64,{}", content_type, base64_str))
+    Ok(cover_store::publish(None, buffer, Some(&content_type)))
 }
 
 // Function to read media information from a session.
 // Uses LastUpdatedTime to compute drift-corrected playback position,
-// matching the same algorithm Windows uses for its volume flyout progress bar.
-async fn read_session_info(session: &GlobalSystemMediaTransportControlsSession, fetch_thumbnail: bool) -> Result<SongInfo> {
+// matching the same algorithm Windows uses for its volume flyout progress bar
+// (see media_source::song_info_from_sample).
+// The capture entry is built here but only the active session's entry is written,
+// so replays stay single-session.
+async fn read_session_info(session: &GlobalSystemMediaTransportControlsSession) -> Result<SessionRead> {
     let media_properties = session.TryGetMediaPropertiesAsync()?.await.context("Failed to get media properties")?;
     let timeline_properties = session.GetTimelineProperties()?;
 
@@ -56,24 +287,8 @@ async fn read_session_info(session: &GlobalSystemMediaTransportControlsSession,
     let artist = media_properties.Artist()?.to_string();
     let album = media_properties.AlbumTitle()?.to_string();
 
-    let mut album_pic_url = String::new();
-    if fetch_thumbnail {
-        if let Ok(thumbnail_ref) = media_properties.Thumbnail() {
-            tokio::task::spawn_local(async move {
-                match read_thumbnail_base64(thumbnail_ref).await {
-                    Ok(base64_img) => {
-                        if let Some(reader_mutex) = SMTC_READER.get() {
-                            let mut r = reader_mutex.lock().await;
-                            r.last_thumbnail_base64 = base64_img;
-                        }
-                    }
-                    Err(e) => {
-                        eprintln!("[SMTC] Failed to read thumbnail: {:?}", e);
-                    }
-                }
-            });
-        }
-    }
+    // The cover is loaded separately (see load_thumbnail), only when the active song changes
+    let album_pic_url = String::new();
 
     if title.is_empty() {
         anyhow::bail!("empty title");
@@ -82,88 +297,127 @@ async fn read_session_info(session: &GlobalSystemMediaTransportControlsSession,
     let position = timeline_properties.Position()?;
     let end_time = timeline_properties.EndTime()?;
 
-    let raw_current_ms = (position.Duration / 10_000) as u64;
-    let total_time_ms = (end_time.Duration / 10_000) as u64;
-    let total_seconds = (end_time.Duration as f64) / 10_000_000.0;
+    let playback_info = session.GetPlaybackInfo().ok();
+    let playback_status = playback_info.as_ref().and_then(|info| info.PlaybackStatus().ok());
+    let is_playing = playback_status == Some(GlobalSystemMediaTransportControlsSessionPlaybackStatus::Playing);
+    // Optional fields are IReference<T>; players that don't report them return null
+    let playback_rate = playback_info
+        .as_ref()
+        .and_then(|info| info.PlaybackRate().ok())
+        .and_then(|rate| rate.Value().ok());
+    let shuffle = playback_info
+        .as_ref()
+        .and_then(|info| info.IsShuffleActive().ok())
+        .and_then(|shuffle| shuffle.Value().ok());
+    let repeat_mode = playback_info
+        .as_ref()
+        .and_then(|info| info.AutoRepeatMode().ok())
+        .and_then(|mode| mode.Value().ok())
+        .and_then(repeat_mode_from_smtc);
 
-    let is_playing = if let Ok(playback_info) = session.GetPlaybackInfo() {
-        matches!(playback_info.PlaybackStatus(), Ok(GlobalSystemMediaTransportControlsSessionPlaybackStatus::Playing))
-    } else {
-        false
+    // SMTC.Position is a snapshot captured at SMTC.LastUpdatedTime (Windows FILETIME,
+    // 100-ns intervals since 1601-01-01). Convert it to Unix ms so the shared
+    // drift correction in media_source can extrapolate the real position.
+    let last_updated_ms = timeline_properties
+        .LastUpdatedTime()
+        .ok()
+        .map(|t| t.UniversalTime)
+        .filter(|&t| t > 0)
+        .map(|t| t / 10_000 - WINDOWS_EPOCH_OFFSET_MS);
+
+    let sample = MediaSample {
+        title,
+        artist,
+        album,
+        position_ms: (position.Duration / 10_000) as u64,
+        end_time_ms: (end_time.Duration / 10_000) as u64,
+        last_updated_ms,
+        is_playing,
+        album_pic_url,
+        playback_rate,
+        shuffle,
+        repeat_mode,
     };
 
-    // Correct the stale SMTC Position using LastUpdatedTime.
-    // SMTC.Position is a snapshot captured at SMTC.LastUpdatedTime.
-    // Real position = Position + (now - LastUpdatedTime) when playing.
-    let corrected_current_ms = if is_playing {
-        match timeline_properties.LastUpdatedTime() {
-            Ok(last_updated) => {
-                let last_updated_100ns = last_updated.UniversalTime;
-                if last_updated_100ns > 0 {
-                    // Convert current UTC to Windows FILETIME epoch (100-ns intervals since 1601-01-01)
-                    let now_unix_ms = chrono::Utc::now().timestamp_millis();
-                    // Offset between Windows FILETIME epoch and Unix epoch in milliseconds
-                    const WINDOWS_EPOCH_OFFSET_MS: i64 = 11_644_473_600_000;
-                    let now_windows_ms = now_unix_ms + WINDOWS_EPOCH_OFFSET_MS;
-                    let last_updated_ms = last_updated_100ns / 10_000; // 100-ns units to ms
-
-                    let elapsed_ms = (now_windows_ms - last_updated_ms).max(0) as u64;
-                    // Clamp elapsed to 5 seconds to avoid huge jumps from stale timestamps
-                    let clamped_elapsed = elapsed_ms.min(5000);
-                    let corrected = raw_current_ms + clamped_elapsed;
-
-                    // Clamp to total duration
-                    if total_time_ms > 0 { corrected.min(total_time_ms) } else { corrected }
-                } else {
-                    raw_current_ms
+    let now_ms = chrono::Utc::now().timestamp_millis();
+    let source_app = session_app_id(session);
+    let capture = capture::is_recording()
+        .then(|| CaptureEntry::from_sample(now_ms, &source_app, &sample, playback_status_name(playback_status)));
+
+    let mut info = song_info_from_sample(sample, now_ms);
+    info.source_app = source_app;
+    Ok(SessionRead { info, media_properties, capture })
+}
+
+// Helper to load the session thumbnail into the cover store in the background.
+// The reader's cached cover URL is updated when it finishes.
+fn load_thumbnail(media_properties: &GlobalSystemMediaTransportControlsSessionMediaProperties) {
+    let Ok(thumbnail_ref) = media_properties.Thumbnail() else {
+        return;
+    };
+    tokio::task::spawn_local(async move {
+        match read_thumbnail(thumbnail_ref).await {
+            Ok(cover_url) => {
+                if let Some(reader_mutex) = SMTC_READER.get() {
+                    let mut r = reader_mutex.lock().await;
+                    r.last_thumbnail_url = cover_url;
                 }
+                // 让下一轮重新读取，带上刚加载好的封面
+                mark_dirty();
+            }
+            Err(e) => {
+                eprintln!("[SMTC] Failed to read thumbnail: {:?}", e);
             }
-            Err(_) => raw_current_ms,
         }
-    } else {
-        raw_current_ms
-    };
-
-    let corrected_seconds = corrected_current_ms as f64 / 1000.0;
-    let progress = if total_seconds > 0.0 {
-        (corrected_seconds / total_seconds) * 100.0
-    } else {
-        0.0
-    };
+    });
+}
 
-    let server_ts = chrono::Utc::now().timestamp_millis() as u64;
+// Helper to map the SMTC AutoRepeatMode to the platform-neutral repeat mode.
+fn repeat_mode_from_smtc(mode: MediaPlaybackAutoRepeatMode) -> Option<RepeatMode> {
+    match mode {
+        MediaPlaybackAutoRepeatMode::None => Some(RepeatMode::None),
+        MediaPlaybackAutoRepeatMode::Track => Some(RepeatMode::Track),
+        MediaPlaybackAutoRepeatMode::List => Some(RepeatMode::List),
+        _ => None,
+    }
+}
 
-    Ok(SongInfo {
-        title,
-        artist,
-        album,
-        lyrics: String::new(),
-        trans: String::new(),
-        qrc_raw: String::new(),
-        qrc_data: Vec::new(),
-        current_time: corrected_seconds as u64,
-        total_time: total_seconds as u64,
-        current_time_ms: corrected_current_ms,
-        total_time_ms,
-        progress_percent: progress as f32,
-        is_playing,
-        album_pic_url,
-        server_ts,
-    })
+// Helper to name the raw SMTC playback status for capture files.
+fn playback_status_name(status: Option<GlobalSystemMediaTransportControlsSessionPlaybackStatus>) -> &'static str {
+    match status {
+        Some(GlobalSystemMediaTransportControlsSessionPlaybackStatus::Closed) => "Closed",
+        Some(GlobalSystemMediaTransportControlsSessionPlaybackStatus::Opened) => "Opened",
+        Some(GlobalSystemMediaTransportControlsSessionPlaybackStatus::Changing) => "Changing",
+        Some(GlobalSystemMediaTransportControlsSessionPlaybackStatus::Stopped) => "Stopped",
+        Some(GlobalSystemMediaTransportControlsSessionPlaybackStatus::Playing) => "Playing",
+        Some(GlobalSystemMediaTransportControlsSessionPlaybackStatus::Paused) => "Paused",
+        _ => "Unknown",
+    }
 }
 
-// Function to get current media info from Windows SMTC using cached session.
-pub async fn get_current_media_info() -> Result<Option<SongInfo>> {
+// Function to read the session picked by the session filter, and every session when `all_sessions` is set.
+pub async fn get_current_media_info(filter: &SessionFilter, all_sessions: bool) -> Result<SmtcSnapshot> {
     // Lazily initialize the global SMTC reader
     let reader_mutex = match SMTC_READER.get() {
         Some(m) => m,
         None => {
             let manager = GlobalSystemMediaTransportControlsSessionManager::RequestAsync()?.await?;
+            // 会话增减 / 系统当前会话切换：立即重新扫描
+            manager.CurrentSessionChanged(&TypedEventHandler::new(|_, _| {
+                mark_sessions_changed();
+                Ok(())
+            }))?;
+            manager.SessionsChanged(&TypedEventHandler::new(|_, _| {
+                mark_sessions_changed();
+                Ok(())
+            }))?;
             let inner = SmtcReaderInner {
                 manager,
                 current_session: None,
                 last_song_key: None,
-                last_thumbnail_base64: String::new(),
+                last_thumbnail_url: String::new(),
+                current_is_first_choice: false,
+                watched_sessions: Vec::new(),
             };
             let _ = SMTC_READER.set(Mutex::new(inner));
             SMTC_READER.get().unwrap()
@@ -171,68 +425,100 @@ pub async fn get_current_media_info() -> Result<Option<SongInfo>> {
     };
 
     let mut reader = reader_mutex.lock().await;
+    let sessions_changed = smtc_events().sessions_changed.swap(false, Ordering::SeqCst);
 
-    // 1. Try reading from the cached session first (do NOT fetch thumbnail initially to save CPU)
-    if let Some(session) = &reader.current_session {
-        if let Ok(mut info) = read_session_info(session, false).await {
-            let song_key = format!("{}|{}", info.title, info.artist);
-            if let Some(last_key) = &reader.last_song_key {
-                if song_key == *last_key {
-                    // Cache hit: same song and session is valid.
-                    // Populate with the cached thumbnail base64 and return immediately.
-                    info.album_pic_url = reader.last_thumbnail_base64.clone();
-                    return Ok(Some(info));
+    // 1. Fast path: the session list is unchanged and the preferred session still plays the
+    // same song, so only that session is read and the cached thumbnail is reused.
+    // On a miss the read is kept for the scan below instead of reading the session again.
+    // Multi-session monitoring needs every session, so it always takes the full scan.
+    let mut fast_read = None;
+    if !all_sessions && !sessions_changed && reader.current_is_first_choice {
+        if let Some(session) = reader.current_session.clone() {
+            if let Ok(read) = read_session_info(&session).await {
+                let song_key = format!("{}|{}|{}", read.info.source_app, read.info.title, read.info.artist);
+                if reader.last_song_key.as_deref() == Some(song_key.as_str()) {
+                    read.record();
+                    let mut info = read.info;
+                    info.album_pic_url = reader.last_thumbnail_url.clone();
+                    return Ok(SmtcSnapshot { active: Some(info), sessions: Vec::new() });
                 }
+                fast_read = Some((session, read));
             }
         }
-        // Cache miss or read error: clear the cache and fall back to full scan
-        reader.current_session = None;
-        reader.last_song_key = None;
-        reader.last_thumbnail_base64 = String::new();
     }
 
-    // 2. Scan all active sessions for QQ Music
-    let mut qqmusic_session = None;
-    if let Ok(sessions) = reader.manager.GetSessions() {
-        if let Ok(size) = sessions.Size() {
+    // 2. List sessions with their AppUserModelIds and locate the system current session
+    let mut sessions = Vec::new();
+    let mut app_ids = Vec::new();
+    if let Ok(list) = reader.manager.GetSessions() {
+        if let Ok(size) = list.Size() {
             for i in 0..size {
-                if let Ok(session) = sessions.GetAt(i) {
-                    if is_qqmusic_session(&session) {
-                        qqmusic_session = Some(session);
-                        break;
-                    }
+                if let Ok(session) = list.GetAt(i) {
+                    app_ids.push(session_app_id(&session));
+                    sessions.push(session);
                 }
             }
         }
     }
+    let current = reader
+        .manager
+        .GetCurrentSession()
+        .ok()
+        .map(|session| session_app_id(&session))
+        .and_then(|id| app_ids.iter().position(|app_id| *app_id == id));
+    watch_sessions(&mut reader, &sessions);
 
-    let mut qqmusic_found = false;
-    if let Some(session) = qqmusic_session {
-        qqmusic_found = true;
-        // Cache miss: must fetch thumbnail here exactly once
-        if let Ok(info) = read_session_info(&session, true).await {
-            reader.last_song_key = Some(format!("{}|{}", info.title, info.artist));
-            reader.last_thumbnail_base64 = info.album_pic_url.clone();
-            reader.current_session = Some(session);
-            return Ok(Some(info));
-        }
-    }
-
-    // If QQ Music session was found but read failed, do not downgrade to other media sources
-    if qqmusic_found {
-        return Ok(None);
+    // Every session is read once (multi-session monitoring); otherwise only candidates are read below
+    let mut reads: Vec<Option<SessionRead>> = Vec::with_capacity(sessions.len());
+    for session in &sessions {
+        let read = match &fast_read {
+            Some((fast_session, read)) if fast_session == session => Some(read.clone()),
+            _ if all_sessions => read_session_info(session).await.ok(),
+            _ => None,
+        };
+        reads.push(read);
     }
+    let snapshot_sessions = |reads: &[Option<SessionRead>]| -> Vec<SongInfo> {
+        if all_sessions {
+            reads.iter().flatten().map(|read| read.info.clone()).collect()
+        } else {
+            Vec::new()
+        }
+    };
 
-    // 3. Fallback: try default current session
-    if let Ok(session) = reader.manager.GetCurrentSession() {
-        // Cache miss: must fetch thumbnail here exactly once
-        if let Ok(info) = read_session_info(&session, true).await {
-            reader.last_song_key = Some(format!("{}|{}", info.title, info.artist));
-            reader.last_thumbnail_base64 = info.album_pic_url.clone();
-            reader.current_session = Some(session);
-            return Ok(Some(info));
+    // 3. Pick from candidates in filter order (allow-list priority, then policy fallback).
+    // When an allowed session exists only allowed sessions are candidates, so a failed
+    // read there never downgrades to browsers or other players.
+    for (rank, idx) in filter.candidates(&app_ids, current).into_iter().enumerate() {
+        let session = &sessions[idx];
+        let read = match &reads[idx] {
+            Some(read) => read.clone(),
+            None if all_sessions => continue,
+            None => match read_session_info(session).await {
+                Ok(read) => read,
+                Err(_) => continue,
+            },
+        };
+        read.record();
+        let mut info = read.info;
+        let song_key = format!("{}|{}|{}", app_ids[idx], info.title, info.artist);
+        if reader.last_song_key.as_deref() == Some(song_key.as_str()) {
+            // Same session and song: reuse the cached thumbnail URL
+            info.album_pic_url = reader.last_thumbnail_url.clone();
+        } else {
+            // Song or session changed: load the thumbnail exactly once, in the background
+            load_thumbnail(&read.media_properties);
+            reader.last_song_key = Some(song_key);
+            reader.last_thumbnail_url = String::new();
         }
+        reader.current_session = Some(session.clone());
+        reader.current_is_first_choice = rank == 0;
+        return Ok(SmtcSnapshot { active: Some(info), sessions: snapshot_sessions(&reads) });
     }
 
-    Ok(None)
+    reader.current_session = None;
+    reader.last_song_key = None;
+    reader.last_thumbnail_url = String::new();
+    reader.current_is_first_choice = false;
+    Ok(SmtcSnapshot { active: None, sessions: snapshot_sessions(&reads) })
 }
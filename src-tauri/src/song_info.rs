@@ -1,6 +1,6 @@
 use serde::{Serialize, Deserialize};
 
-#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Hash)]
 pub struct QrcLine {
     pub content: String,
     pub start_time_ms: u64,
@@ -8,13 +8,22 @@ pub struct QrcLine {
     pub words: Vec<QrcWord>,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Hash)]
 pub struct QrcWord {
     pub content: String,
     pub start_time_ms: u64, // Relative to line start
     pub duration_ms: u64,
 }
 
+/// 循环模式（SMTC AutoRepeatMode / MPRIS LoopStatus）
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum RepeatMode {
+    None,
+    Track,
+    List,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
 pub struct SongInfo {
     pub title: String,
@@ -33,6 +42,20 @@ pub struct SongInfo {
     pub is_playing: bool,         // 当前是否正在播放
     pub album_pic_url: String,    // 专辑封面图片地址
     pub server_ts: u64,           // 后端采样时的绝对时间戳（毫秒）
+    #[serde(default)]
+    pub source_app: String,       // 选中的会话来源（SMTC: AppUserModelId，MPRIS: 总线名）
+    #[serde(default)]
+    pub shuffle: Option<bool>,    // 随机播放；源不提供时为 None
+    #[serde(default)]
+    pub repeat_mode: Option<RepeatMode>, // 循环模式；源不提供时为 None
+    #[serde(default)]
+    pub playback_rate: Option<f64>, // 播放速率（1.0 为正常）；源不提供时为 None
+    #[serde(default)]
+    pub lyrics_confidence: Option<f32>, // 在线搜索命中歌曲与当前歌曲的匹配度（0~1）；本地歌词等非搜索来源为 None
+    #[serde(default)]
+    pub lyrics_offset_ms: i64,    // 本曲歌词偏移（毫秒，正值提前）；全局 smtc_offset_ms 不含在内
+    #[serde(default)]
+    pub lyric_time_ms: u64,       // 歌词时间（毫秒）= current_time_ms 按本曲偏移修正，消费方按它匹配歌词行，不必再加偏移
 }
 
 #[allow(dead_code)]
@@ -41,6 +64,17 @@ impl SongInfo {
     pub fn is_valid(&self) -> bool {
         !self.title.is_empty()
     }
+
+    /// 设置本曲歌词偏移，并据此修正歌词时间；`current_time_ms` 确定后调用
+    pub fn set_lyrics_offset(&mut self, offset_ms: i64) {
+        self.lyrics_offset_ms = offset_ms;
+        self.lyric_time_ms = crate::lyrics_offsets::apply_offset(self.current_time_ms, offset_ms);
+    }
+
+    /// 用于进度外推的播放速率：未知或异常值按 1.0 处理
+    pub fn rate(&self) -> f64 {
+        effective_rate(self.playback_rate)
+    }
     
     /// 格式化当前时间为 MM:SS 格式
     pub fn format_current_time(&self) -> String {
@@ -64,6 +98,19 @@ impl SongInfo {
     }
 }
 
+/// 有效播放速率：未知、非正数或非有限值按 1.0 处理
+pub fn effective_rate(rate: Option<f64>) -> f64 {
+    match rate {
+        Some(rate) if rate.is_finite() && rate > 0.0 => rate,
+        _ => 1.0,
+    }
+}
+
+/// 按播放速率缩放墙钟流逝时间，得到歌曲内容前进的时间
+pub fn scale_elapsed(elapsed_ms: u64, rate: f64) -> u64 {
+    (elapsed_ms as f64 * rate).round() as u64
+}
+
 /// 将秒数格式化为 MM:SS 格式
 #[allow(dead_code)]
 fn format_time(seconds: u64) -> String {
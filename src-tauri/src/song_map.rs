@@ -0,0 +1,103 @@
+use anyhow::{Context, Result};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+
+/// 按歌曲保存的用户设置（固定歌词、歌词偏移等）的映射文件：`{"title|artist": V}`，
+/// 修改后立即写回，用户也可以直接编辑
+pub struct SongMap<V> {
+    path: PathBuf,
+    entries: RwLock<BTreeMap<String, V>>,
+}
+
+pub fn song_key(title: &str, artist: &str) -> String {
+    format!("{}|{}", title, artist)
+}
+
+impl<V: Clone + Serialize + DeserializeOwned> SongMap<V> {
+    /// 读取映射文件；文件不存在时为空
+    pub fn open(path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+        let entries = match std::fs::read(&path) {
+            Ok(data) => serde_json::from_slice(&data).with_context(|| format!("映射文件格式错误 {}", path.display()))?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => BTreeMap::new(),
+            Err(e) => return Err(e).with_context(|| format!("无法读取映射文件 {}", path.display())),
+        };
+        Ok(Self { path, entries: RwLock::new(entries) })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn get(&self, title: &str, artist: &str) -> Option<V> {
+        self.entries.read().unwrap_or_else(|e| e.into_inner()).get(&song_key(title, artist)).cloned()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.read().unwrap_or_else(|e| e.into_inner()).len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// 设置（`Some`）或删除（`None`）并写回映射文件；写入失败时内存中的修改一并撤销
+    pub fn set(&self, title: &str, artist: &str, value: Option<V>) -> Result<()> {
+        let mut entries = self.entries.write().unwrap_or_else(|e| e.into_inner());
+        let key = song_key(title, artist);
+        let previous = match value {
+            Some(value) => entries.insert(key.clone(), value),
+            None => entries.remove(&key),
+        };
+        if let Err(e) = self.save(&entries) {
+            match previous {
+                Some(previous) => entries.insert(key, previous),
+                None => entries.remove(&key),
+            };
+            return Err(e);
+        }
+        Ok(())
+    }
+
+    fn save(&self, entries: &BTreeMap<String, V>) -> Result<()> {
+        if let Some(dir) = self.path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            std::fs::create_dir_all(dir)?;
+        }
+        let data = serde_json::to_vec_pretty(entries)?;
+        // 先写临时文件再改名，避免退出时留下半截 JSON
+        let tmp = self.path.with_extension("json.tmp");
+        std::fs::write(&tmp, data)?;
+        std::fs::rename(&tmp, &self.path).with_context(|| format!("无法写入映射文件 {}", self.path.display()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::temp_dir;
+
+    #[test]
+    fn test_round_trip() {
+        let dir = temp_dir("song_map_round_trip");
+        let path = dir.join("offsets.json");
+
+        let map: SongMap<i64> = SongMap::open(&path).unwrap();
+        assert!(map.is_empty());
+        map.set("那时雨", "徐良", Some(-300)).unwrap();
+        assert_eq!(map.get("那时雨", "徐良"), Some(-300));
+        assert_eq!(map.get("那时雨", "别人"), None);
+
+        // 重启后仍然生效；删除后写回
+        let map: SongMap<i64> = SongMap::open(&path).unwrap();
+        assert_eq!(map.get("那时雨", "徐良"), Some(-300));
+        map.set("那时雨", "徐良", None).unwrap();
+        assert_eq!(SongMap::<i64>::open(&path).unwrap().len(), 0);
+
+        std::fs::write(&path, b"not json").unwrap();
+        assert!(SongMap::<i64>::open(&path).is_err());
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
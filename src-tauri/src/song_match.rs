@@ -0,0 +1,232 @@
+use crate::song_info::SongInfo;
+
+const TITLE_WEIGHT: f32 = 0.4;
+const ARTIST_WEIGHT: f32 = 0.25;
+const DURATION_WEIGHT: f32 = 0.25;
+const ALBUM_WEIGHT: f32 = 0.1;
+/// 时长相差在此范围内记满分（本地缓存文件名中的时长只精确到秒，也按此视为同一版本）
+pub const DURATION_EXACT_MS: u64 = 2000;
+/// 时长相差超过此值记 0 分（现场版、混音版通常长出十几秒以上）
+const DURATION_MAX_MS: u64 = 15_000;
+
+/// 一次歌词查找的输入
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LyricsQuery {
+    pub title: String,
+    pub artist: String,
+    pub album: String,
+    /// 媒体源报告的总时长（毫秒），0 表示未知
+    pub duration_ms: u64,
+}
+
+impl LyricsQuery {
+    pub fn from_song(info: &SongInfo) -> Self {
+        Self {
+            title: info.title.clone(),
+            artist: info.artist.clone(),
+            album: info.album.clone(),
+            duration_ms: info.total_time_ms,
+        }
+    }
+}
+
+/// 归一化后的查询，对大量文件打分时只需 normalize 一次
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct NormalizedQuery {
+    pub title: String,
+    pub artist: String,
+    pub album: String,
+    pub duration_ms: u64,
+}
+
+impl NormalizedQuery {
+    pub fn new(query: &LyricsQuery) -> Self {
+        Self {
+            title: normalize(&query.title),
+            artist: normalize(&query.artist),
+            album: normalize(&query.album),
+            duration_ms: query.duration_ms,
+        }
+    }
+}
+
+/// 在线歌词源候选中参与打分的字段；缺失的字段留空（时长为 0）
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct SongFields<'a> {
+    pub title: &'a str,
+    pub artists: &'a [String],
+    pub album: &'a str,
+    pub duration_ms: u64,
+}
+
+/// 候选与查询的匹配度（0~1），作为歌词的置信度。
+///
+/// 标题、歌手、时长、专辑分别打分后加权：标题完全一致 1、去括号后缀后一致 0.8、互相包含 0.5；
+/// 歌手有交集 1；时长相差 2 秒内 1，到 15 秒线性降为 0；专辑一致 1、去括号后一致 0.8。
+/// 任一侧信息缺失（如媒体源未报告时长）时该项记 0.5，既不加分也不排除。
+pub fn score_candidate(candidate: &SongFields, query: &LyricsQuery) -> f32 {
+    let title = text_similarity(candidate.title, &query.title);
+
+    let wanted = normalize(&query.artist);
+    let artist = if wanted.is_empty() || candidate.artists.is_empty() {
+        0.5
+    } else if artists_overlap(candidate.artists, &wanted) {
+        1.0
+    } else {
+        0.0
+    };
+
+    let duration = if candidate.duration_ms == 0 || query.duration_ms == 0 {
+        0.5
+    } else {
+        let diff = candidate.duration_ms.abs_diff(query.duration_ms);
+        if diff <= DURATION_EXACT_MS {
+            1.0
+        } else {
+            1.0 - ((diff - DURATION_EXACT_MS) as f32 / (DURATION_MAX_MS - DURATION_EXACT_MS) as f32).min(1.0)
+        }
+    };
+
+    let album = if candidate.album.is_empty() || query.album.is_empty() {
+        0.5
+    } else {
+        match text_similarity(candidate.album, &query.album) {
+            s if s >= 0.8 => s,
+            _ => 0.0,
+        }
+    };
+
+    TITLE_WEIGHT * title + ARTIST_WEIGHT * artist + DURATION_WEIGHT * duration + ALBUM_WEIGHT * album
+}
+
+/// 标题归一化后完全一致，且查询有歌手时候选歌手与之有交集。
+/// 备用歌词源只采用同一首歌，"(伴奏)"、"(Live)" 等其他版本交给下一个歌词源。
+pub fn is_same_song(candidate: &SongFields, query: &LyricsQuery) -> bool {
+    let title = normalize(candidate.title);
+    if title.is_empty() || title != normalize(&query.title) {
+        return false;
+    }
+    let wanted = normalize(&query.artist);
+    wanted.is_empty() || artists_overlap(candidate.artists, &wanted)
+}
+
+/// 从候选中选出与查询是同一首歌（见 `is_same_song`）且 `score_candidate` 最高的一个；
+/// 同分时取先出现的（搜索结果的相关度顺序）
+pub fn pick_best<'a, T: 'a>(
+    items: impl IntoIterator<Item = &'a T>,
+    query: &LyricsQuery,
+    fields: impl Fn(&'a T) -> SongFields<'a>,
+) -> Option<&'a T> {
+    let mut best: Option<(&T, f32)> = None;
+    for item in items {
+        let candidate = fields(item);
+        if !is_same_song(&candidate, query) {
+            continue;
+        }
+        let score = score_candidate(&candidate, query);
+        let better = match best {
+            Some((_, best_score)) => score > best_score,
+            None => true,
+        };
+        if better {
+            best = Some((item, score));
+        }
+    }
+    best.map(|(item, _)| item)
+}
+
+/// `wanted` 已归一化
+fn artists_overlap(artists: &[String], wanted: &str) -> bool {
+    artists.iter().map(|a| normalize(a)).any(|a| !a.is_empty() && (wanted.contains(&a) || a.contains(wanted)))
+}
+
+// 1.0 exact (after normalize), 0.8 equal after stripping brackets/suffixes, 0.5 containment, else 0
+fn text_similarity(a: &str, b: &str) -> f32 {
+    let (na, nb) = (normalize(a), normalize(b));
+    if na.is_empty() || nb.is_empty() {
+        return 0.0;
+    }
+    if na == nb {
+        return 1.0;
+    }
+    if normalize(&clean_search_term(a)) == normalize(&clean_search_term(b)) {
+        return 0.8;
+    }
+    if na.contains(&nb) || nb.contains(&na) {
+        return 0.5;
+    }
+    0.0
+}
+
+// Helper to normalize strings for fuzzy comparison.
+//
+// 将所有非字母数字字符（含标点、括号、`_`/`/`/`\\`、`(feat.` 的点和括号等）
+// 折叠为单个空格，解决 SMTC 报告 `Go Again (feat. ELYSA)` 与本地缓存文件名
+// `Go Again (feat_ ELYSA)` 因标点差异导致 contains/equal 匹配双双失败的问题。
+// CJK 字符属于 `is_alphanumeric`，故中日韩标题不受影响。
+pub fn normalize(s: &str) -> String {
+    s.to_lowercase()
+        .chars()
+        .map(|c| if c.is_alphanumeric() { c } else { ' ' })
+        .collect::<String>()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+// Helper to clean search terms by removing parenthetical content and special chars
+pub fn clean_search_term(s: &str) -> String {
+    let mut result = s.to_string();
+    // Remove content in parentheses (both English and Chinese)
+    while let Some(start) = result.find('(') {
+        if let Some(end) = result[start..].find(')') {
+            result.replace_range(start..start + end + 1, "");
+        } else {
+            break;
+        }
+    }
+    while let Some(start) = result.find('（') {
+        if let Some(end) = result[start..].find('）') {
+            result.replace_range(start..start + end + '）'.len_utf8(), "");
+        } else {
+            break;
+        }
+    }
+    // Remove common suffixes
+    for suffix in &[" - Single", " (Explicit)", " (Remastered)", " (Deluxe)"] {
+        result = result.replace(suffix, "");
+    }
+    result.trim().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::song_query;
+
+
+    #[test]
+    fn test_same_song_and_pick_best() {
+        let xu = ["徐良".to_string()];
+        let others = ["群星".to_string()];
+        let song = |title, artists, album, duration_ms| SongFields { title, artists, album, duration_ms };
+        let candidates = [
+            song("那时雨 (伴奏)", &xu[..], "那时雨", 253_000),
+            song("那时雨", &others[..], "那时雨", 253_000),
+            song("那时雨", &xu[..], "现场", 281_000),
+            song("那时雨", &xu[..], "那时雨", 253_440),
+            song("那时雨", &xu[..], "那时雨", 253_000),
+        ];
+        assert!(!is_same_song(&candidates[0], &song_query(0)));
+        assert!(!is_same_song(&candidates[1], &song_query(0)));
+        assert!(is_same_song(&candidates[1], &LyricsQuery { artist: String::new(), ..song_query(0) }));
+
+        // 时长、专辑一致的优先；同分时取先出现的
+        assert_eq!(pick_best(&candidates, &song_query(253_000), |c| *c), Some(&candidates[3]));
+        assert_eq!(pick_best(&candidates[..3], &song_query(253_000), |c| *c), Some(&candidates[2]));
+        assert_eq!(pick_best(&candidates[..2], &song_query(253_000), |c| *c), None);
+
+        assert_eq!(text_similarity("Go Again (Explicit)", "go again"), 0.8);
+        assert_eq!(text_similarity("那时雨", "像风一样"), 0.0);
+    }
+}
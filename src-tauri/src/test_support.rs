@@ -0,0 +1,16 @@
+use crate::song_match::LyricsQuery;
+use std::path::PathBuf;
+
+/// 测试用的空临时目录：`name` 在所有测试中唯一（如 `lyrics_store_round_trip`），
+/// 目录名附带进程 id，已存在时先清空；测试结束时自行删除
+pub fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("{}_{}", name, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+/// 测试用的查询：徐良《那时雨》（专辑同名），`duration_ms` 为 0 表示时长未知
+pub fn song_query(duration_ms: u64) -> LyricsQuery {
+    LyricsQuery { title: "那时雨".to_string(), artist: "徐良".to_string(), album: "那时雨".to_string(), duration_ms }
+}